@@ -0,0 +1,22 @@
+//! Read arbitrary bytes as NMEA fields of every supported type. Run with:
+//!
+//! cargo +nightly fuzz run field_reader
+
+#![no_main]
+
+use ix_nmea::field_reader::FieldReader;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let mut reader = FieldReader::new(data, b',');
+
+    loop {
+        let _ = reader.read_optional::<f64>();
+        let _ = reader.read_optional::<char>();
+        let _ = reader.read::<u8>();
+        let _ = reader.read::<String>();
+        if !reader.skip() {
+            break;
+        }
+    }
+});
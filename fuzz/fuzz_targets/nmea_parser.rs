@@ -0,0 +1,24 @@
+//! Feed arbitrary bytes to the NMEA parser. Run with:
+//!
+//! cargo +nightly fuzz run nmea_parser
+
+#![no_main]
+
+use ix_nmea::Parser;
+use libfuzzer_sys::fuzz_target;
+
+/// Valid sentence used to check that the parser recovers from any input
+const SENTENCE: &[u8] = b"$GPVTG,,T,,M,0.00,N,0.00,K,N*2C\r\n";
+
+fuzz_target!(|data: &[u8]| {
+    let mut parser = Parser::new();
+    for b in data {
+        let _ = parser.push(*b);
+    }
+
+    let parsed = SENTENCE
+        .iter()
+        .filter_map(|b| parser.push(*b).ok().flatten())
+        .count();
+    assert_eq!(parsed, 1);
+});
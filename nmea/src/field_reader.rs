@@ -0,0 +1,437 @@
+use core::fmt;
+use core::fmt::Formatter;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReadResult {
+    Empty,
+    InvalidFormat,
+    InternalError,
+}
+
+impl fmt::Display for ReadResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadResult::Empty => f.write_str("Empty"),
+            ReadResult::InvalidFormat => f.write_str("Invalid format"),
+            ReadResult::InternalError => f.write_str("Internal parsing error"),
+        }
+    }
+}
+
+/// Reads separated fields in place from a byte slice
+pub struct FieldReader<'a> {
+    /// Remaining data, None once every field has been consumed
+    data: Option<&'a [u8]>,
+    sep: u8,
+    /// Total data length
+    size: usize,
+    /// Number of fields fetched so far
+    fetched: usize,
+    /// Last field fetched
+    last: &'a [u8],
+    /// Byte offset of the last field fetched
+    last_offset: usize,
+}
+
+impl<'a> FieldReader<'a> {
+    pub fn new(data: &'a [u8], separator: u8) -> FieldReader<'a> {
+        FieldReader {
+            data: if data.is_empty() { None } else { Some(data) },
+            sep: separator,
+            size: data.len(),
+            fetched: 0,
+            last: &[],
+            last_offset: 0,
+        }
+    }
+
+    /// Index of the last field fetched, 0 being the first one
+    pub fn index(&self) -> usize {
+        self.fetched.saturating_sub(1)
+    }
+
+    /// Byte offset of the last field fetched
+    pub fn offset(&self) -> usize {
+        self.last_offset
+    }
+
+    /// Raw bytes of the last field fetched
+    pub fn last(&self) -> &'a [u8] {
+        self.last
+    }
+
+    /// Skip next field
+    pub fn skip(&mut self) -> bool {
+        self.next_raw().is_some()
+    }
+
+    /// Read next value of the given type.
+    /// This method allows for empty values and returns Option::None in such a case
+    pub fn read_optional<T: core::str::FromStr>(&mut self) -> Result<Option<T>, ReadResult> {
+        match self.read() {
+            Ok(v) => Ok(Some(v)),
+            Err(e) => match e {
+                ReadResult::Empty => Ok(None),
+                _ => Err(e),
+            },
+        }
+    }
+
+    /// Read next value of the given type.
+    /// This method assumes that and empty value is not allowed hence an error
+    /// ReadResult::Empty is returned in such a case
+    pub fn read<T: core::str::FromStr>(&mut self) -> Result<T, ReadResult> {
+        let data_str = self.next()?;
+
+        if let Ok(value) = data_str.parse::<T>() {
+            Ok(value)
+        } else {
+            Err(ReadResult::InvalidFormat)
+        }
+    }
+
+    /// Fetch the next non empty field up to separator
+    fn next(&mut self) -> Result<&'a str, ReadResult> {
+        match self.next_raw() {
+            None => Err(ReadResult::Empty),
+            Some([]) => Err(ReadResult::Empty),
+            Some(field) => core::str::from_utf8(field).map_err(|_| ReadResult::InternalError),
+        }
+    }
+
+    /// Fetch the next field's bytes, without the separator
+    fn next_raw(&mut self) -> Option<&'a [u8]> {
+        self.fetched += 1;
+        self.last = &[];
+
+        let data = match self.data {
+            Some(data) => data,
+            None => {
+                self.last_offset = self.size;
+                return None;
+            }
+        };
+
+        self.last_offset = self.size - data.len();
+        self.last = match data.iter().position(|b| *b == self.sep) {
+            Some(i) => {
+                self.data = Some(&data[i + 1..]);
+                &data[..i]
+            }
+            None => {
+                self.data = None;
+                data
+            }
+        };
+
+        Some(self.last)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::String;
+
+    #[test]
+    /// Given an empty string guarantee that we receive a ReadResult::Empty
+    pub fn empty_str() {
+        let mut reader = FieldReader::new("".as_bytes(), b',');
+        let ret = reader.read::<i32>();
+
+        assert!(ret.is_err());
+        assert!(matches!(ret.err().unwrap(), ReadResult::Empty));
+    }
+
+    #[test]
+    /// Try to read a i32 value from something that can't be parsed as one
+    /// Make sure we receive a ReadResult::InvalidFormat
+    pub fn invalid_format() {
+        let mut reader = FieldReader::new("#".as_bytes(), b',');
+        let ret = reader.read::<i32>();
+
+        assert!(ret.is_err());
+        assert!(matches!(ret.err().unwrap(), ReadResult::InvalidFormat));
+    }
+
+    #[test]
+    /// Parse a value from a string with no separator
+    /// Expect the value to be properly parsed and a ReadResult::Empty to be
+    /// returned when trying to read a new value
+    pub fn single_argument() {
+        let mut reader = FieldReader::new("10".as_bytes(), b',');
+        let mut ret = reader.read::<i32>();
+
+        assert!(ret.is_ok());
+        assert_eq!(ret.unwrap(), 10);
+
+        ret = reader.read::<i32>();
+        assert!(ret.is_err());
+        assert!(matches!(ret.err().unwrap(), ReadResult::Empty));
+    }
+
+    #[test]
+    /// Parse multiple values until end of string
+    /// Expect all values to be parsed correctly and ReadResult::Empty to be
+    /// return when reaching EOS
+    pub fn to_completion() {
+        let mut reader = FieldReader::new("10,2,1001,50".as_bytes(), b',');
+        let mut ret = reader.read::<i32>();
+
+        assert!(ret.is_ok());
+        assert_eq!(ret.unwrap(), 10);
+
+        ret = reader.read::<i32>();
+
+        assert!(ret.is_ok());
+        assert_eq!(ret.unwrap(), 2);
+
+        ret = reader.read::<i32>();
+
+        assert!(ret.is_ok());
+        assert_eq!(ret.unwrap(), 1001);
+
+        ret = reader.read::<i32>();
+
+        assert!(ret.is_ok());
+        assert_eq!(ret.unwrap(), 50);
+    }
+
+    #[test]
+    /// Parse multiple f64 value among other types
+    pub fn with_floating_point_f64() {
+        let mut reader = FieldReader::new("10.2,2,1001.999,50".as_bytes(), b',');
+        let ret = reader.read::<f64>();
+
+        assert!(ret.is_ok());
+        assert_eq!(ret.unwrap(), 10.2);
+
+        let ret2 = reader.read::<i32>();
+
+        assert!(ret2.is_ok());
+        assert_eq!(ret2.unwrap(), 2);
+
+        let ret3 = reader.read::<f64>();
+
+        assert!(ret3.is_ok());
+        assert_eq!(ret3.unwrap(), 1001.999);
+
+        let ret4 = reader.read::<i32>();
+
+        assert!(ret4.is_ok());
+        assert_eq!(ret4.unwrap(), 50);
+    }
+
+    #[test]
+    /// Parser multiple f32 values among other types
+    pub fn with_floating_point_f32() {
+        let mut reader = FieldReader::new("10.2,2,1001.999,50".as_bytes(), b',');
+        let ret = reader.read::<f32>();
+
+        assert!(ret.is_ok());
+        assert_eq!(ret.unwrap(), 10.2);
+
+        let ret2 = reader.read::<i32>();
+
+        assert!(ret2.is_ok());
+        assert_eq!(ret2.unwrap(), 2);
+
+        let ret3 = reader.read::<f32>();
+
+        assert!(ret3.is_ok());
+        assert_eq!(ret3.unwrap(), 1001.999);
+
+        let ret4 = reader.read::<i32>();
+
+        assert!(ret4.is_ok());
+        assert_eq!(ret4.unwrap(), 50);
+    }
+
+    #[test]
+    /// Parse multiple value types such as string, floating point and integer
+    pub fn mixed() {
+        let mut reader = FieldReader::new("10.2,2,ABCD,0.22,#aa".as_bytes(), b',');
+
+        let ret = reader.read::<f64>();
+        assert!(ret.is_ok());
+        assert_eq!(ret.unwrap(), 10.2);
+
+        let ret2 = reader.read::<i32>();
+        assert!(ret2.is_ok());
+        assert_eq!(ret2.unwrap(), 2);
+
+        let ret3 = reader.read::<String>();
+        assert!(ret3.is_ok());
+        assert_eq!(ret3.unwrap(), "ABCD");
+
+        let ret4 = reader.read::<f64>();
+        assert!(ret4.is_ok());
+        assert_eq!(ret4.unwrap(), 0.22);
+
+        let ret5 = reader.read::<String>();
+        assert!(ret5.is_ok());
+        assert_eq!(ret5.unwrap(), "#aa");
+    }
+
+    #[test]
+    /// Parse an integer into a floating point type
+    pub fn integer_as_floating() {
+        let mut reader = FieldReader::new("10".as_bytes(), b',');
+
+        let ret = reader.read::<f64>();
+        assert!(ret.is_ok());
+        assert_eq!(ret.unwrap(), 10.0);
+    }
+
+    #[test]
+    /// Parse a floating point type as integer and expect a failing test with
+    /// return ReadResult::InvalidFormat
+    pub fn floating_as_integer() {
+        let mut reader = FieldReader::new("12.02".as_bytes(), b',');
+
+        let ret = reader.read::<i32>();
+        assert!(ret.is_err());
+        assert!(matches!(ret.err().unwrap(), ReadResult::InvalidFormat));
+    }
+
+    #[test]
+    /// Skip value
+    /// Expect all values to be parsed correctly except skipped one
+    pub fn skip() {
+        let mut reader = FieldReader::new("10,2,1001,50".as_bytes(), b',');
+        let mut ret = reader.read::<i32>();
+
+        assert!(ret.is_ok());
+        assert_eq!(ret.unwrap(), 10);
+
+        assert!(reader.skip());
+
+        ret = reader.read::<i32>();
+
+        assert!(ret.is_ok());
+        assert_eq!(ret.unwrap(), 1001);
+
+        ret = reader.read::<i32>();
+
+        assert!(ret.is_ok());
+        assert_eq!(ret.unwrap(), 50);
+    }
+
+    #[test]
+    /// Skip all fields
+    /// Expect true on all calls until no more fields to skip
+    pub fn skip_all() {
+        let mut reader = FieldReader::new("10,2,1001,50".as_bytes(), b',');
+
+        assert!(reader.skip());
+        assert!(reader.skip());
+        assert!(reader.skip());
+        assert!(reader.skip());
+
+        // nothing more to skip
+        assert!(!reader.skip());
+    }
+
+    #[test]
+    /// Empty fields between separators are reported as ReadResult::Empty
+    /// and read as None when optional
+    pub fn empty_fields() {
+        let mut reader = FieldReader::new("1,,,2,".as_bytes(), b',');
+
+        assert_eq!(reader.read::<i32>().unwrap(), 1);
+        assert!(matches!(reader.read::<i32>(), Err(ReadResult::Empty)));
+        assert_eq!(reader.read_optional::<f32>().unwrap(), None);
+        assert_eq!(reader.read_optional::<i32>().unwrap(), Some(2));
+
+        // trailing empty field
+        assert!(reader.skip());
+        assert!(!reader.skip());
+    }
+
+    #[test]
+    /// Single character fields
+    pub fn characters() {
+        let mut reader = FieldReader::new("N,W".as_bytes(), b',');
+
+        assert_eq!(reader.read::<char>().unwrap(), 'N');
+        assert_eq!(reader.read_optional::<char>().unwrap(), Some('W'));
+    }
+
+    #[test]
+    /// Position of the last field is tracked for error reporting
+    pub fn position() {
+        let mut reader = FieldReader::new("10,ab,,3".as_bytes(), b',');
+
+        assert!(reader.read::<i32>().is_ok());
+        assert_eq!(reader.index(), 0);
+        assert_eq!(reader.offset(), 0);
+
+        assert!(reader.read::<i32>().is_err());
+        assert_eq!(reader.index(), 1);
+        assert_eq!(reader.offset(), 3);
+        assert_eq!(reader.last(), "ab".as_bytes());
+
+        assert!(reader.skip());
+        assert!(reader.skip());
+        assert_eq!(reader.index(), 3);
+        assert_eq!(reader.offset(), 7);
+
+        assert!(!reader.skip());
+        assert_eq!(reader.offset(), 8);
+        assert!(reader.last().is_empty());
+    }
+}
+
+#[cfg(test)]
+mod proptests {
+    use alloc::string::{String, ToString};
+    use alloc::vec::Vec;
+    use proptest::prelude::*;
+
+    use super::*;
+
+    proptest! {
+        #[test]
+        /// Joined fields are read back one by one, empty ones as None
+        fn round_trip(fields in prop::collection::vec("[^,]{0,12}", 1..16)) {
+            let data = fields.join(",");
+            let mut reader = FieldReader::new(data.as_bytes(), b',');
+
+            for field in &fields {
+                let expected = if field.is_empty() { None } else { Some(field.clone()) };
+                prop_assert_eq!(reader.read_optional::<String>().unwrap(), expected);
+            }
+
+            prop_assert!(!reader.skip());
+        }
+
+        #[test]
+        /// Numbers are parsed from their textual representation
+        fn numbers(values in prop::collection::vec(any::<f64>(), 1..16)) {
+            let data: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+            let data = data.join(",");
+            let mut reader = FieldReader::new(data.as_bytes(), b',');
+
+            for v in &values {
+                let read = reader.read::<f64>().unwrap();
+                prop_assert!(read == *v || (read.is_nan() && v.is_nan()));
+            }
+        }
+
+        #[test]
+        /// Any byte sequence is handled without panicking
+        fn never_panics(data in prop::collection::vec(any::<u8>(), 0..256)) {
+            let mut reader = FieldReader::new(&data, b',');
+
+            loop {
+                let _ = reader.read_optional::<f32>();
+                let _ = reader.read::<char>();
+                let _ = reader.read_optional::<String>();
+                let _ = reader.read::<u8>();
+                if !reader.skip() {
+                    break;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,17 @@
+//! NMEA 0183 parsing and encoding. Only depends on `core` and `alloc` so that
+//! the same code runs on the ix drivers and on microcontroller firmware.
+
+#![no_std]
+
+extern crate alloc;
+#[cfg(test)]
+extern crate std;
+
+pub mod field_reader;
+mod parser;
+pub mod registry;
+pub mod sentences;
+
+pub use parser::{
+    Builder, Error, ErrorKind, Parser, Sentence, SentenceId, Talker, MAX_ID_LEN, MAX_SENTENCE_LEN,
+};
@@ -0,0 +1,1544 @@
+use alloc::format;
+use alloc::string::String;
+use core::fmt;
+use core::fmt::Formatter;
+
+use crate::field_reader::ReadResult;
+use crate::registry::{DataCustom, Registry};
+use crate::sentences::*;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Sentence {
+    Invalid,
+    /// Global Positioning System Fix Data
+    GGA(DataGGA),
+    /// GNSS Fix Data
+    GNS(DataGNS),
+    /// Course over Ground and Ground Speed
+    VTG(DataVTG),
+    /// Recommended Minimum Specific GNSS Data
+    RMC(DataRMC),
+    /// Time and Date
+    ZDA(DataZDA),
+    /// GNSS Pseudorange Error Statistics
+    GST(DataGST),
+    /// Heading, True
+    HDT(DataHDT),
+    /// Heading, Deviation and Variation
+    HDG(DataHDG),
+    /// True Heading and Status
+    THS(DataTHS),
+    /// Depth Below Transducer
+    DBT(DataDBT),
+    /// Depth
+    DPT(DataDPT),
+    /// Wind Speed and Angle
+    MWV(DataMWV),
+    /// Wind Direction and Speed
+    MWD(DataMWD),
+    /// Water Temperature
+    MTW(DataMTW),
+    /// Water Speed and Heading
+    VHW(DataVHW),
+    /// AIS VHF Data-link Message (other vessels)
+    VDM(DataVDM),
+    /// AIS VHF Data-link Own-vessel report
+    VDO(DataVDM),
+    /// Sentence parsed by a registered definition
+    Custom(DataCustom),
+}
+
+/// Talker identifier, i.e., the first two characters of the sentence address
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Talker {
+    /// GPS
+    GP,
+    /// GLONASS
+    GL,
+    /// Galileo
+    GA,
+    /// BeiDou
+    GB,
+    /// QZSS
+    GQ,
+    /// NavIC (IRNSS)
+    GI,
+    /// Combined multi-constellation solution
+    GN,
+    /// AIS transponder
+    AI,
+    /// Unrecognized or missing talker
+    #[default]
+    Unknown,
+}
+
+impl Talker {
+    /// Extract the talker from a sentence address such as "GPGGA"
+    pub fn from(address: &str) -> Talker {
+        if address.len() != 5 {
+            return Talker::Unknown;
+        }
+
+        match &address.as_bytes()[..2] {
+            b"GP" => Talker::GP,
+            b"GL" => Talker::GL,
+            b"GA" => Talker::GA,
+            // legacy BeiDou talker
+            b"GB" | b"BD" => Talker::GB,
+            b"GQ" => Talker::GQ,
+            b"GI" => Talker::GI,
+            b"GN" => Talker::GN,
+            b"AI" => Talker::AI,
+            _ => Talker::Unknown,
+        }
+    }
+
+    /// Whether this talker reports a solution combining several constellations
+    pub fn is_combined(&self) -> bool {
+        *self == Talker::GN
+    }
+}
+
+impl Sentence {
+    pub fn from(s: &str) -> Sentence {
+        let talker = Talker::from(s);
+
+        if s.ends_with("GGA") {
+            return Sentence::GGA(DataGGA {
+                talker,
+                ..Default::default()
+            });
+        } else if s.ends_with("GNS") {
+            return Sentence::GNS(DataGNS {
+                talker,
+                ..Default::default()
+            });
+        } else if s.ends_with("VTG") {
+            return Sentence::VTG(DataVTG {
+                talker,
+                ..Default::default()
+            });
+        } else if s.ends_with("RMC") {
+            return Sentence::RMC(DataRMC {
+                talker,
+                ..Default::default()
+            });
+        } else if s.ends_with("GST") {
+            return Sentence::GST(DataGST {
+                talker,
+                ..Default::default()
+            });
+        } else if s.ends_with("ZDA") {
+            return Sentence::ZDA(DataZDA {
+                talker,
+                ..Default::default()
+            });
+        } else if s.ends_with("HDT") {
+            return Sentence::HDT(DataHDT {
+                talker,
+                ..Default::default()
+            });
+        } else if s.ends_with("HDG") {
+            return Sentence::HDG(DataHDG {
+                talker,
+                ..Default::default()
+            });
+        } else if s.ends_with("THS") {
+            return Sentence::THS(DataTHS {
+                talker,
+                ..Default::default()
+            });
+        } else if s.ends_with("DBT") {
+            return Sentence::DBT(DataDBT {
+                talker,
+                ..Default::default()
+            });
+        } else if s.ends_with("DPT") {
+            return Sentence::DPT(DataDPT {
+                talker,
+                ..Default::default()
+            });
+        } else if s.ends_with("MWV") {
+            return Sentence::MWV(DataMWV {
+                talker,
+                ..Default::default()
+            });
+        } else if s.ends_with("MWD") {
+            return Sentence::MWD(DataMWD {
+                talker,
+                ..Default::default()
+            });
+        } else if s.ends_with("MTW") {
+            return Sentence::MTW(DataMTW {
+                talker,
+                ..Default::default()
+            });
+        } else if s.ends_with("VHW") {
+            return Sentence::VHW(DataVHW {
+                talker,
+                ..Default::default()
+            });
+        } else if s.ends_with("VDM") {
+            return Sentence::VDM(DataVDM {
+                talker,
+                ..Default::default()
+            });
+        } else if s.ends_with("VDO") {
+            return Sentence::VDO(DataVDM {
+                talker,
+                ..Default::default()
+            });
+        }
+
+        Sentence::Invalid
+    }
+
+    /// Talker that emitted this sentence
+    pub fn talker(&self) -> Talker {
+        match self {
+            Sentence::Invalid => Talker::Unknown,
+            Sentence::GGA(m) => m.talker,
+            Sentence::GNS(m) => m.talker,
+            Sentence::VTG(m) => m.talker,
+            Sentence::RMC(m) => m.talker,
+            Sentence::ZDA(m) => m.talker,
+            Sentence::GST(m) => m.talker,
+            Sentence::HDT(m) => m.talker,
+            Sentence::HDG(m) => m.talker,
+            Sentence::THS(m) => m.talker,
+            Sentence::DBT(m) => m.talker,
+            Sentence::DPT(m) => m.talker,
+            Sentence::MWV(m) => m.talker,
+            Sentence::MWD(m) => m.talker,
+            Sentence::MTW(m) => m.talker,
+            Sentence::VHW(m) => m.talker,
+            Sentence::VDM(m) => m.talker,
+            Sentence::VDO(m) => m.talker,
+            Sentence::Custom(m) => m.talker,
+        }
+    }
+}
+
+/// Maximum length of a sentence address, e.g. "GPGGA" or "PSTMPV"
+pub const MAX_ID_LEN: usize = 8;
+/// Maximum length of the sentence data handled by the parser. NMEA 0183
+/// limits sentences to 82 characters but several receivers exceed it
+pub const MAX_SENTENCE_LEN: usize = 128;
+
+/// Sentence address stored inline, without allocations
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub struct SentenceId {
+    bytes: [u8; MAX_ID_LEN],
+    len: usize,
+}
+
+impl SentenceId {
+    /// Build an id from the given bytes, truncated to MAX_ID_LEN
+    pub fn new(id: &[u8]) -> SentenceId {
+        let len = id.len().min(MAX_ID_LEN);
+        let mut bytes = [0u8; MAX_ID_LEN];
+        bytes[..len].copy_from_slice(&id[..len]);
+
+        SentenceId { bytes, len }
+    }
+
+    pub fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.bytes[..self.len]).unwrap_or("")
+    }
+}
+
+impl fmt::Debug for SentenceId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", String::from_utf8_lossy(&self.bytes[..self.len]))
+    }
+}
+
+impl fmt::Display for SentenceId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&String::from_utf8_lossy(&self.bytes[..self.len]))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ErrorKind {
+    /// Invalid sync byte (should be '$' or '!')
+    InvalidSync(u8),
+    /// Unknown or malformed sentence ID
+    InvalidId,
+    /// A data field could not be parsed
+    InvalidField(ReadResult),
+    /// Sentence longer than MAX_SENTENCE_LEN
+    Overflow,
+    /// A new sentence started before the current one was complete
+    Interrupted,
+    /// Checksum is not a two digit hexadecimal number
+    InvalidChecksum,
+    /// Checksum mismatch between parser and sentence
+    ChecksumMismatch { expected: u8, received: u8 },
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::InvalidSync(b) => write!(f, "invalid sync byte 0x{:02X}", b),
+            ErrorKind::InvalidId => f.write_str("unsupported sentence id"),
+            ErrorKind::InvalidField(e) => write!(f, "invalid field ({})", e),
+            ErrorKind::Overflow => f.write_str("sentence too long"),
+            ErrorKind::Interrupted => f.write_str("sentence interrupted"),
+            ErrorKind::InvalidChecksum => f.write_str("invalid checksum"),
+            ErrorKind::ChecksumMismatch { expected, received } => write!(
+                f,
+                "checksum mismatch: expected {:02X}, received {:02X}",
+                expected, received
+            ),
+        }
+    }
+}
+
+/// Parsing error with the location of the offending data
+#[derive(Clone, Debug, PartialEq)]
+pub struct Error {
+    pub kind: ErrorKind,
+    /// Sentence address, if already known
+    pub id: Option<SentenceId>,
+    /// Index (0 being the first data field) and name of the offending field
+    pub field: Option<(usize, &'static str)>,
+    /// Offending raw text
+    pub text: String,
+    /// Position of the offending text in the sentence, the start delimiter
+    /// being at 0
+    pub position: usize,
+}
+
+impl Error {
+    fn new(kind: ErrorKind, id: Option<SentenceId>, position: usize) -> Error {
+        Error {
+            kind,
+            id,
+            field: None,
+            text: String::new(),
+            position,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some(id) = &self.id {
+            write!(f, "{}: ", id)?;
+        }
+
+        write!(f, "{}", self.kind)?;
+
+        if let Some((index, name)) = self.field {
+            write!(f, " in field {} ({})", index, name)?;
+        }
+
+        if !self.text.is_empty() {
+            write!(f, " \"{}\"", self.text)?;
+        }
+
+        write!(f, " at byte {}", self.position)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Field {
+    Sync,
+    Id,
+    Data,
+    Checksum,
+}
+
+pub struct Parser {
+    /// Current NMEA field being parsed
+    field: Field,
+    /// Which sentence we're parsing
+    sntc: Sentence,
+    /// Address of the sentence being parsed
+    id: Option<SentenceId>,
+    /// Work buffer
+    bfr: [u8; MAX_SENTENCE_LEN],
+    /// Number of bytes in the work buffer
+    len: usize,
+    /// Position of the next byte in the sentence
+    pos: usize,
+    /// Current checksum value
+    checksum: u8,
+    /// Checksum read from sentence
+    read_checksum: u8,
+    /// Definitions of sentences not built into the parser
+    registry: Registry,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Parser {
+    pub fn new() -> Parser {
+        Parser {
+            field: Field::Sync,
+            sntc: Sentence::Invalid,
+            id: None,
+            bfr: [0; MAX_SENTENCE_LEN],
+            len: 0,
+            pos: 0,
+            checksum: 0,
+            read_checksum: 0,
+            registry: Registry::new(),
+        }
+    }
+
+    /// Definitions consulted before the built-in sentences
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    pub fn registry_mut(&mut self) -> &mut Registry {
+        &mut self.registry
+    }
+
+    pub fn reset(&mut self) {
+        self.field = Field::Sync;
+        self.sntc = Sentence::Invalid;
+        self.id = None;
+        self.len = 0;
+        self.pos = 0;
+        self.checksum = 0;
+        self.read_checksum = 0;
+    }
+
+    pub fn fail_with(&mut self, e: Error) -> Result<Option<Sentence>, Error> {
+        self.reset();
+        Err(e)
+    }
+
+    /// Append a byte to the work buffer
+    fn store(&mut self, b: u8) -> bool {
+        if self.len == MAX_SENTENCE_LEN {
+            return false;
+        }
+
+        self.bfr[self.len] = b;
+        self.len += 1;
+        true
+    }
+
+    /// Feed the next byte. Returns the sentence once it is complete and
+    /// valid, None while parsing is on going
+    pub fn push(&mut self, b: u8) -> Result<Option<Sentence>, Error> {
+        let position = self.pos;
+        self.pos += 1;
+
+        // start delimiters are reserved: resynchronize on them
+        if utils::is_start(b) && self.field != Field::Sync {
+            let e = Error::new(ErrorKind::Interrupted, self.id, position);
+            self.reset();
+            self.field = Field::Id;
+            self.pos = 1;
+
+            return Err(e);
+        }
+
+        match self.field {
+            Field::Sync => {
+                if utils::is_start(b) {
+                    self.reset();
+
+                    self.field = Field::Id;
+                    self.pos = 1;
+                } else {
+                    return self.fail_with(Error::new(ErrorKind::InvalidSync(b), None, 0));
+                }
+            }
+            Field::Id => {
+                self.checksum = utils::xor(self.checksum, b);
+                if b == b',' {
+                    let id = SentenceId::new(&self.bfr[..self.len]);
+                    self.sntc = self
+                        .registry
+                        .sentence(&id)
+                        .unwrap_or_else(|| Sentence::from(id.as_str()));
+                    if self.sntc == Sentence::Invalid {
+                        return self.fail_with(Error::new(ErrorKind::InvalidId, Some(id), 1));
+                    } else {
+                        self.id = Some(id);
+                        self.field = Field::Data;
+                        self.len = 0;
+                    }
+                } else if self.len == MAX_ID_LEN {
+                    let id = SentenceId::new(&self.bfr[..self.len]);
+                    return self.fail_with(Error::new(ErrorKind::InvalidId, Some(id), 1));
+                } else {
+                    self.store(b);
+                }
+            }
+            Field::Data => {
+                if b == b'*' {
+                    let fields = &self.bfr[..self.len];
+                    let parsed = match &mut self.sntc {
+                        Sentence::Custom(m) => self.registry.parse_fields(m, fields),
+                        sentence => parse_fields(sentence, fields),
+                    };
+
+                    if let Err(e) = parsed {
+                        // data starts right after the address separator
+                        let start = position - self.len;
+                        return self.fail_with(Error {
+                            kind: ErrorKind::InvalidField(e.reason),
+                            id: self.id,
+                            field: Some((e.index, e.name)),
+                            text: e.text,
+                            position: start + e.offset,
+                        });
+                    }
+
+                    self.field = Field::Checksum;
+                    self.len = 0;
+                } else {
+                    if !self.store(b) {
+                        return self.fail_with(Error::new(ErrorKind::Overflow, self.id, position));
+                    }
+                    self.checksum = utils::xor(self.checksum, b);
+                }
+            }
+            Field::Checksum => {
+                self.store(b);
+
+                if self.len == 2 {
+                    let start = position - 1;
+                    let hex = &self.bfr[..2];
+
+                    self.read_checksum = match utils::parse_checksum(hex) {
+                        Some(checksum) => checksum,
+                        None => {
+                            let mut e = Error::new(ErrorKind::InvalidChecksum, self.id, start);
+                            e.text = String::from_utf8_lossy(hex).into_owned();
+                            return self.fail_with(e);
+                        }
+                    };
+
+                    return if self.checksum == self.read_checksum {
+                        self.field = Field::Sync;
+                        self.pos = 0;
+                        Ok(Some(core::mem::replace(&mut self.sntc, Sentence::Invalid)))
+                    } else {
+                        let kind = ErrorKind::ChecksumMismatch {
+                            expected: self.checksum,
+                            received: self.read_checksum,
+                        };
+                        self.fail_with(Error::new(kind, self.id, start))
+                    };
+                }
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Sentence encoder. Fields are appended in order and the checksum and line
+/// terminator are added on build, e.g.:
+///
+/// Builder::new("GPHDT").fixed(274.07, 2).field('T').build()
+pub struct Builder {
+    /// Sentence being built, starting with '$'
+    bfr: String,
+}
+
+impl Builder {
+    /// Start a sentence with the given address, e.g. "GPGGA"
+    pub fn new(address: &str) -> Builder {
+        let mut bfr = String::with_capacity(MAX_SENTENCE_LEN);
+        bfr.push('$');
+        bfr.push_str(address);
+
+        Builder { bfr }
+    }
+
+    /// Append a field
+    pub fn field<T: fmt::Display>(mut self, value: T) -> Builder {
+        use core::fmt::Write;
+
+        self.bfr.push(',');
+        // writing to a String does not fail
+        let _ = write!(self.bfr, "{}", value);
+        self
+    }
+
+    /// Append a field, left empty if there is no value
+    pub fn optional<T: fmt::Display>(self, value: Option<T>) -> Builder {
+        match value {
+            Some(v) => self.field(v),
+            None => self.empty(),
+        }
+    }
+
+    /// Append an empty field
+    pub fn empty(mut self) -> Builder {
+        self.bfr.push(',');
+        self
+    }
+
+    /// Append a number with the given number of decimal places
+    pub fn fixed(self, value: f64, decimals: usize) -> Builder {
+        self.field(format_args!("{:.*}", decimals, value))
+    }
+
+    /// Append a number with the given number of decimal places, left empty
+    /// if there is no value
+    pub fn optional_fixed(self, value: Option<f64>, decimals: usize) -> Builder {
+        match value {
+            Some(v) => self.fixed(v, decimals),
+            None => self.empty(),
+        }
+    }
+
+    /// Append latitude in degrees as the "ddmm.mmmmm" and N/S fields
+    pub fn latitude(self, degrees: Option<f64>) -> Builder {
+        match degrees {
+            Some(d) => {
+                let (value, hemisphere) = if d < 0.0 { (-d, 'S') } else { (d, 'N') };
+                self.field(degrees_minutes(value, 2)).field(hemisphere)
+            }
+            None => self.empty().empty(),
+        }
+    }
+
+    /// Append longitude in degrees as the "dddmm.mmmmm" and E/W fields
+    pub fn longitude(self, degrees: Option<f64>) -> Builder {
+        match degrees {
+            Some(d) => {
+                let (value, hemisphere) = if d < 0.0 { (-d, 'W') } else { (d, 'E') };
+                self.field(degrees_minutes(value, 3)).field(hemisphere)
+            }
+            None => self.empty().empty(),
+        }
+    }
+
+    /// Append UTC time of day given in seconds as "hhmmss.ss"
+    pub fn time(self, seconds: Option<f64>) -> Builder {
+        match seconds {
+            Some(s) => {
+                // round first so that 59.999 does not show up as 60.00
+                let centis = round(s * 100.0) % (24 * 3600 * 100);
+                let (h, m, c) = (centis / 360_000, centis / 6000 % 60, centis % 6000);
+                self.field(format_args!(
+                    "{:02}{:02}{:02}.{:02}",
+                    h,
+                    m,
+                    c / 100,
+                    c % 100
+                ))
+            }
+            None => self.empty(),
+        }
+    }
+
+    /// Append date as "ddmmyy"
+    pub fn date(self, date: Option<(u8, u8, u16)>) -> Builder {
+        match date {
+            Some((day, month, year)) => {
+                self.field(format_args!("{:02}{:02}{:02}", day, month, year % 100))
+            }
+            None => self.empty(),
+        }
+    }
+
+    /// Finish the sentence with its checksum and line terminator
+    pub fn build(self) -> String {
+        let checksum = self.bfr.bytes().skip(1).fold(0, utils::xor);
+        format!("{}*{:02X}\r\n", self.bfr, checksum)
+    }
+}
+
+/// Format an angle in degrees as degrees and decimal minutes, the degrees
+/// padded to the given width
+fn degrees_minutes(value: f64, width: usize) -> String {
+    // round first so that minutes never show up as 60
+    let total = round(value * 60.0 * 1e5);
+    let degrees = total / (60 * 100_000);
+    let minutes = (total % (60 * 100_000)) as f64 / 1e5;
+
+    format!("{:0w$}{:08.5}", degrees, minutes, w = width)
+}
+
+/// Round a non-negative value to the nearest integer, f64::round is not
+/// available without std
+fn round(value: f64) -> u64 {
+    (value + 0.5) as u64
+}
+
+mod utils {
+    /// Whether the byte starts a sentence, either a parametric ('$') or an
+    /// encapsulated ('!') one
+    pub fn is_start(b: u8) -> bool {
+        b == b'$' || b == b'!'
+    }
+
+    pub fn xor(current: u8, b: u8) -> u8 {
+        current ^ b
+    }
+
+    /// Parse a checksum given as two hexadecimal digits
+    pub fn parse_checksum(hex: &[u8]) -> Option<u8> {
+        let mut value = 0;
+        for b in hex {
+            value = value * 16 + (*b as char).to_digit(16)? as u8;
+        }
+
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::boxed::Box;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    /// Push every byte of the given sentence, returning the first error or
+    /// the last result
+    fn parse(parser: &mut Parser, sentence: &str) -> Result<Option<Sentence>, Error> {
+        let mut ret = Ok(None);
+        for b in sentence.bytes() {
+            ret = parser.push(b);
+            if ret.is_err() {
+                break;
+            }
+        }
+
+        ret
+    }
+
+    #[test]
+    fn initial_state() {
+        let parser = Parser::new();
+
+        assert_eq!(parser.field, Field::Sync);
+        assert_eq!(parser.sntc, Sentence::Invalid);
+        assert_eq!(parser.len, 0);
+        assert_eq!(parser.read_checksum, 0);
+        assert_eq!(parser.checksum, 0);
+    }
+
+    #[test]
+    fn reset_state() {
+        let mut bfr = [0; MAX_SENTENCE_LEN];
+        bfr[..2].copy_from_slice(b"42");
+
+        let mut parser = Parser {
+            field: Field::Checksum,
+            sntc: Sentence::from(&String::from("GGA")),
+            id: Some(SentenceId::new(b"GGA")),
+            bfr,
+            len: 2,
+            pos: 10,
+            checksum: 2,
+            read_checksum: 5,
+            registry: Registry::new(),
+        };
+
+        assert_ne!(parser.field, Field::Sync);
+        assert_ne!(parser.sntc, Sentence::Invalid);
+        assert_ne!(parser.len, 0);
+        assert_ne!(parser.read_checksum, 0);
+        assert_ne!(parser.checksum, 0);
+
+        parser.reset();
+
+        assert_eq!(parser.field, Field::Sync);
+        assert_eq!(parser.sntc, Sentence::Invalid);
+        assert_eq!(parser.id, None);
+        assert_eq!(parser.len, 0);
+        assert_eq!(parser.pos, 0);
+        assert_eq!(parser.read_checksum, 0);
+        assert_eq!(parser.checksum, 0);
+    }
+
+    #[test]
+    fn invalid_sync() {
+        let sentence = "#GGA,dasda,dasdsad,*321";
+        let mut parser = Parser::new();
+
+        let ret = parser.push(sentence.as_bytes()[0]);
+        assert!(ret.is_err());
+        assert!(matches!(
+            ret.err().unwrap().kind,
+            ErrorKind::InvalidSync(b'#')
+        ));
+    }
+
+    #[test]
+    fn invalid_sentence_id() {
+        let sentence = "$X,dasda,dasdsad*321";
+        let mut parser = Parser::new();
+
+        let mut ret = parser.push(sentence.as_bytes()[0]);
+        assert_eq!(ret, Ok(None));
+
+        ret = parser.push(sentence.as_bytes()[1]);
+        assert_eq!(ret, Ok(None));
+
+        ret = parser.push(sentence.as_bytes()[2]);
+        assert!(ret.is_err());
+
+        let e = ret.err().unwrap();
+        assert_eq!(e.kind, ErrorKind::InvalidId);
+        assert_eq!(e.id.unwrap().as_str(), "X");
+        assert_eq!(e.position, 1);
+    }
+
+    #[test]
+    fn valid_sentence_id() {
+        let sentence = "$GPGGA,dasda,dasdsad*321";
+        let mut parser = Parser::new();
+
+        let mut ret = parser.push(sentence.as_bytes()[0]);
+        assert_eq!(ret, Ok(None));
+
+        for i in 1..7 {
+            ret = parser.push(sentence.as_bytes()[i]);
+            assert_eq!(ret, Ok(None));
+        }
+
+        assert!(matches!(parser.sntc, Sentence::GGA(_)));
+        assert_eq!(parser.sntc.talker(), Talker::GP);
+    }
+
+    #[test]
+    fn talker_ids() {
+        assert_eq!(Talker::from("GPGGA"), Talker::GP);
+        assert_eq!(Talker::from("GLGGA"), Talker::GL);
+        assert_eq!(Talker::from("GAGGA"), Talker::GA);
+        assert_eq!(Talker::from("GBGGA"), Talker::GB);
+        assert_eq!(Talker::from("BDGGA"), Talker::GB);
+        assert_eq!(Talker::from("GQGGA"), Talker::GQ);
+        assert_eq!(Talker::from("GIGGA"), Talker::GI);
+        assert_eq!(Talker::from("GNGGA"), Talker::GN);
+        assert_eq!(Talker::from("AIVDM"), Talker::AI);
+        assert_eq!(Talker::from("XXGGA"), Talker::Unknown);
+        assert_eq!(Talker::from("GGA"), Talker::Unknown);
+        assert_eq!(Talker::from("\u{e9}GGA"), Talker::Unknown);
+
+        assert!(Talker::GN.is_combined());
+        assert!(!Talker::GP.is_combined());
+
+        assert_eq!(Sentence::from(&String::from("GNVTG")).talker(), Talker::GN);
+        assert_eq!(Sentence::from(&String::from("GAZDA")).talker(), Talker::GA);
+    }
+
+    #[test]
+    fn full_valid_parse() {
+        let sentence =
+            "$GPGGA,202530.00,5109.0262,N,11401.8407,W,5,40,0.5,1097.36,M,-17.00,M,18,TSTR*61";
+        let mut parser = Parser::new();
+
+        // parse full sentence
+        let ret = parse(&mut parser, sentence);
+
+        // check parser reset state properly
+        assert!(matches!(parser.field, Field::Sync));
+        assert!(ret.is_ok());
+        // proper checksum
+        assert_eq!(parser.read_checksum, 97);
+
+        match ret.unwrap().unwrap() {
+            Sentence::GGA(m) => {
+                assert_eq!(m.utc_time, Some(202530.0));
+                assert_eq!(m.lat, Some(5109.0262));
+                assert_eq!(m.ns, Some('N'));
+                assert_eq!(m.lon, Some(11401.8407));
+                assert_eq!(m.ew, Some('W'));
+                assert_eq!(m.validity, 5);
+                assert_eq!(m.sat, Some(40));
+                assert_eq!(m.hdop, Some(0.5));
+                assert_eq!(m.alt, Some(1097.36));
+                assert_eq!(m.units, Some('M'));
+                assert_eq!(m.gsep, Some(-17.0));
+                assert_eq!(m.gsep_units, Some('M'));
+                assert_eq!(m.dgps_age, Some(18.0));
+                assert_eq!(m.dgps_id, Some(String::from("TSTR")));
+            }
+            _ => panic!("expected GGA sentence"),
+        }
+    }
+
+    #[test]
+    fn gns_parse() {
+        let sentence =
+            "$GNGNS,014035.00,4332.69262,S,17235.48549,E,RR,13,0.9,25.63,11.24,1.2,0021,V*24";
+        let mut parser = Parser::new();
+
+        match parse(&mut parser, sentence).unwrap().unwrap() {
+            Sentence::GNS(m) => {
+                assert_eq!(m.talker, Talker::GN);
+                assert_eq!(m.utc_time, Some(14035.0));
+                assert_eq!(m.lat, Some(4332.69262));
+                assert_eq!(m.ns, Some('S'));
+                assert_eq!(m.lon, Some(17235.48549));
+                assert_eq!(m.ew, Some('E'));
+                assert_eq!(m.mode, Some(String::from("RR")));
+                assert_eq!(m.sat, Some(13));
+                assert_eq!(m.hdop, Some(0.9));
+                assert_eq!(m.alt, Some(25.63));
+                assert_eq!(m.gsep, Some(11.24));
+                assert_eq!(m.dgps_age, Some(1.2));
+                assert_eq!(m.dgps_id, Some(String::from("0021")));
+                assert_eq!(m.nav_status, Some('V'));
+            }
+            _ => panic!("expected GNS sentence"),
+        }
+
+        // no fix, without the fields of NMEA 4.1
+        let sentence = "$GPGNS,014035.00,,,,,NN,00,,,,,*60";
+        match parse(&mut parser, sentence).unwrap().unwrap() {
+            Sentence::GNS(m) => {
+                assert_eq!(m.mode, Some(String::from("NN")));
+                assert_eq!(m.sat, Some(0));
+                assert_eq!(m.lat, None);
+                assert_eq!(m.alt, None);
+                assert_eq!(m.dgps_id, None);
+                assert_eq!(m.nav_status, None);
+            }
+            _ => panic!("expected GNS sentence"),
+        }
+    }
+
+    #[test]
+    fn zda_parse() {
+        let sentence = "$GPZDA,201530.50,04,07,2002,-03,00*4B";
+        let mut parser = Parser::new();
+
+        match parse(&mut parser, sentence).unwrap().unwrap() {
+            Sentence::ZDA(m) => {
+                assert_eq!(m.utc, Some(201530.5));
+                assert_eq!(m.day, Some(4));
+                assert_eq!(m.month, Some(7));
+                assert_eq!(m.year, Some(2002));
+                assert_eq!(m.zone_hours, Some(-3));
+                assert_eq!(m.zone_minutes, Some(0));
+            }
+            _ => panic!("expected ZDA sentence"),
+        }
+    }
+
+    #[test]
+    fn rmc_parse() {
+        let sentence = "$GNRMC,083559.00,A,4717.11437,N,00833.91522,E,0.004,77.52,091202,,,R,V*20";
+        let mut parser = Parser::new();
+
+        match parse(&mut parser, sentence).unwrap().unwrap() {
+            Sentence::RMC(m) => {
+                assert_eq!(m.talker, Talker::GN);
+                assert_eq!(m.utc_time, Some(83559.0));
+                assert_eq!(m.status, Some('A'));
+                assert_eq!(m.lat, Some(4717.11437));
+                assert_eq!(m.ns, Some('N'));
+                assert_eq!(m.lon, Some(833.91522));
+                assert_eq!(m.ew, Some('E'));
+                assert_eq!(m.sog_knots, Some(0.004));
+                assert_eq!(m.cog_true, Some(77.52));
+                assert_eq!(m.date, Some(91202));
+                assert_eq!(m.variation, None);
+                assert_eq!(m.variation_dir, None);
+                assert_eq!(m.mode, Some('R'));
+                assert_eq!(m.nav_status, Some('V'));
+            }
+            _ => panic!("expected RMC sentence"),
+        }
+
+        // before NMEA 2.3
+        let sentence = "$GPRMC,083559.00,V,,,,,,,091202,,*15";
+        match parse(&mut parser, sentence).unwrap().unwrap() {
+            Sentence::RMC(m) => {
+                assert_eq!(m.status, Some('V'));
+                assert_eq!(m.lat, None);
+                assert_eq!(m.mode, None);
+            }
+            _ => panic!("expected RMC sentence"),
+        }
+    }
+
+    #[test]
+    /// Empty optional fields are parsed as None
+    fn empty_fields_parse() {
+        let sentence = "$GPVTG,,T,,M,0.00,N,0.00,K,N*2C";
+        let mut parser = Parser::new();
+
+        match parse(&mut parser, sentence).unwrap().unwrap() {
+            Sentence::VTG(m) => {
+                assert_eq!(m.cog_true, None);
+                assert_eq!(m.cog_magnetic, None);
+                assert_eq!(m.sog_knots, Some(0.0));
+                assert_eq!(m.sog_kph, Some(0.0));
+                assert_eq!(m.mode, Some('N'));
+            }
+            _ => panic!("expected VTG sentence"),
+        }
+    }
+
+    #[test]
+    fn heading_parse() {
+        let mut parser = Parser::new();
+
+        assert_eq!(
+            parse(&mut parser, "$GPHDT,274.07,T*03"),
+            Ok(Some(Sentence::HDT(DataHDT {
+                talker: Talker::GP,
+                heading: Some(274.07),
+            })))
+        );
+
+        assert_eq!(
+            parse(&mut parser, "$HCHDG,98.3,0.0,E,12.6,W*57"),
+            Ok(Some(Sentence::HDG(DataHDG {
+                talker: Talker::Unknown,
+                heading: Some(98.3),
+                deviation: Some(0.0),
+                deviation_dir: Some('E'),
+                variation: Some(12.6),
+                variation_dir: Some('W'),
+            })))
+        );
+
+        assert_eq!(
+            parse(&mut parser, "$GNTHS,,V*10"),
+            Ok(Some(Sentence::THS(DataTHS {
+                talker: Talker::GN,
+                heading: None,
+                mode: Some('V'),
+            })))
+        );
+    }
+
+    #[test]
+    /// Registered definitions are consulted before the built-in sentences
+    fn custom_parse() {
+        use crate::registry::*;
+
+        struct Pxdep;
+
+        impl SentenceDefinition for Pxdep {
+            fn id(&self) -> &str {
+                "PXDEP"
+            }
+
+            fn fields(&self) -> &[(&'static str, FieldKind)] {
+                &[("depth", FieldKind::Float), ("status", FieldKind::Char)]
+            }
+        }
+
+        let mut parser = Parser::new();
+        assert_eq!(
+            parse(&mut parser, "$PXDEP,12.5,A,3*1F").err().unwrap().kind,
+            ErrorKind::InvalidId
+        );
+
+        parser.registry_mut().register(Box::new(Pxdep)).unwrap();
+        match parse(&mut parser, "$PXDEP,12.5,A,3*1F").unwrap().unwrap() {
+            Sentence::Custom(m) => {
+                assert_eq!(m.id.as_str(), "PXDEP");
+                assert_eq!(m.definition, 0);
+                assert_eq!(m.values, vec![Value::Float(12.5), Value::Char('A')]);
+            }
+            _ => panic!("expected custom sentence"),
+        }
+
+        let e = parse(&mut parser, "$PXDEP,x,A*60").err().unwrap();
+        assert_eq!(e.kind, ErrorKind::InvalidField(ReadResult::InvalidFormat));
+        assert_eq!(e.field, Some((0, "depth")));
+        assert_eq!(e.position, 7);
+    }
+
+    #[test]
+    fn instrument_parse() {
+        let mut parser = Parser::new();
+
+        assert_eq!(
+            parse(&mut parser, "$SDDBT,8.1,f,2.47,M,1.35,F*09"),
+            Ok(Some(Sentence::DBT(DataDBT {
+                talker: Talker::Unknown,
+                depth_feet: Some(8.1),
+                depth_meters: Some(2.47),
+                depth_fathoms: Some(1.35),
+            })))
+        );
+
+        // NMEA 2.x, without maximum range
+        assert_eq!(
+            parse(&mut parser, "$SDDPT,2.47,-0.3*48"),
+            Ok(Some(Sentence::DPT(DataDPT {
+                depth: Some(2.47),
+                offset: Some(-0.3),
+                ..Default::default()
+            })))
+        );
+
+        assert_eq!(
+            parse(&mut parser, "$WIMWV,214.8,R,0.1,K,A*28"),
+            Ok(Some(Sentence::MWV(DataMWV {
+                angle: Some(214.8),
+                reference: Some('R'),
+                speed: Some(0.1),
+                units: Some('K'),
+                status: Some('A'),
+                ..Default::default()
+            })))
+        );
+
+        assert_eq!(
+            parse(&mut parser, "$WIMWD,186.5,T,196.3,M,12.4,N,6.4,M*68"),
+            Ok(Some(Sentence::MWD(DataMWD {
+                direction_true: Some(186.5),
+                direction_magnetic: Some(196.3),
+                speed_knots: Some(12.4),
+                speed_mps: Some(6.4),
+                ..Default::default()
+            })))
+        );
+
+        assert_eq!(
+            parse(&mut parser, "$YXMTW,17.75,C*26"),
+            Ok(Some(Sentence::MTW(DataMTW {
+                temperature: Some(17.75),
+                units: Some('C'),
+                ..Default::default()
+            })))
+        );
+
+        assert_eq!(
+            parse(&mut parser, "$VWVHW,,T,,M,6.5,N,12.0,K*64"),
+            Ok(Some(Sentence::VHW(DataVHW {
+                speed_knots: Some(6.5),
+                speed_kph: Some(12.0),
+                ..Default::default()
+            })))
+        );
+    }
+
+    #[test]
+    /// AIS sentences start with '!' and carry an armored payload
+    fn encapsulated_parse() {
+        let sentence = "!AIVDM,2,2,1,A,88888888880,2*25";
+        let mut parser = Parser::new();
+
+        assert_eq!(
+            parse(&mut parser, sentence),
+            Ok(Some(Sentence::VDM(DataVDM {
+                talker: Talker::AI,
+                fragments: 2,
+                fragment: 2,
+                sequence: Some(1),
+                channel: Some('A'),
+                payload: String::from("88888888880"),
+                fill_bits: 2,
+            })))
+        );
+
+        let sentence = "!AIVDO,1,1,,,B52K>;h00Fc>jpUlNV@ikwpUoP06,0*0F";
+        match parse(&mut parser, sentence).unwrap().unwrap() {
+            Sentence::VDO(m) => {
+                assert_eq!(m.sequence, None);
+                assert_eq!(m.channel, None);
+                assert_eq!(m.payload, "B52K>;h00Fc>jpUlNV@ikwpUoP06");
+            }
+            _ => panic!("expected VDO sentence"),
+        }
+
+        // '!' also interrupts an incomplete sentence
+        let e = parse(&mut parser, "$GPGGA,!").err().unwrap();
+        assert_eq!(e.kind, ErrorKind::Interrupted);
+    }
+
+    #[test]
+    /// Built sentences carry a valid checksum and are parsed back
+    fn build_sentence() {
+        let sentence = Builder::new("GPVTG")
+            .empty()
+            .field('T')
+            .empty()
+            .field('M')
+            .fixed(0.0, 2)
+            .field('N')
+            .fixed(0.0, 2)
+            .field('K')
+            .field('N')
+            .build();
+        assert_eq!(sentence, "$GPVTG,,T,,M,0.00,N,0.00,K,N*2C\r\n");
+
+        let sentence = Builder::new("GPGGA")
+            .time(Some(72930.5))
+            .latitude(Some(-41.185))
+            .longitude(Some(-8.7050025))
+            .field(1)
+            .optional(Some(8))
+            .optional_fixed(Some(1.01), 1)
+            .optional_fixed(None, 1)
+            .field('M')
+            .build();
+        assert!(sentence.starts_with("$GPGGA,201530.50,4111.10000,S,00842.30015,W,1,8,1.0,,M*"));
+
+        let mut parser = Parser::new();
+        match parse(&mut parser, sentence.trim_end()).unwrap().unwrap() {
+            Sentence::GGA(m) => {
+                assert_eq!(m.utc_time, Some(201530.5));
+                assert_eq!(m.lat, Some(4111.1));
+                assert_eq!(m.ns, Some('S'));
+                assert_eq!(m.lon, Some(842.30015));
+                assert_eq!(m.ew, Some('W'));
+                assert_eq!(m.sat, Some(8));
+                assert_eq!(m.alt, None);
+            }
+            _ => panic!("expected GGA sentence"),
+        }
+    }
+
+    #[test]
+    fn build_rounding() {
+        assert_eq!(degrees_minutes(8.9999999999, 3), "00900.00000");
+        assert_eq!(degrees_minutes(41.5, 2), "4130.00000");
+
+        let sentence = Builder::new("GPZDA")
+            .time(Some(86399.999))
+            .date(Some((1, 2, 2024)))
+            .build();
+        assert!(sentence.starts_with("$GPZDA,000000.00,010224*"));
+
+        let sentence = Builder::new("GPZDA").time(Some(45296.999)).build();
+        assert!(sentence.starts_with("$GPZDA,123457.00*"));
+    }
+
+    #[test]
+    /// Sentences larger than the work buffer are dropped
+    fn overflow() {
+        let mut parser = Parser::new();
+
+        let mut ret = parse(&mut parser, "$GPGGA,");
+        assert_eq!(ret, Ok(None));
+
+        for _ in 0..MAX_SENTENCE_LEN {
+            ret = parser.push(b'1');
+        }
+        assert_eq!(ret, Ok(None));
+
+        let e = parser.push(b'1').err().unwrap();
+        assert_eq!(e.kind, ErrorKind::Overflow);
+        assert_eq!(e.position, 7 + MAX_SENTENCE_LEN);
+        assert_eq!(parser.field, Field::Sync);
+    }
+
+    #[test]
+    /// Addresses longer than MAX_ID_LEN are rejected
+    fn overlong_id() {
+        let mut parser = Parser::new();
+
+        let mut ret = Ok(None);
+        for b in "$GPGGAGPGGA".bytes() {
+            ret = parser.push(b);
+            if ret != Ok(None) {
+                break;
+            }
+        }
+
+        let e = ret.err().unwrap();
+        assert_eq!(e.kind, ErrorKind::InvalidId);
+        assert_eq!(e.id.unwrap().as_str(), "GPGGAGPG");
+    }
+
+    #[test]
+    fn gst_parse() {
+        let sentence = "$GPGST,172814.0,0.006,0.023,0.020,273.6,0.023,0.020,0.031*6A";
+        let mut parser = Parser::new();
+
+        let ret = parse(&mut parser, sentence);
+
+        assert!(ret.is_ok());
+        match ret.unwrap().unwrap() {
+            Sentence::GST(m) => {
+                assert_eq!(m.utc, Some(172814.0));
+                assert_eq!(m.rms, Some(0.006));
+                assert_eq!(m.sd_major, Some(0.023));
+                assert_eq!(m.sd_minor, Some(0.020));
+                assert_eq!(m.orientation, Some(273.6));
+                assert_eq!(m.sd_lat, Some(0.023));
+                assert_eq!(m.sd_lon, Some(0.020));
+                assert_eq!(m.sd_alt, Some(0.031));
+            }
+            _ => panic!("expected GST sentence"),
+        }
+    }
+
+    #[test]
+    /// Field errors report the sentence, field and offending text location
+    fn invalid_field() {
+        let sentence = "$GPGGA,202530.00,51O9.0262,N,11401.8407,W,1,08,0.9,545.4,M,,,,*00";
+        let mut parser = Parser::new();
+
+        let e = parse(&mut parser, sentence).err().unwrap();
+        assert_eq!(e.kind, ErrorKind::InvalidField(ReadResult::InvalidFormat));
+        assert_eq!(e.id.unwrap().as_str(), "GPGGA");
+        assert_eq!(e.field, Some((1, "latitude")));
+        assert_eq!(e.text, "51O9.0262");
+        assert_eq!(e.position, 17);
+        assert_eq!(&sentence[e.position..e.position + e.text.len()], e.text);
+        assert_eq!(parser.field, Field::Sync);
+    }
+
+    #[test]
+    /// A missing mandatory field is reported with its name
+    fn missing_field() {
+        let mut parser = Parser::new();
+
+        let e = parse(&mut parser, "$GPVTG,054.7*00").err().unwrap();
+        assert_eq!(e.kind, ErrorKind::InvalidField(ReadResult::Empty));
+        assert_eq!(e.field, Some((1, "T")));
+        assert_eq!(e.position, 12);
+    }
+
+    #[test]
+    /// Non hexadecimal checksums are reported instead of panicking
+    fn invalid_checksum() {
+        let mut parser = Parser::new();
+
+        let e = parse(&mut parser, "$GPVTG,,T,,M,0.00,N,0.00,K,N*G1")
+            .err()
+            .unwrap();
+        assert_eq!(e.kind, ErrorKind::InvalidChecksum);
+        assert_eq!(e.text, "G1");
+        assert_eq!(e.position, 29);
+        assert_eq!(parser.field, Field::Sync);
+    }
+
+    #[test]
+    fn checksum_mismatch() {
+        let mut parser = Parser::new();
+
+        let e = parse(&mut parser, "$GPVTG,,T,,M,0.00,N,0.00,K,N*2D")
+            .err()
+            .unwrap();
+        assert_eq!(
+            e.kind,
+            ErrorKind::ChecksumMismatch {
+                expected: 0x2C,
+                received: 0x2D
+            }
+        );
+        assert_eq!(e.id.unwrap().as_str(), "GPVTG");
+    }
+
+    #[test]
+    /// Errors are readable
+    fn error_display() {
+        let e = Error {
+            kind: ErrorKind::InvalidField(ReadResult::InvalidFormat),
+            id: Some(SentenceId::new(b"GPGGA")),
+            field: Some((1, "latitude")),
+            text: String::from("51O9"),
+            position: 17,
+        };
+
+        assert_eq!(
+            e.to_string(),
+            "GPGGA: invalid field (Invalid format) in field 1 (latitude) \"51O9\" at byte 17"
+        );
+    }
+
+    #[test]
+    /// Arbitrary bytes never panic the parser
+    fn garbage_input() {
+        let mut parser = Parser::new();
+
+        for b in 0..=255u8 {
+            let _ = parser.push(b);
+        }
+
+        let garbage = [
+            b'$', 0xFF, 0xFE, b',', b'$', b'G', b'P', b'G', b'G', b'A', b',', 0xC3,
+        ];
+        for b in garbage.iter().chain(b"*\xFF\xFF".iter()) {
+            let _ = parser.push(*b);
+        }
+    }
+
+    #[test]
+    /// A sentence start in the middle of another one aborts it and the new
+    /// sentence is parsed
+    fn resync_on_start() {
+        let mut parser = Parser::new();
+
+        let e = parse(&mut parser, "$GPGGA,2025$").err().unwrap();
+        assert_eq!(e.kind, ErrorKind::Interrupted);
+        assert_eq!(e.position, 11);
+
+        let ret = parse(&mut parser, "GPVTG,,T,,M,0.00,N,0.00,K,N*2C");
+        assert!(matches!(ret, Ok(Some(Sentence::VTG(_)))));
+    }
+}
+
+#[cfg(test)]
+mod proptests {
+    use alloc::string::ToString;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use proptest::prelude::*;
+
+    use super::*;
+
+    /// Complete a sentence body (without '$') with its checksum
+    fn with_checksum(body: &str) -> String {
+        let checksum = body.bytes().fold(0, utils::xor);
+        format!("${}*{:02X}\r\n", body, checksum)
+    }
+
+    /// Push every byte and collect the parsed sentences
+    fn parse_all(parser: &mut Parser, data: &[u8]) -> Vec<Sentence> {
+        data.iter()
+            .filter_map(|b| parser.push(*b).ok().flatten())
+            .collect()
+    }
+
+    fn talker() -> impl Strategy<Value = &'static str> {
+        prop::sample::select(vec!["GP", "GL", "GA", "GB", "GQ", "GN"])
+    }
+
+    prop_compose! {
+        fn gga()(
+            talker in talker(),
+            utc in 0u32..235959,
+            lat in 0.0f64..9000.0,
+            ns in prop::sample::select(vec!['N', 'S']),
+            lon in 0.0f64..18000.0,
+            ew in prop::sample::select(vec!['E', 'W']),
+            validity in 0u8..9,
+            sat in prop::option::of(0u8..64),
+            hdop in prop::option::of(0.0f32..100.0),
+            alt in prop::option::of(-1000.0f32..10000.0),
+        ) -> (String, DataGGA) {
+            let opt = |v: Option<String>| v.unwrap_or_default();
+            let body = format!(
+                "{}GGA,{}.00,{},{},{},{},{},{},{},{},M",
+                talker,
+                utc,
+                lat,
+                ns,
+                lon,
+                ew,
+                validity,
+                opt(sat.map(|v| v.to_string())),
+                opt(hdop.map(|v| v.to_string())),
+                opt(alt.map(|v| v.to_string())),
+            );
+
+            let data = DataGGA {
+                talker: Talker::from(&body[..5]),
+                utc_time: Some(utc as f64),
+                lat: Some(lat),
+                ns: Some(ns),
+                lon: Some(lon),
+                ew: Some(ew),
+                validity,
+                sat,
+                hdop,
+                alt,
+                units: Some('M'),
+                ..Default::default()
+            };
+
+            (with_checksum(&body), data)
+        }
+    }
+
+    prop_compose! {
+        fn gst()(
+            talker in talker(),
+            utc in 0u32..235959,
+            values in prop::collection::vec(prop::option::of(0.0f32..1000.0), 7),
+        ) -> (String, DataGST) {
+            let fields: Vec<String> = values
+                .iter()
+                .map(|v| v.map(|v| v.to_string()).unwrap_or_default())
+                .collect();
+            let body = format!("{}GST,{},{}", talker, utc, fields.join(","));
+
+            let data = DataGST {
+                talker: Talker::from(&body[..5]),
+                utc: Some(utc as f64),
+                rms: values[0],
+                sd_major: values[1],
+                sd_minor: values[2],
+                orientation: values[3],
+                sd_lat: values[4],
+                sd_lon: values[5],
+                sd_alt: values[6],
+            };
+
+            (with_checksum(&body), data)
+        }
+    }
+
+    proptest! {
+        #[test]
+        /// Valid GGA sentences are parsed back into the data they were built from
+        fn gga_round_trip((sentence, data) in gga()) {
+            let mut parser = Parser::new();
+            let parsed = parse_all(&mut parser, sentence.as_bytes());
+
+            prop_assert_eq!(parsed, vec![Sentence::GGA(data)]);
+        }
+
+        #[test]
+        /// Valid GST sentences are parsed back into the data they were built from
+        fn gst_round_trip((sentence, data) in gst()) {
+            let mut parser = Parser::new();
+            let parsed = parse_all(&mut parser, sentence.as_bytes());
+
+            prop_assert_eq!(parsed, vec![Sentence::GST(data)]);
+        }
+
+        #[test]
+        /// Any byte sequence is handled without panicking
+        fn never_panics(data in prop::collection::vec(any::<u8>(), 0..512)) {
+            let mut parser = Parser::new();
+            for b in data {
+                let _ = parser.push(b);
+            }
+        }
+
+        #[test]
+        /// Whatever garbage precedes it, a valid sentence is always parsed
+        fn resynchronizes(
+            garbage in prop::collection::vec(any::<u8>(), 0..256),
+            (sentence, data) in gga(),
+        ) {
+            let mut parser = Parser::new();
+            parse_all(&mut parser, &garbage);
+
+            let parsed = parse_all(&mut parser, sentence.as_bytes());
+            prop_assert_eq!(parsed, vec![Sentence::GGA(data)]);
+        }
+
+        #[test]
+        /// Corrupting a single byte of a sentence never yields a different
+        /// sentence
+        fn corruption_detected(
+            (sentence, data) in gga(),
+            index in any::<prop::sample::Index>(),
+            value in any::<u8>(),
+        ) {
+            let mut bytes = sentence.into_bytes();
+            // keep the sentence start and line terminator
+            let i = 1 + index.index(bytes.len() - 3);
+            bytes[i] = value;
+
+            let mut parser = Parser::new();
+            for sentence in parse_all(&mut parser, &bytes) {
+                prop_assert_eq!(sentence, Sentence::GGA(data.clone()));
+            }
+        }
+    }
+}
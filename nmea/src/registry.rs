@@ -0,0 +1,293 @@
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::field_reader::{FieldReader, ReadResult};
+use crate::sentences::FieldError;
+use crate::{Sentence, SentenceId, Talker};
+
+/// Expected type of a custom sentence field
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FieldKind {
+    Integer,
+    Float,
+    Char,
+    Text,
+}
+
+/// Value of a custom sentence field
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Empty,
+    Integer(i64),
+    Float(f64),
+    Char(char),
+    Text(String),
+}
+
+/// Sentence parsed by a registered definition
+#[derive(Clone, Debug, PartialEq)]
+pub struct DataCustom {
+    /// Talker that emitted the sentence, Unknown for proprietary sentences
+    pub talker: Talker,
+    /// Sentence address
+    pub id: SentenceId,
+    /// Index of the definition in the registry
+    pub definition: usize,
+    /// Field values, in order
+    pub values: Vec<Value>,
+}
+
+impl DataCustom {
+    pub fn integer(&self, index: usize) -> Option<i64> {
+        match self.values.get(index) {
+            Some(Value::Integer(v)) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Float value, integers are converted
+    pub fn float(&self, index: usize) -> Option<f64> {
+        match self.values.get(index) {
+            Some(Value::Float(v)) => Some(*v),
+            Some(Value::Integer(v)) => Some(*v as f64),
+            _ => None,
+        }
+    }
+
+    pub fn char(&self, index: usize) -> Option<char> {
+        match self.values.get(index) {
+            Some(Value::Char(v)) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn text(&self, index: usize) -> Option<&str> {
+        match self.values.get(index) {
+            Some(Value::Text(v)) => Some(v.as_str()),
+            _ => None,
+        }
+    }
+}
+
+/// Definition of a sentence not built into the parser, usually a
+/// proprietary one such as $PASHR
+pub trait SentenceDefinition: Send {
+    /// Sentence address, matched on its suffix like the built-in sentences,
+    /// e.g. "PASHR" or "PSTMPV". Ids the built-in sentences would match are
+    /// refused
+    fn id(&self) -> &str;
+
+    /// Name and type of every data field, in order
+    fn fields(&self) -> &[(&'static str, FieldKind)];
+
+    /// Parse the data fields. By default they are parsed according to
+    /// fields(), empty and missing ones as Value::Empty
+    fn parse(&self, fin: &mut FieldReader, values: &mut Vec<Value>) -> Result<(), FieldError> {
+        for (name, kind) in self.fields() {
+            let value = match kind {
+                FieldKind::Integer => fin.read_optional().map(|v| v.map(Value::Integer)),
+                FieldKind::Float => fin.read_optional().map(|v| v.map(Value::Float)),
+                FieldKind::Char => fin.read_optional().map(|v| v.map(Value::Char)),
+                FieldKind::Text => fin.read_optional().map(|v| v.map(Value::Text)),
+            }
+            .map_err(|e| FieldError::new(fin, e, name))?;
+
+            values.push(value.unwrap_or(Value::Empty));
+        }
+
+        Ok(())
+    }
+}
+
+/// Reasons a definition is refused by the registry
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RegisterError {
+    /// Empty id, which would match no sentence
+    EmptyId,
+    /// Id matched by a built-in sentence, which a definition cannot replace
+    BuiltIn,
+}
+
+/// Sentence definitions consulted by the parser before the built-in ones.
+/// The built-in sentences (GGA, RMC, ...) are not registry entries: they are
+/// parsed by the parser itself and can be neither listed nor replaced here
+#[derive(Default)]
+pub struct Registry {
+    definitions: Vec<Box<dyn SentenceDefinition>>,
+}
+
+impl Registry {
+    pub fn new() -> Registry {
+        Default::default()
+    }
+
+    /// Add a definition, refused when its id is empty or would shadow a
+    /// built-in sentence
+    pub fn register(
+        &mut self,
+        definition: Box<dyn SentenceDefinition>,
+    ) -> Result<(), RegisterError> {
+        let id = definition.id();
+        if id.is_empty() {
+            return Err(RegisterError::EmptyId);
+        }
+        if Sentence::from(id) != Sentence::Invalid {
+            return Err(RegisterError::BuiltIn);
+        }
+
+        self.definitions.push(definition);
+        Ok(())
+    }
+
+    /// Number of registered definitions
+    pub fn len(&self) -> usize {
+        self.definitions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.definitions.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&dyn SentenceDefinition> {
+        self.definitions.get(index).map(|d| d.as_ref())
+    }
+
+    /// Sentence for the given address, if a definition matches it
+    pub fn sentence(&self, id: &SentenceId) -> Option<Sentence> {
+        let address = id.as_str();
+        let definition = self
+            .definitions
+            .iter()
+            .position(|d| address.ends_with(d.id()))?;
+
+        Some(Sentence::Custom(DataCustom {
+            talker: Talker::from(address),
+            id: *id,
+            definition,
+            values: Vec::new(),
+        }))
+    }
+
+    /// Parse the data fields of a custom sentence
+    pub fn parse_fields(&self, data: &mut DataCustom, fields: &[u8]) -> Result<(), FieldError> {
+        let mut fin = FieldReader::new(fields, b',');
+        match self.get(data.definition) {
+            Some(definition) => definition.parse(&mut fin, &mut data.values),
+            None => Err(FieldError::new(&fin, ReadResult::InternalError, "id")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    /// Hypothetical proprietary sentence carrying a depth and a status
+    struct Depth;
+
+    impl SentenceDefinition for Depth {
+        fn id(&self) -> &str {
+            "PXDEP"
+        }
+
+        fn fields(&self) -> &[(&'static str, FieldKind)] {
+            &[
+                ("depth", FieldKind::Float),
+                ("status", FieldKind::Char),
+                ("count", FieldKind::Integer),
+                ("name", FieldKind::Text),
+            ]
+        }
+    }
+
+    #[test]
+    fn lookup() {
+        let mut registry = Registry::new();
+        assert_eq!(registry.sentence(&SentenceId::new(b"PXDEP")), None);
+
+        registry.register(Box::new(Depth)).unwrap();
+        assert!(registry.get(0).is_some());
+        assert_eq!(registry.len(), 1);
+        assert!(registry.get(1).is_none());
+
+        match registry.sentence(&SentenceId::new(b"PXDEP")) {
+            Some(Sentence::Custom(m)) => {
+                assert_eq!(m.definition, 0);
+                assert_eq!(m.id.as_str(), "PXDEP");
+                assert_eq!(m.talker, Talker::Unknown);
+            }
+            s => panic!("unexpected {:?}", s),
+        }
+
+        assert_eq!(registry.sentence(&SentenceId::new(b"GPGGA")), None);
+    }
+
+    #[test]
+    fn default_parse() {
+        let mut registry = Registry::new();
+        registry.register(Box::new(Depth)).unwrap();
+
+        let mut data = match registry.sentence(&SentenceId::new(b"PXDEP")) {
+            Some(Sentence::Custom(m)) => m,
+            s => panic!("unexpected {:?}", s),
+        };
+
+        registry.parse_fields(&mut data, b"12.5,A,3").unwrap();
+        assert_eq!(
+            data.values,
+            vec![
+                Value::Float(12.5),
+                Value::Char('A'),
+                Value::Integer(3),
+                Value::Empty
+            ]
+        );
+        assert_eq!(data.float(0), Some(12.5));
+        assert_eq!(data.float(2), Some(3.0));
+        assert_eq!(data.char(1), Some('A'));
+        assert_eq!(data.integer(2), Some(3));
+        assert_eq!(data.text(3), None);
+
+        data.values.clear();
+        let e = registry.parse_fields(&mut data, b"12.5,A,x").err().unwrap();
+        assert_eq!(e.index, 2);
+        assert_eq!(e.name, "count");
+    }
+
+    /// Definition with any id
+    struct Named(&'static str);
+
+    impl SentenceDefinition for Named {
+        fn id(&self) -> &str {
+            self.0
+        }
+
+        fn fields(&self) -> &[(&'static str, FieldKind)] {
+            &[]
+        }
+    }
+
+    #[test]
+    fn built_in_ids() {
+        let mut registry = Registry::new();
+
+        for id in ["GGA", "GPRMC", "PXGGA", "HDT"] {
+            assert_eq!(
+                registry.register(Box::new(Named(id))),
+                Err(RegisterError::BuiltIn),
+                "{}",
+                id
+            );
+        }
+        assert_eq!(
+            registry.register(Box::new(Named(""))),
+            Err(RegisterError::EmptyId)
+        );
+        assert!(registry.is_empty());
+
+        // built-in sentences are still parsed as such
+        assert_eq!(registry.sentence(&SentenceId::new(b"GPGGA")), None);
+    }
+}
@@ -0,0 +1,491 @@
+use alloc::string::String;
+
+use crate::field_reader::{FieldReader, ReadResult};
+use crate::{Sentence, Talker};
+
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct DataGGA {
+    /// Talker that emitted the sentence
+    pub talker: Talker,
+    pub utc_time: Option<f64>,
+    pub lat: Option<f64>,
+    pub ns: Option<char>,
+    pub lon: Option<f64>,
+    pub ew: Option<char>,
+    /// Quality indicator, 0 (invalid) to 8 (simulator)
+    pub validity: u8,
+    pub sat: Option<u8>,
+    pub hdop: Option<f32>,
+    /// Altitude above mean sea level
+    pub alt: Option<f32>,
+    pub units: Option<char>,
+    /// Geoid separation, the height of the geoid above the WGS-84 ellipsoid
+    pub gsep: Option<f32>,
+    pub gsep_units: Option<char>,
+    /// Age of the differential corrections, in seconds
+    pub dgps_age: Option<f32>,
+    /// Differential reference station, 0000 to 1023 though some receivers
+    /// use names
+    pub dgps_id: Option<String>,
+}
+
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct DataGNS {
+    /// Talker that emitted the sentence
+    pub talker: Talker,
+    pub utc_time: Option<f64>,
+    pub lat: Option<f64>,
+    pub ns: Option<char>,
+    pub lon: Option<f64>,
+    pub ew: Option<char>,
+    /// Mode indicator of each constellation: GPS, GLONASS, Galileo, BeiDou,
+    /// QZSS then NavIC, as far as the receiver reports them
+    pub mode: Option<String>,
+    /// Satellites in use, all constellations together
+    pub sat: Option<u8>,
+    pub hdop: Option<f32>,
+    /// Altitude above mean sea level, in meters
+    pub alt: Option<f32>,
+    /// Geoid separation, in meters
+    pub gsep: Option<f32>,
+    /// Age of the differential corrections, in seconds
+    pub dgps_age: Option<f32>,
+    /// Differential reference station
+    pub dgps_id: Option<String>,
+    /// Navigational status, NMEA 4.1 and later
+    pub nav_status: Option<char>,
+}
+
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct DataVTG {
+    /// Talker that emitted the sentence
+    pub talker: Talker,
+    pub cog_true: Option<f32>,
+    pub cog_magnetic: Option<f32>,
+    pub sog_knots: Option<f32>,
+    pub sog_kph: Option<f32>,
+    /// Mode indicator, NMEA 2.3 and later
+    pub mode: Option<char>,
+}
+
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct DataRMC {
+    /// Talker that emitted the sentence
+    pub talker: Talker,
+    pub utc_time: Option<f64>,
+    /// 'A' valid, 'V' warning
+    pub status: Option<char>,
+    pub lat: Option<f64>,
+    pub ns: Option<char>,
+    pub lon: Option<f64>,
+    pub ew: Option<char>,
+    pub sog_knots: Option<f32>,
+    pub cog_true: Option<f32>,
+    /// ddmmyy
+    pub date: Option<u32>,
+    pub variation: Option<f32>,
+    pub variation_dir: Option<char>,
+    /// Mode indicator, NMEA 2.3 and later
+    pub mode: Option<char>,
+    /// Navigational status, NMEA 4.1 and later
+    pub nav_status: Option<char>,
+}
+
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct DataZDA {
+    /// Talker that emitted the sentence
+    pub talker: Talker,
+    /// UTC time status hhmmss.ss
+    pub utc: Option<f64>,
+    /// Day from 01 to 31
+    pub day: Option<u8>,
+    /// Month from 01 to 12
+    pub month: Option<u8>,
+    /// Year
+    pub year: Option<u16>,
+    /// Local zone hours, -13 to 13
+    pub zone_hours: Option<i8>,
+    /// Local zone minutes
+    pub zone_minutes: Option<u8>,
+}
+
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct DataGST {
+    /// Talker that emitted the sentence
+    pub talker: Talker,
+    /// UTC time hhmmss.ss
+    pub utc: Option<f64>,
+    /// RMS value of the standard deviation of the range inputs
+    pub rms: Option<f32>,
+    /// Standard deviation of the error ellipse semi-major axis (meters)
+    pub sd_major: Option<f32>,
+    /// Standard deviation of the error ellipse semi-minor axis (meters)
+    pub sd_minor: Option<f32>,
+    /// Orientation of the error ellipse semi-major axis (degrees from true north)
+    pub orientation: Option<f32>,
+    /// Standard deviation of latitude error (meters)
+    pub sd_lat: Option<f32>,
+    /// Standard deviation of longitude error (meters)
+    pub sd_lon: Option<f32>,
+    /// Standard deviation of altitude error (meters)
+    pub sd_alt: Option<f32>,
+}
+
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct DataHDT {
+    /// Talker that emitted the sentence
+    pub talker: Talker,
+    /// True heading in degrees
+    pub heading: Option<f32>,
+}
+
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct DataHDG {
+    /// Talker that emitted the sentence
+    pub talker: Talker,
+    /// Magnetic sensor heading in degrees
+    pub heading: Option<f32>,
+    /// Magnetic deviation in degrees
+    pub deviation: Option<f32>,
+    /// Deviation direction, 'E' or 'W'
+    pub deviation_dir: Option<char>,
+    /// Magnetic variation in degrees
+    pub variation: Option<f32>,
+    /// Variation direction, 'E' or 'W'
+    pub variation_dir: Option<char>,
+}
+
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct DataTHS {
+    /// Talker that emitted the sentence
+    pub talker: Talker,
+    /// True heading in degrees
+    pub heading: Option<f32>,
+    /// Mode indicator: A autonomous, E estimated, M manual, S simulator,
+    /// V invalid
+    pub mode: Option<char>,
+}
+
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct DataDBT {
+    /// Talker that emitted the sentence
+    pub talker: Talker,
+    /// Water depth below the transducer in feet
+    pub depth_feet: Option<f32>,
+    /// Water depth below the transducer in meters
+    pub depth_meters: Option<f32>,
+    /// Water depth below the transducer in fathoms
+    pub depth_fathoms: Option<f32>,
+}
+
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct DataDPT {
+    /// Talker that emitted the sentence
+    pub talker: Talker,
+    /// Water depth below the transducer in meters
+    pub depth: Option<f32>,
+    /// Transducer offset in meters, positive from the transducer to the
+    /// waterline and negative from the transducer to the keel
+    pub offset: Option<f32>,
+    /// Maximum range scale in use, in meters (NMEA 3.0)
+    pub max_range: Option<f32>,
+}
+
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct DataMWV {
+    /// Talker that emitted the sentence
+    pub talker: Talker,
+    /// Wind angle in degrees, from 0 to 359
+    pub angle: Option<f32>,
+    /// Reference, 'R' relative or 'T' theoretical (true)
+    pub reference: Option<char>,
+    pub speed: Option<f32>,
+    /// Speed units: 'K' kph, 'M' m/s, 'N' knots or 'S' statute mph
+    pub units: Option<char>,
+    /// Status, 'A' valid or 'V' invalid
+    pub status: Option<char>,
+}
+
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct DataMWD {
+    /// Talker that emitted the sentence
+    pub talker: Talker,
+    /// True direction the wind blows from, in degrees
+    pub direction_true: Option<f32>,
+    /// Magnetic direction the wind blows from, in degrees
+    pub direction_magnetic: Option<f32>,
+    pub speed_knots: Option<f32>,
+    pub speed_mps: Option<f32>,
+}
+
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct DataMTW {
+    /// Talker that emitted the sentence
+    pub talker: Talker,
+    /// Water temperature
+    pub temperature: Option<f32>,
+    /// Temperature units, 'C' for Celsius
+    pub units: Option<char>,
+}
+
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct DataVHW {
+    /// Talker that emitted the sentence
+    pub talker: Talker,
+    /// True heading in degrees
+    pub heading_true: Option<f32>,
+    /// Magnetic heading in degrees
+    pub heading_magnetic: Option<f32>,
+    /// Speed through water in knots
+    pub speed_knots: Option<f32>,
+    /// Speed through water in kph
+    pub speed_kph: Option<f32>,
+}
+
+/// AIS message fragment, carried by both VDM and VDO sentences
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct DataVDM {
+    /// Talker that emitted the sentence
+    pub talker: Talker,
+    /// Number of fragments of the message
+    pub fragments: u8,
+    /// Fragment number, starting at 1
+    pub fragment: u8,
+    /// Sequential message id, used to group the fragments of a message
+    pub sequence: Option<u8>,
+    /// Radio channel, 'A' or 'B'
+    pub channel: Option<char>,
+    /// 6-bit armored payload
+    pub payload: String,
+    /// Number of padding bits at the end of the payload
+    pub fill_bits: u8,
+}
+
+/// Failure to parse a sentence data field
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldError {
+    /// Why the field could not be parsed
+    pub reason: ReadResult,
+    /// Field index, 0 being the first data field
+    pub index: usize,
+    /// Field name
+    pub name: &'static str,
+    /// Raw field text
+    pub text: String,
+    /// Byte offset of the field in the data section
+    pub offset: usize,
+}
+
+impl FieldError {
+    /// Error on the field last fetched from the given reader
+    pub fn new(fin: &FieldReader, reason: ReadResult, name: &'static str) -> FieldError {
+        FieldError {
+            reason,
+            index: fin.index(),
+            name,
+            text: String::from_utf8_lossy(fin.last()).into_owned(),
+            offset: fin.offset(),
+        }
+    }
+}
+
+pub fn parse_fields(s: &mut Sentence, fields: &[u8]) -> Result<(), FieldError> {
+    let mut fin = FieldReader::new(fields, b',');
+    match s {
+        Sentence::GGA(m) => {
+            optional_field(&mut fin, &mut m.utc_time, "utc_time")?;
+            optional_field(&mut fin, &mut m.lat, "latitude")?;
+            optional_field(&mut fin, &mut m.ns, "N/S")?;
+            optional_field(&mut fin, &mut m.lon, "longitude")?;
+            optional_field(&mut fin, &mut m.ew, "E/W")?;
+            field(&mut fin, &mut m.validity, "validity")?;
+            optional_field(&mut fin, &mut m.sat, "number of satellites")?;
+            optional_field(&mut fin, &mut m.hdop, "HDOP")?;
+            optional_field(&mut fin, &mut m.alt, "altitude")?;
+            optional_field(&mut fin, &mut m.units, "units")?;
+            optional_field(&mut fin, &mut m.gsep, "geoid separation")?;
+            optional_field(&mut fin, &mut m.gsep_units, "geoid separation units")?;
+            optional_field(&mut fin, &mut m.dgps_age, "differential age")?;
+            optional_field(&mut fin, &mut m.dgps_id, "differential station")?;
+        }
+        Sentence::GNS(m) => {
+            optional_field(&mut fin, &mut m.utc_time, "utc_time")?;
+            optional_field(&mut fin, &mut m.lat, "latitude")?;
+            optional_field(&mut fin, &mut m.ns, "N/S")?;
+            optional_field(&mut fin, &mut m.lon, "longitude")?;
+            optional_field(&mut fin, &mut m.ew, "E/W")?;
+            optional_field(&mut fin, &mut m.mode, "mode indicator")?;
+            optional_field(&mut fin, &mut m.sat, "number of satellites")?;
+            optional_field(&mut fin, &mut m.hdop, "HDOP")?;
+            optional_field(&mut fin, &mut m.alt, "altitude")?;
+            optional_field(&mut fin, &mut m.gsep, "geoid separation")?;
+            optional_field(&mut fin, &mut m.dgps_age, "differential age")?;
+            optional_field(&mut fin, &mut m.dgps_id, "differential station")?;
+            optional_field(&mut fin, &mut m.nav_status, "navigational status")?;
+        }
+        Sentence::VTG(m) => {
+            // True COG and fixed field 'T'
+            optional_field(&mut fin, &mut m.cog_true, "true COG")?;
+            skip(&mut fin, "T")?;
+
+            // Magnetic COG and fixed field 'M'
+            optional_field(&mut fin, &mut m.cog_magnetic, "magnetic COG")?;
+            skip(&mut fin, "M")?;
+
+            // Speed Over Ground knots and fixed field 'N'
+            optional_field(&mut fin, &mut m.sog_knots, "SOG knots")?;
+            skip(&mut fin, "N")?;
+
+            // Speed Over Ground kph and fixed field 'K'
+            optional_field(&mut fin, &mut m.sog_kph, "SOG kph")?;
+            skip(&mut fin, "K")?;
+
+            // Mode indicator
+            optional_field(&mut fin, &mut m.mode, "mode indicator")?;
+        }
+        Sentence::GST(m) => {
+            optional_field(&mut fin, &mut m.utc, "utc")?;
+            optional_field(&mut fin, &mut m.rms, "RMS")?;
+            optional_field(&mut fin, &mut m.sd_major, "semi-major")?;
+            optional_field(&mut fin, &mut m.sd_minor, "semi-minor")?;
+            optional_field(&mut fin, &mut m.orientation, "orientation")?;
+            optional_field(&mut fin, &mut m.sd_lat, "latitude error")?;
+            optional_field(&mut fin, &mut m.sd_lon, "longitude error")?;
+            optional_field(&mut fin, &mut m.sd_alt, "altitude error")?;
+        }
+        Sentence::HDT(m) => {
+            // True heading and fixed field 'T'
+            optional_field(&mut fin, &mut m.heading, "heading")?;
+            skip(&mut fin, "T")?;
+        }
+        Sentence::HDG(m) => {
+            optional_field(&mut fin, &mut m.heading, "heading")?;
+            optional_field(&mut fin, &mut m.deviation, "deviation")?;
+            optional_field(&mut fin, &mut m.deviation_dir, "deviation E/W")?;
+            optional_field(&mut fin, &mut m.variation, "variation")?;
+            optional_field(&mut fin, &mut m.variation_dir, "variation E/W")?;
+        }
+        Sentence::THS(m) => {
+            optional_field(&mut fin, &mut m.heading, "heading")?;
+            optional_field(&mut fin, &mut m.mode, "mode indicator")?;
+        }
+        Sentence::DBT(m) => {
+            optional_field(&mut fin, &mut m.depth_feet, "depth feet")?;
+            skip(&mut fin, "f")?;
+            optional_field(&mut fin, &mut m.depth_meters, "depth meters")?;
+            skip(&mut fin, "M")?;
+            optional_field(&mut fin, &mut m.depth_fathoms, "depth fathoms")?;
+            skip(&mut fin, "F")?;
+        }
+        Sentence::DPT(m) => {
+            optional_field(&mut fin, &mut m.depth, "depth")?;
+            optional_field(&mut fin, &mut m.offset, "offset")?;
+            optional_field(&mut fin, &mut m.max_range, "maximum range")?;
+        }
+        Sentence::MWV(m) => {
+            optional_field(&mut fin, &mut m.angle, "wind angle")?;
+            optional_field(&mut fin, &mut m.reference, "reference")?;
+            optional_field(&mut fin, &mut m.speed, "wind speed")?;
+            optional_field(&mut fin, &mut m.units, "speed units")?;
+            optional_field(&mut fin, &mut m.status, "status")?;
+        }
+        Sentence::MWD(m) => {
+            optional_field(&mut fin, &mut m.direction_true, "true direction")?;
+            skip(&mut fin, "T")?;
+            optional_field(&mut fin, &mut m.direction_magnetic, "magnetic direction")?;
+            skip(&mut fin, "M")?;
+            optional_field(&mut fin, &mut m.speed_knots, "speed knots")?;
+            skip(&mut fin, "N")?;
+            optional_field(&mut fin, &mut m.speed_mps, "speed m/s")?;
+            skip(&mut fin, "M")?;
+        }
+        Sentence::MTW(m) => {
+            optional_field(&mut fin, &mut m.temperature, "temperature")?;
+            optional_field(&mut fin, &mut m.units, "units")?;
+        }
+        Sentence::VHW(m) => {
+            optional_field(&mut fin, &mut m.heading_true, "true heading")?;
+            skip(&mut fin, "T")?;
+            optional_field(&mut fin, &mut m.heading_magnetic, "magnetic heading")?;
+            skip(&mut fin, "M")?;
+            optional_field(&mut fin, &mut m.speed_knots, "speed knots")?;
+            skip(&mut fin, "N")?;
+            optional_field(&mut fin, &mut m.speed_kph, "speed kph")?;
+            skip(&mut fin, "K")?;
+        }
+        Sentence::VDM(m) | Sentence::VDO(m) => {
+            field(&mut fin, &mut m.fragments, "fragment count")?;
+            field(&mut fin, &mut m.fragment, "fragment number")?;
+            optional_field(&mut fin, &mut m.sequence, "sequential message id")?;
+            optional_field(&mut fin, &mut m.channel, "channel")?;
+            field(&mut fin, &mut m.payload, "payload")?;
+            field(&mut fin, &mut m.fill_bits, "fill bits")?;
+        }
+        Sentence::RMC(m) => {
+            optional_field(&mut fin, &mut m.utc_time, "utc_time")?;
+            optional_field(&mut fin, &mut m.status, "status")?;
+            optional_field(&mut fin, &mut m.lat, "latitude")?;
+            optional_field(&mut fin, &mut m.ns, "N/S")?;
+            optional_field(&mut fin, &mut m.lon, "longitude")?;
+            optional_field(&mut fin, &mut m.ew, "E/W")?;
+            optional_field(&mut fin, &mut m.sog_knots, "SOG knots")?;
+            optional_field(&mut fin, &mut m.cog_true, "true COG")?;
+            optional_field(&mut fin, &mut m.date, "date")?;
+            optional_field(&mut fin, &mut m.variation, "variation")?;
+            optional_field(&mut fin, &mut m.variation_dir, "variation E/W")?;
+            optional_field(&mut fin, &mut m.mode, "mode indicator")?;
+            optional_field(&mut fin, &mut m.nav_status, "navigational status")?;
+        }
+        Sentence::ZDA(m) => {
+            optional_field(&mut fin, &mut m.utc, "utc")?;
+            optional_field(&mut fin, &mut m.day, "day")?;
+            optional_field(&mut fin, &mut m.month, "month")?;
+            optional_field(&mut fin, &mut m.year, "year")?;
+            optional_field(&mut fin, &mut m.zone_hours, "local zone hours")?;
+            optional_field(&mut fin, &mut m.zone_minutes, "local zone minutes")?;
+        }
+        // custom sentences are parsed by their registry
+        Sentence::Custom(_) | Sentence::Invalid => {
+            return Err(FieldError::new(&fin, ReadResult::InternalError, "id"));
+        }
+    }
+
+    Ok(())
+}
+
+// Utils
+
+/// Parse an optional field from the given field reader and store in the
+/// given reference variable.
+fn optional_field<T: core::str::FromStr>(
+    fin: &mut FieldReader,
+    out: &mut Option<T>,
+    name: &'static str,
+) -> Result<(), FieldError> {
+    *out = fin
+        .read_optional::<T>()
+        .map_err(|e| FieldError::new(fin, e, name))?;
+
+    Ok(())
+}
+
+/// Parse a field from the given field reader and store in the
+/// given reference variable.
+fn field<T: core::str::FromStr>(
+    fin: &mut FieldReader,
+    out: &mut T,
+    name: &'static str,
+) -> Result<(), FieldError> {
+    *out = fin.read::<T>().map_err(|e| FieldError::new(fin, e, name))?;
+
+    Ok(())
+}
+
+/// Skip a field that must be present
+fn skip(fin: &mut FieldReader, name: &'static str) -> Result<(), FieldError> {
+    if fin.skip() {
+        Ok(())
+    } else {
+        Err(FieldError::new(fin, ReadResult::Empty, name))
+    }
+}
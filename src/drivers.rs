@@ -1,2 +1,7 @@
+pub mod clock;
 pub mod gps;
+pub mod instrument;
+pub mod io;
 pub mod logger;
+pub mod nmea_out;
+pub mod ntrip;
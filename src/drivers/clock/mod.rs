@@ -0,0 +1,185 @@
+use std::time::Duration;
+
+use actix::prelude::*;
+use actix_broker::{BrokerIssue, BrokerSubscribe};
+
+use crate::drivers::clock::shm::Shm;
+use crate::drivers::clock::sync::{gnss_time, Clock, ClockOffset, Mode, Synchronizer};
+use crate::drivers::clock::system::SystemClock;
+use crate::ix::Parameter;
+use crate::task;
+use crate::task::status_text;
+use crate::BrokerType;
+use crate::MessageWrapper;
+use crate::TaskBehaviour;
+
+pub mod shm;
+pub mod sync;
+pub mod system;
+
+#[derive(Default)]
+pub struct Configuration {
+    pub enabled: Parameter<bool>,
+    pub mode: Parameter<Mode>,
+    pub threshold: Parameter<u64>,
+    pub latency: Parameter<u64>,
+    pub interval: Parameter<u64>,
+    pub shm_unit: Parameter<u8>,
+}
+
+pub struct Task {
+    pub ctx: task::Context,
+    clock: Box<dyn Clock>,
+    sync: Synchronizer,
+    /// Refclock segment, in SHM mode
+    shm: Option<Shm>,
+    /// Last offset measured
+    pub offset: Option<ClockOffset>,
+    cfg: Configuration,
+}
+
+impl TaskBehaviour for Task {
+    fn get_ctx(&self) -> &task::Context {
+        &self.ctx
+    }
+
+    fn get_name(&self) -> &str {
+        "Clock Sync"
+    }
+
+    fn register_configuration(&mut self) {
+        self.cfg
+            .enabled
+            .name("Enabled")
+            .default(false)
+            .description("Measure the offset of the system clock to GNSS time");
+
+        self.cfg
+            .mode
+            .name("Mode")
+            .default(Mode::Monitor)
+            .description("Monitor only publishes the offset, Step and Slew correct the system clock, Shm feeds chrony or ntpd");
+
+        self.cfg
+            .threshold
+            .name("Threshold")
+            .default(500)
+            .description("In milliseconds, offsets below it are not corrected");
+
+        self.cfg
+            .latency
+            .name("Latency")
+            .default(0)
+            .description("In milliseconds, from the GNSS epoch to the reception of its time");
+
+        self.cfg
+            .interval
+            .name("Correction Interval")
+            .default(60)
+            .description("In seconds, minimum time between two corrections");
+
+        self.cfg.shm_unit.name("SHM Unit").default(0).description(
+            "Refclock segment NTP<unit> in SHM mode. Units 0 and 1 are only accessible to root",
+        );
+    }
+}
+
+impl Task {
+    pub fn new(context: task::Context) -> Task {
+        Task::with_clock(context, Box::new(SystemClock))
+    }
+
+    /// Task adjusting the given clock instead of the system one
+    pub fn with_clock(context: task::Context, clock: Box<dyn Clock>) -> Task {
+        Task {
+            ctx: context,
+            clock,
+            sync: Synchronizer::new(Mode::Monitor, 0.0, 0.0, Duration::ZERO),
+            shm: None,
+            offset: None,
+            cfg: Default::default(),
+        }
+    }
+
+    /// Measure the offset to the time of the fix, correcting the clock or
+    /// handing the sample over to chrony or ntpd as configured
+    pub fn handle_fix(&mut self, fix: &imc::GpsFix) -> Option<ClockOffset> {
+        let gnss = gnss_time(fix)?;
+        let receive = self.clock.now();
+
+        let offset = match self.sync.update(self.clock.as_mut(), gnss, receive) {
+            Ok(offset) => offset,
+            Err(e) => {
+                println!("ERROR: {}: {}", self.get_name(), e);
+                return None;
+            }
+        };
+
+        if let Some(shm) = self.shm.as_mut() {
+            shm.sample(
+                gnss + Duration::from_millis(*self.cfg.latency.get()),
+                receive,
+            );
+        }
+
+        if let Some(correction) = offset.correction {
+            println!(
+                "{}: {:?} of {:.3} s",
+                self.get_name(),
+                correction,
+                offset.offset
+            );
+        }
+
+        self.offset = Some(offset.clone());
+        Some(offset)
+    }
+}
+
+impl Actor for Task {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        if !*self.cfg.enabled.get() {
+            println!("{}: disabled", self.get_name());
+            return;
+        }
+
+        let mut mode = *self.cfg.mode.get();
+        if mode == Mode::Shm {
+            match Shm::open(*self.cfg.shm_unit.get()) {
+                Ok(shm) => self.shm = Some(shm),
+                Err(e) => {
+                    println!("ERROR: SHM unit {}: {}", self.cfg.shm_unit.get(), e);
+                    mode = Mode::Monitor;
+                }
+            }
+        }
+
+        self.sync = Synchronizer::new(
+            mode,
+            *self.cfg.threshold.get() as f64 / 1000.0,
+            *self.cfg.latency.get() as f64 / 1000.0,
+            Duration::from_secs(*self.cfg.interval.get()),
+        );
+
+        subscribe_to!(imc::GpsFix, self, ctx);
+    }
+
+    fn stopped(&mut self, _ctx: &mut Self::Context) {
+        if let Some(offset) = &self.offset {
+            println!("last clock offset: {:.3} s", offset.offset);
+        }
+        println!("{} stopped", self.get_name());
+    }
+}
+
+impl Handler<MessageWrapper<imc::GpsFix>> for Task {
+    type Result = ();
+
+    fn handle(&mut self, msg: MessageWrapper<imc::GpsFix>, _ctx: &mut Self::Context) {
+        if let Some(offset) = self.handle_fix(&msg.0) {
+            send_message!(self, imc::DevDataText, status_text(offset.to_string()));
+        }
+    }
+}
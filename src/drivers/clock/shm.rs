@@ -0,0 +1,154 @@
+use std::io;
+use std::ptr;
+use std::sync::atomic::{fence, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use libc::{c_int, c_uint, time_t};
+
+/// Key of the first segment, "NTP0"
+const KEY_BASE: libc::key_t = 0x4E54_5030;
+
+/// Estimated precision of NMEA time, as a power of two in seconds. gpsd uses
+/// the same without PPS
+const PRECISION: c_int = -1;
+
+/// Shared memory segment of the ntpd SHM driver, also read by chrony
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct ShmTime {
+    /// 1: the reader checks that count did not change while reading
+    pub mode: c_int,
+    pub count: c_int,
+    pub clock_sec: time_t,
+    pub clock_usec: c_int,
+    pub receive_sec: time_t,
+    pub receive_usec: c_int,
+    pub leap: c_int,
+    pub precision: c_int,
+    pub nsamples: c_int,
+    pub valid: c_int,
+    pub clock_nsec: c_uint,
+    pub receive_nsec: c_uint,
+    pub dummy: [c_int; 8],
+}
+
+/// Write a sample: the reference time and the system time it was received
+/// at, following the mode 1 protocol
+pub fn store(shm: &mut ShmTime, clock: SystemTime, receive: SystemTime) {
+    let split = |t: SystemTime| {
+        let d = t.duration_since(UNIX_EPOCH).unwrap_or_default();
+        (d.as_secs() as time_t, d.subsec_nanos())
+    };
+    let (clock_sec, clock_nsec) = split(clock);
+    let (receive_sec, receive_nsec) = split(receive);
+
+    // SAFETY: plain writes to fields of a valid reference, volatile as the
+    // segment is read concurrently by another process
+    unsafe {
+        ptr::write_volatile(&mut shm.valid, 0);
+        ptr::write_volatile(&mut shm.count, shm.count.wrapping_add(1));
+        fence(Ordering::SeqCst);
+
+        ptr::write_volatile(&mut shm.mode, 1);
+        ptr::write_volatile(&mut shm.clock_sec, clock_sec);
+        ptr::write_volatile(&mut shm.clock_usec, (clock_nsec / 1000) as c_int);
+        ptr::write_volatile(&mut shm.clock_nsec, clock_nsec);
+        ptr::write_volatile(&mut shm.receive_sec, receive_sec);
+        ptr::write_volatile(&mut shm.receive_usec, (receive_nsec / 1000) as c_int);
+        ptr::write_volatile(&mut shm.receive_nsec, receive_nsec);
+        ptr::write_volatile(&mut shm.leap, 0);
+        ptr::write_volatile(&mut shm.precision, PRECISION);
+        ptr::write_volatile(&mut shm.nsamples, 0);
+
+        fence(Ordering::SeqCst);
+        ptr::write_volatile(&mut shm.count, shm.count.wrapping_add(1));
+        ptr::write_volatile(&mut shm.valid, 1);
+    }
+}
+
+/// Refclock segment NTP<unit>, e.g. "refclock SHM 0" for chrony. Units 0 and
+/// 1 are only accessible to root
+pub struct Shm {
+    segment: *mut ShmTime,
+}
+
+// the segment is only accessed through &mut self
+unsafe impl Send for Shm {}
+
+impl Shm {
+    pub fn open(unit: u8) -> io::Result<Shm> {
+        let permissions = if unit <= 1 { 0o600 } else { 0o666 };
+
+        // SAFETY: the segment is created with the size of ShmTime, or has
+        // it already, and detached on drop
+        unsafe {
+            let id = libc::shmget(
+                KEY_BASE + unit as libc::key_t,
+                std::mem::size_of::<ShmTime>(),
+                libc::IPC_CREAT | permissions,
+            );
+            if id == -1 {
+                return Err(io::Error::last_os_error());
+            }
+
+            let segment = libc::shmat(id, ptr::null(), 0);
+            if segment as isize == -1 {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok(Shm {
+                segment: segment as *mut ShmTime,
+            })
+        }
+    }
+
+    pub fn sample(&mut self, clock: SystemTime, receive: SystemTime) {
+        // SAFETY: attached in open, until drop
+        store(unsafe { &mut *self.segment }, clock, receive);
+    }
+}
+
+impl Drop for Shm {
+    fn drop(&mut self) {
+        // SAFETY: attached in open
+        unsafe {
+            libc::shmdt(self.segment as *const libc::c_void);
+        }
+    }
+}
+
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn sample() {
+        let mut shm = ShmTime::default();
+        let clock = UNIX_EPOCH + Duration::new(1_709_164_800, 250_000_000);
+        let receive = UNIX_EPOCH + Duration::new(60, 999_999_999);
+
+        store(&mut shm, clock, receive);
+        assert_eq!(shm.mode, 1);
+        assert_eq!(shm.valid, 1);
+        assert_eq!(shm.count, 2);
+        assert_eq!(shm.clock_sec, 1_709_164_800);
+        assert_eq!(shm.clock_usec, 250_000);
+        assert_eq!(shm.clock_nsec, 250_000_000);
+        assert_eq!(shm.receive_sec, 60);
+        assert_eq!(shm.receive_usec, 999_999);
+        assert_eq!(shm.receive_nsec, 999_999_999);
+        assert_eq!(shm.precision, PRECISION);
+
+        // the reader sees an even count once done
+        store(&mut shm, clock, receive);
+        assert_eq!(shm.count, 4);
+    }
+
+    #[test]
+    fn layout() {
+        // as struct shmTime of ntpd on 64-bit targets
+        if std::mem::size_of::<time_t>() == 8 {
+            assert_eq!(std::mem::size_of::<ShmTime>(), 96);
+        }
+    }
+}
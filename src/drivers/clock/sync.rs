@@ -0,0 +1,362 @@
+use std::fmt;
+use std::fmt::Formatter;
+use std::io;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use imc::GpsFix;
+
+/// System clock, as seen and adjusted by the synchronization
+pub trait Clock: Send {
+    fn now(&self) -> SystemTime;
+
+    /// Jump the clock by the given offset, in seconds
+    fn step(&mut self, offset: f64) -> io::Result<()>;
+
+    /// Gradually correct the given offset, in seconds
+    fn slew(&mut self, offset: f64) -> io::Result<()>;
+
+    /// Largest offset slew() accepts, in seconds. Larger ones are stepped
+    fn max_slew(&self) -> f64 {
+        f64::INFINITY
+    }
+}
+
+/// What to do with the measured offset
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Mode {
+    /// Only publish it
+    #[default]
+    Monitor,
+    /// Jump to GNSS time
+    Step,
+    /// Speed up or slow down the clock until it catches up. Offsets too
+    /// large to slew, e.g. after booting without RTC, are stepped once
+    Slew,
+    /// Leave it to chrony or ntpd, through their shared memory refclock
+    Shm,
+}
+
+/// How the offset was acted upon
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Correction {
+    Step,
+    Slew,
+    /// Sample handed over to chrony or ntpd
+    Shm,
+}
+
+/// Offset between GNSS and system time, published as text on every fix with
+/// a valid time
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClockOffset {
+    /// GNSS time minus system time, in seconds. Positive when the system
+    /// clock is late
+    pub offset: f64,
+    pub correction: Option<Correction>,
+}
+
+impl fmt::Display for ClockOffset {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "clock offset: {:.3} s", self.offset)?;
+
+        match self.correction {
+            Some(Correction::Step) => f.write_str(", stepped"),
+            Some(Correction::Slew) => f.write_str(", slewed"),
+            Some(Correction::Shm) => f.write_str(", sampled to SHM"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// UTC time of a fix with valid time and date
+pub fn gnss_time(fix: &GpsFix) -> Option<SystemTime> {
+    use imc::messages::GpsFix::ValidityBits;
+
+    let valid = ValidityBits::GFV_VALID_TIME as u16 | ValidityBits::GFV_VALID_DATE as u16;
+    if fix._validity & valid != valid || !(0.0..86400.0).contains(&fix._utc_time) {
+        return None;
+    }
+
+    let days = days_from_civil(fix._utc_year as i64, fix._utc_month, fix._utc_day)?;
+    let seconds = days as f64 * 86400.0 + fix._utc_time as f64;
+    if seconds < 0.0 {
+        return None;
+    }
+
+    Some(UNIX_EPOCH + Duration::from_secs_f64(seconds))
+}
+
+/// Days since 1970-01-01 of a date of the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: u8, day: u8) -> Option<i64> {
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // years starting in March, the leap day being the last one
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    Some(era * 146097 + day_of_era - 719468)
+}
+
+/// Signed seconds from `from` to `to`
+fn difference(from: SystemTime, to: SystemTime) -> f64 {
+    match to.duration_since(from) {
+        Ok(d) => d.as_secs_f64(),
+        Err(e) => -e.duration().as_secs_f64(),
+    }
+}
+
+/// Decide, from the offsets measured, when to correct the clock
+pub struct Synchronizer {
+    mode: Mode,
+    /// Offsets below it are left alone, in seconds
+    threshold: f64,
+    /// Between the GNSS epoch and its reception, in seconds
+    latency: f64,
+    /// Minimum time between corrections
+    interval: Duration,
+    /// System time of the last correction
+    last: Option<SystemTime>,
+}
+
+impl Synchronizer {
+    pub fn new(mode: Mode, threshold: f64, latency: f64, interval: Duration) -> Synchronizer {
+        Synchronizer {
+            mode,
+            threshold,
+            latency,
+            interval,
+            last: None,
+        }
+    }
+
+    /// GNSS minus system time, for a GNSS time received now
+    pub fn offset(&self, gnss: SystemTime, now: SystemTime) -> f64 {
+        difference(now, gnss) + self.latency
+    }
+
+    /// Measure the offset of the clock to the given GNSS time, received at
+    /// `now` by the clock, and correct the clock when needed. SHM samples
+    /// are handed over by the caller, with the same reception time
+    pub fn update(
+        &mut self,
+        clock: &mut dyn Clock,
+        gnss: SystemTime,
+        now: SystemTime,
+    ) -> io::Result<ClockOffset> {
+        let offset = self.offset(gnss, now);
+
+        let due = match self.last {
+            Some(last) => difference(last, now).abs() >= self.interval.as_secs_f64(),
+            None => true,
+        };
+
+        let correction = match self.mode {
+            Mode::Monitor => None,
+            Mode::Shm => Some(Correction::Shm),
+            _ if !due || offset.abs() < self.threshold => None,
+            Mode::Step => {
+                clock.step(offset)?;
+                Some(Correction::Step)
+            }
+            Mode::Slew if offset.abs() > clock.max_slew() => {
+                clock.step(offset)?;
+                Some(Correction::Step)
+            }
+            Mode::Slew => {
+                clock.slew(offset)?;
+                Some(Correction::Slew)
+            }
+        };
+
+        if matches!(correction, Some(Correction::Step | Correction::Slew)) {
+            // after a step, the time the correction was made
+            self.last = Some(clock.now());
+        }
+
+        Ok(ClockOffset { offset, correction })
+    }
+}
+
+mod tests {
+    use super::*;
+
+    /// Clock recording the corrections, stepping its own time
+    struct Mock {
+        now: SystemTime,
+        steps: Vec<f64>,
+        slews: Vec<f64>,
+        max_slew: f64,
+    }
+
+    impl Mock {
+        fn new(now: SystemTime) -> Mock {
+            Mock {
+                now,
+                steps: vec![],
+                slews: vec![],
+                max_slew: 2145.0,
+            }
+        }
+    }
+
+    impl Clock for Mock {
+        fn now(&self) -> SystemTime {
+            self.now
+        }
+
+        fn step(&mut self, offset: f64) -> io::Result<()> {
+            self.steps.push(offset);
+            self.now = if offset >= 0.0 {
+                self.now + Duration::from_secs_f64(offset)
+            } else {
+                self.now - Duration::from_secs_f64(-offset)
+            };
+            Ok(())
+        }
+
+        fn slew(&mut self, offset: f64) -> io::Result<()> {
+            self.slews.push(offset);
+            Ok(())
+        }
+
+        fn max_slew(&self) -> f64 {
+            self.max_slew
+        }
+    }
+
+    fn fix(year: u16, month: u8, day: u8, time: f32) -> GpsFix {
+        let mut fix = GpsFix::new();
+        fix._utc_year = year;
+        fix._utc_month = month;
+        fix._utc_day = day;
+        fix._utc_time = time;
+        fix._validity = imc::messages::GpsFix::ValidityBits::GFV_VALID_TIME as u16
+            | imc::messages::GpsFix::ValidityBits::GFV_VALID_DATE as u16;
+        fix
+    }
+
+    fn unix(seconds: f64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs_f64(seconds)
+    }
+
+    /// Update with the GNSS time received now
+    fn update(sync: &mut Synchronizer, clock: &mut Mock, gnss: SystemTime) -> ClockOffset {
+        let now = clock.now;
+        sync.update(clock, gnss, now).unwrap()
+    }
+
+    #[test]
+    fn time_of_fix() {
+        assert_eq!(gnss_time(&fix(1970, 1, 1, 0.0)), Some(UNIX_EPOCH));
+        // leap year
+        assert_eq!(
+            gnss_time(&fix(2024, 2, 29, 3600.5)),
+            Some(unix(1_709_164_800.0 + 3600.5))
+        );
+        assert_eq!(
+            gnss_time(&fix(2002, 7, 4, 72930.0)),
+            Some(unix(1_025_740_800.0 + 72930.0))
+        );
+
+        // date or time unknown
+        let mut m = fix(2024, 2, 29, 0.0);
+        m._validity = imc::messages::GpsFix::ValidityBits::GFV_VALID_TIME as u16;
+        assert_eq!(gnss_time(&m), None);
+        assert_eq!(gnss_time(&fix(2024, 13, 1, 0.0)), None);
+        assert_eq!(gnss_time(&fix(1969, 12, 31, 0.0)), None);
+    }
+
+    #[test]
+    fn step() {
+        // booted without RTC, back in 1970
+        let mut clock = Mock::new(unix(60.0));
+        let mut sync = Synchronizer::new(Mode::Step, 0.5, 0.1, Duration::from_secs(60));
+
+        let gnss = unix(1_709_164_800.0);
+        let result = update(&mut sync, &mut clock, gnss);
+        assert_eq!(result.correction, Some(Correction::Step));
+        assert!((result.offset - (1_709_164_740.1)).abs() < 1e-3);
+        assert_eq!(clock.steps.len(), 1);
+
+        // in sync, within the threshold
+        let gnss = difference(UNIX_EPOCH, clock.now) + 0.2;
+        let result = update(&mut sync, &mut clock, unix(gnss));
+        assert_eq!(result.correction, None);
+        assert!((result.offset - 0.3).abs() < 1e-3);
+
+        // not before the interval elapsed
+        let gnss = difference(UNIX_EPOCH, clock.now) + 10.0;
+        assert_eq!(update(&mut sync, &mut clock, unix(gnss)).correction, None);
+        clock.now += Duration::from_secs(60);
+        let gnss = difference(UNIX_EPOCH, clock.now) + 10.0;
+        assert_eq!(
+            update(&mut sync, &mut clock, unix(gnss)).correction,
+            Some(Correction::Step)
+        );
+        assert_eq!(clock.steps.len(), 2);
+    }
+
+    #[test]
+    fn slew_and_monitor() {
+        let mut clock = Mock::new(unix(1_000_000.0));
+
+        let mut sync = Synchronizer::new(Mode::Slew, 0.01, 0.0, Duration::ZERO);
+        let result = update(&mut sync, &mut clock, unix(999_999.75));
+        assert_eq!(result.correction, Some(Correction::Slew));
+        assert_eq!(clock.slews, vec![-0.25]);
+        assert_eq!(result.to_string(), "clock offset: -0.250 s, slewed");
+
+        let mut sync = Synchronizer::new(Mode::Monitor, 0.01, 0.0, Duration::ZERO);
+        let result = update(&mut sync, &mut clock, unix(1_000_100.0));
+        assert_eq!(
+            result,
+            ClockOffset {
+                offset: 100.0,
+                correction: None
+            }
+        );
+        assert_eq!(result.to_string(), "clock offset: 100.000 s");
+        assert!(clock.steps.is_empty());
+        assert_eq!(clock.slews.len(), 1);
+    }
+
+    #[test]
+    /// The offset is to the time the GNSS time was received, not to the time
+    /// it is processed at
+    fn reception_time() {
+        let mut clock = Mock::new(unix(1_000_005.0));
+        let mut sync = Synchronizer::new(Mode::Monitor, 0.01, 0.0, Duration::ZERO);
+
+        let result = sync
+            .update(&mut clock, unix(1_000_000.5), unix(1_000_000.0))
+            .unwrap();
+        assert_eq!(result.offset, 0.5);
+    }
+
+    #[test]
+    fn slew_too_large() {
+        // booted without RTC, back in 1970
+        let mut clock = Mock::new(unix(60.0));
+        let mut sync = Synchronizer::new(Mode::Slew, 0.5, 0.0, Duration::ZERO);
+
+        let result = update(&mut sync, &mut clock, unix(1_709_164_800.0));
+        assert_eq!(result.correction, Some(Correction::Step));
+        assert_eq!(clock.steps, vec![1_709_164_740.0]);
+        assert!(clock.slews.is_empty());
+
+        // slewed from then on
+        let gnss = difference(UNIX_EPOCH, clock.now) + 2.0;
+        let result = update(&mut sync, &mut clock, unix(gnss));
+        assert_eq!(result.correction, Some(Correction::Slew));
+        assert_eq!(clock.steps.len(), 1);
+        assert_eq!(clock.slews.len(), 1);
+        assert!((clock.slews[0] - 2.0).abs() < 1e-3);
+    }
+}
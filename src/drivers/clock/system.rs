@@ -0,0 +1,60 @@
+use std::io;
+use std::ptr;
+use std::time::SystemTime;
+
+use crate::drivers::clock::sync::Clock;
+
+/// Largest offset adjtime() accepts on Linux, in seconds. Larger ones fail
+/// with EINVAL
+const MAX_ADJTIME: f64 = 2145.0;
+
+/// The real time clock of the system. Adjusting it needs CAP_SYS_TIME
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn step(&mut self, offset: f64) -> io::Result<()> {
+        // SAFETY: timespec is plain data, filled by clock_gettime
+        unsafe {
+            let mut ts: libc::timespec = std::mem::zeroed();
+            if libc::clock_gettime(libc::CLOCK_REALTIME, &mut ts) == -1 {
+                return Err(io::Error::last_os_error());
+            }
+
+            let nanos =
+                ts.tv_sec as i128 * 1_000_000_000 + ts.tv_nsec as i128 + (offset * 1e9) as i128;
+            ts.tv_sec = nanos.div_euclid(1_000_000_000) as libc::time_t;
+            ts.tv_nsec = nanos.rem_euclid(1_000_000_000) as libc::c_long;
+
+            if libc::clock_settime(libc::CLOCK_REALTIME, &ts) == -1 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn slew(&mut self, offset: f64) -> io::Result<()> {
+        let micros = (offset * 1e6).round() as i64;
+
+        // SAFETY: timeval is plain data, the previous adjustment is not asked
+        unsafe {
+            let mut delta: libc::timeval = std::mem::zeroed();
+            delta.tv_sec = micros.div_euclid(1_000_000) as libc::time_t;
+            delta.tv_usec = micros.rem_euclid(1_000_000) as libc::suseconds_t;
+
+            if libc::adjtime(&delta, ptr::null_mut()) == -1 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn max_slew(&self) -> f64 {
+        MAX_ADJTIME
+    }
+}
@@ -0,0 +1,722 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use ix_nmea::sentences::DataVDM;
+
+/// Longitude reported when not available, in 1/10000 minutes
+const LON_NOT_AVAILABLE: i32 = 181 * 60 * 10_000;
+/// Latitude reported when not available, in 1/10000 minutes
+const LAT_NOT_AVAILABLE: i32 = 91 * 60 * 10_000;
+/// Speed over ground reported when not available, in 1/10 knots
+const SOG_NOT_AVAILABLE: u32 = 1023;
+/// Course over ground reported when not available, in 1/10 degrees
+const COG_NOT_AVAILABLE: u32 = 3600;
+/// True heading reported when not available, in degrees
+const HEADING_NOT_AVAILABLE: u32 = 511;
+
+/// Message payload re-assembled from one or more fragments
+#[derive(Clone, Debug, PartialEq)]
+pub struct Payload {
+    /// 6-bit armored payload
+    pub data: String,
+    /// Number of padding bits at the end of the payload
+    pub fill_bits: u8,
+}
+
+/// Message being re-assembled
+struct Pending {
+    fragments: u8,
+    next: u8,
+    channel: Option<char>,
+    payload: String,
+}
+
+/// Join the fragments of multi-sentence messages
+#[derive(Default)]
+pub struct Reassembler {
+    /// Messages being re-assembled, by sequential message id
+    pending: HashMap<Option<u8>, Pending>,
+}
+
+impl Reassembler {
+    pub fn new() -> Reassembler {
+        Default::default()
+    }
+
+    /// Feed a fragment. Returns the payload once all fragments of a message
+    /// were received in order. Out of order fragments drop the message
+    pub fn push(&mut self, m: &DataVDM) -> Option<Payload> {
+        if m.fragments == 0 || m.fragment == 0 || m.fragment > m.fragments {
+            return None;
+        }
+
+        if m.fragments == 1 {
+            return Some(Payload {
+                data: m.payload.clone(),
+                fill_bits: m.fill_bits,
+            });
+        }
+
+        if m.fragment == 1 {
+            self.pending.insert(
+                m.sequence,
+                Pending {
+                    fragments: m.fragments,
+                    next: 2,
+                    channel: m.channel,
+                    payload: m.payload.clone(),
+                },
+            );
+            return None;
+        }
+
+        let mut pending = self.pending.remove(&m.sequence)?;
+        if pending.fragments != m.fragments
+            || pending.next != m.fragment
+            || pending.channel != m.channel
+        {
+            return None;
+        }
+
+        pending.payload.push_str(&m.payload);
+        if m.fragment == m.fragments {
+            return Some(Payload {
+                data: pending.payload,
+                fill_bits: m.fill_bits,
+            });
+        }
+
+        pending.next += 1;
+        self.pending.insert(m.sequence, pending);
+        None
+    }
+}
+
+/// Bit level access to a de-armored payload
+struct Bits {
+    /// 6-bit values
+    data: Vec<u8>,
+    /// Number of valid bits
+    len: usize,
+}
+
+impl Bits {
+    fn new(payload: &Payload) -> Option<Bits> {
+        let data = payload
+            .data
+            .bytes()
+            .map(dearmor)
+            .collect::<Option<Vec<u8>>>()?;
+        let len = (data.len() * 6).checked_sub(payload.fill_bits as usize)?;
+
+        Some(Bits { data, len })
+    }
+
+    /// Unsigned integer of the given number of bits (up to 32)
+    fn uint(&self, start: usize, bits: usize) -> Option<u32> {
+        if start + bits > self.len {
+            return None;
+        }
+
+        let mut value = 0u32;
+        for i in start..start + bits {
+            let bit = (self.data[i / 6] >> (5 - i % 6)) & 1;
+            value = (value << 1) | bit as u32;
+        }
+
+        Some(value)
+    }
+
+    /// Two's complement signed integer of the given number of bits
+    fn int(&self, start: usize, bits: usize) -> Option<i32> {
+        let value = self.uint(start, bits)?;
+        let shift = 32 - bits as u32;
+
+        Some(((value << shift) as i32) >> shift)
+    }
+
+    /// Text of the given number of 6-bit characters, without padding
+    fn text(&self, start: usize, chars: usize) -> Option<String> {
+        let mut text = String::with_capacity(chars);
+        for i in 0..chars {
+            let c = self.uint(start + i * 6, 6)? as u8;
+            text.push(if c < 32 { c + 64 } else { c } as char);
+        }
+
+        let text = text.trim_end_matches(|c| c == '@' || c == ' ');
+        Some(String::from(text))
+    }
+}
+
+/// Map an armored payload character to its 6-bit value
+fn dearmor(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'W' => Some(c - 48),
+        b'`'..=b'w' => Some(c - 56),
+        _ => None,
+    }
+}
+
+/// Dynamic data of a class A (types 1, 2 and 3) or class B (type 18) report
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PositionReport {
+    pub mmsi: u32,
+    /// Navigation status, class A only
+    pub nav_status: Option<u8>,
+    /// Latitude in degrees
+    pub lat: Option<f64>,
+    /// Longitude in degrees
+    pub lon: Option<f64>,
+    /// Speed over ground in knots
+    pub sog: Option<f32>,
+    /// Course over ground in degrees
+    pub cog: Option<f32>,
+    /// True heading in degrees
+    pub heading: Option<u16>,
+}
+
+/// Static data of a class A (type 5) or class B (type 24) vessel
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StaticData {
+    pub mmsi: u32,
+    pub name: Option<String>,
+    pub callsign: Option<String>,
+    pub ship_type: Option<u8>,
+    /// Distances from the reference point to bow, stern, port and
+    /// starboard, in meters
+    pub dimensions: Option<[u16; 4]>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message {
+    Position(PositionReport),
+    Static(StaticData),
+}
+
+impl Message {
+    /// Decode a re-assembled payload. Returns None for unsupported or
+    /// truncated messages
+    pub fn decode(payload: &Payload) -> Option<Message> {
+        let bits = Bits::new(payload)?;
+        let mmsi = bits.uint(8, 30)?;
+
+        match bits.uint(0, 6)? {
+            1..=3 => Some(Message::Position(PositionReport {
+                mmsi,
+                nav_status: Some(bits.uint(38, 4)? as u8),
+                sog: sog(bits.uint(50, 10)?),
+                lon: lon(bits.int(61, 28)?),
+                lat: lat(bits.int(89, 27)?),
+                cog: cog(bits.uint(116, 12)?),
+                heading: heading(bits.uint(128, 9)?),
+            })),
+            18 => Some(Message::Position(PositionReport {
+                mmsi,
+                nav_status: None,
+                sog: sog(bits.uint(46, 10)?),
+                lon: lon(bits.int(57, 28)?),
+                lat: lat(bits.int(85, 27)?),
+                cog: cog(bits.uint(112, 12)?),
+                heading: heading(bits.uint(124, 9)?),
+            })),
+            5 => Some(Message::Static(StaticData {
+                mmsi,
+                callsign: not_empty(bits.text(70, 7)?),
+                name: not_empty(bits.text(112, 20)?),
+                ship_type: Some(bits.uint(232, 8)? as u8),
+                dimensions: Some([
+                    bits.uint(240, 9)? as u16,
+                    bits.uint(249, 9)? as u16,
+                    bits.uint(258, 6)? as u16,
+                    bits.uint(264, 6)? as u16,
+                ]),
+            })),
+            // part A carries the name, part B the remaining data
+            24 => match bits.uint(38, 2)? {
+                0 => Some(Message::Static(StaticData {
+                    mmsi,
+                    name: not_empty(bits.text(40, 20)?),
+                    ..Default::default()
+                })),
+                1 => Some(Message::Static(StaticData {
+                    mmsi,
+                    ship_type: Some(bits.uint(40, 8)? as u8),
+                    callsign: not_empty(bits.text(90, 7)?),
+                    dimensions: Some([
+                        bits.uint(132, 9)? as u16,
+                        bits.uint(141, 9)? as u16,
+                        bits.uint(150, 6)? as u16,
+                        bits.uint(156, 6)? as u16,
+                    ]),
+                    ..Default::default()
+                })),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    pub fn mmsi(&self) -> u32 {
+        match self {
+            Message::Position(m) => m.mmsi,
+            Message::Static(m) => m.mmsi,
+        }
+    }
+}
+
+fn lat(value: i32) -> Option<f64> {
+    if value == LAT_NOT_AVAILABLE {
+        None
+    } else {
+        Some(value as f64 / 600_000.0)
+    }
+}
+
+fn lon(value: i32) -> Option<f64> {
+    if value == LON_NOT_AVAILABLE {
+        None
+    } else {
+        Some(value as f64 / 600_000.0)
+    }
+}
+
+fn sog(value: u32) -> Option<f32> {
+    if value == SOG_NOT_AVAILABLE {
+        None
+    } else {
+        Some(value as f32 / 10.0)
+    }
+}
+
+fn cog(value: u32) -> Option<f32> {
+    if value >= COG_NOT_AVAILABLE {
+        None
+    } else {
+        Some(value as f32 / 10.0)
+    }
+}
+
+fn heading(value: u32) -> Option<u16> {
+    if value == HEADING_NOT_AVAILABLE {
+        None
+    } else {
+        Some(value as u16)
+    }
+}
+
+fn not_empty(text: String) -> Option<String> {
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+/// Vessel known from its AIS reports
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Target {
+    pub mmsi: u32,
+    pub position: PositionReport,
+    pub info: StaticData,
+}
+
+impl Target {
+    /// Whether the target's position is known
+    pub fn has_position(&self) -> bool {
+        self.position.lat.is_some() && self.position.lon.is_some()
+    }
+}
+
+/// Latest known state of every vessel, by MMSI
+#[derive(Default)]
+pub struct Targets {
+    targets: HashMap<u32, Known>,
+}
+
+/// Target and when it was last heard from
+struct Known {
+    target: Target,
+    heard: Instant,
+}
+
+impl Targets {
+    pub fn new() -> Targets {
+        Default::default()
+    }
+
+    /// Merge a message received at the given time into its vessel's state
+    /// and return the updated target
+    pub fn update(&mut self, msg: Message, now: Instant) -> &Target {
+        let mmsi = msg.mmsi();
+        let known = self.targets.entry(mmsi).or_insert_with(|| Known {
+            target: Target {
+                mmsi,
+                ..Default::default()
+            },
+            heard: now,
+        });
+        known.heard = now;
+
+        let target = &mut known.target;
+        match msg {
+            Message::Position(m) => target.position = m,
+            Message::Static(m) => {
+                // class B static data comes in two parts
+                let info = &mut target.info;
+                info.mmsi = m.mmsi;
+                info.name = m.name.or(info.name.take());
+                info.callsign = m.callsign.or(info.callsign.take());
+                info.ship_type = m.ship_type.or(info.ship_type);
+                info.dimensions = m.dimensions.or(info.dimensions);
+            }
+        }
+
+        target
+    }
+
+    /// Forget the vessels not heard from within the given age. Returns the
+    /// number of vessels forgotten
+    pub fn prune(&mut self, max_age: Duration, now: Instant) -> usize {
+        let before = self.targets.len();
+        self.targets
+            .retain(|_, known| now.saturating_duration_since(known.heard) <= max_age);
+
+        before - self.targets.len()
+    }
+
+    pub fn get(&self, mmsi: u32) -> Option<&Target> {
+        self.targets.get(&mmsi).map(|known| &known.target)
+    }
+
+    pub fn len(&self) -> usize {
+        self.targets.len()
+    }
+}
+
+mod tests {
+    use super::*;
+
+    /// Fragment with the given numbering and payload
+    fn fragment(fragments: u8, fragment: u8, sequence: Option<u8>, payload: &str) -> DataVDM {
+        DataVDM {
+            fragments,
+            fragment,
+            sequence,
+            channel: Some('A'),
+            payload: String::from(payload),
+            fill_bits: 0,
+            ..Default::default()
+        }
+    }
+
+    /// Armor the given (value, bits) fields (up to 32 bits each), padding to
+    /// a 6-bit boundary
+    fn armor(fields: &[(u32, usize)]) -> Payload {
+        let mut bits: Vec<u8> = vec![];
+        for (value, len) in fields {
+            for i in (0..*len).rev() {
+                bits.push(((value >> i) & 1) as u8);
+            }
+        }
+
+        let fill_bits = (6 - bits.len() % 6) % 6;
+        bits.resize(bits.len() + fill_bits, 0);
+
+        let data = bits
+            .chunks(6)
+            .map(|c| c.iter().fold(0, |v, b| (v << 1) | b))
+            .map(|v| if v < 40 { v + 48 } else { v + 56 } as char)
+            .collect();
+
+        Payload {
+            data,
+            fill_bits: fill_bits as u8,
+        }
+    }
+
+    /// Text as (value, bits) fields of 6-bit characters, padded with '@'
+    fn text(s: &str, chars: usize) -> Vec<(u32, usize)> {
+        let mut fields: Vec<(u32, usize)> = s.bytes().map(|c| ((c & 0x3F) as u32, 6)).collect();
+        fields.resize(chars, (0, 6));
+        fields
+    }
+
+    #[test]
+    fn dearmoring() {
+        assert_eq!(dearmor(b'0'), Some(0));
+        assert_eq!(dearmor(b'W'), Some(39));
+        assert_eq!(dearmor(b'`'), Some(40));
+        assert_eq!(dearmor(b'w'), Some(63));
+        assert_eq!(dearmor(b'X'), None);
+        assert_eq!(dearmor(b'x'), None);
+    }
+
+    #[test]
+    fn single_fragment() {
+        let mut reassembler = Reassembler::new();
+
+        let payload = reassembler.push(&fragment(1, 1, None, "177KQJ5000G?tO`K>RA1wUbN0TKH"));
+        assert_eq!(payload.unwrap().data, "177KQJ5000G?tO`K>RA1wUbN0TKH");
+    }
+
+    #[test]
+    fn multiple_fragments() {
+        let mut reassembler = Reassembler::new();
+
+        assert_eq!(reassembler.push(&fragment(3, 1, Some(4), "AB")), None);
+        // fragments of another message are not mixed
+        assert_eq!(reassembler.push(&fragment(2, 1, Some(5), "XY")), None);
+        assert_eq!(reassembler.push(&fragment(3, 2, Some(4), "CD")), None);
+
+        let mut last = fragment(3, 3, Some(4), "EF");
+        last.fill_bits = 2;
+        assert_eq!(
+            reassembler.push(&last),
+            Some(Payload {
+                data: String::from("ABCDEF"),
+                fill_bits: 2
+            })
+        );
+
+        assert_eq!(
+            reassembler
+                .push(&fragment(2, 2, Some(5), "Z"))
+                .unwrap()
+                .data,
+            "XYZ"
+        );
+    }
+
+    #[test]
+    fn missing_fragment() {
+        let mut reassembler = Reassembler::new();
+
+        assert_eq!(reassembler.push(&fragment(3, 1, Some(1), "AB")), None);
+        assert_eq!(reassembler.push(&fragment(3, 3, Some(1), "EF")), None);
+        // message was dropped
+        assert_eq!(reassembler.push(&fragment(3, 2, Some(1), "CD")), None);
+        assert_eq!(reassembler.push(&fragment(3, 3, Some(1), "EF")), None);
+
+        assert_eq!(reassembler.push(&fragment(0, 1, None, "AB")), None);
+        assert_eq!(reassembler.push(&fragment(1, 2, None, "AB")), None);
+    }
+
+    #[test]
+    fn position_report_class_a() {
+        let payload = Payload {
+            data: String::from("177KQJ5000G?tO`K>RA1wUbN0TKH"),
+            fill_bits: 0,
+        };
+
+        let msg = match Message::decode(&payload) {
+            Some(Message::Position(m)) => m,
+            m => panic!("unexpected {:?}", m),
+        };
+
+        assert_eq!(msg.mmsi, 477553000);
+        assert_eq!(msg.nav_status, Some(5));
+        assert_eq!(msg.sog, Some(0.0));
+        assert!((msg.lon.unwrap() - -122.345833).abs() < 1e-6);
+        assert!((msg.lat.unwrap() - 47.582833).abs() < 1e-6);
+        assert_eq!(msg.cog, Some(51.0));
+        assert_eq!(msg.heading, Some(181));
+    }
+
+    #[test]
+    fn position_report_class_b() {
+        let payload = armor(&[
+            (18, 6),
+            (0, 2),
+            (338087471, 30),
+            (0, 8),
+            (15, 10),
+            (1, 1),
+            ((-44443000i32) as u32 & 0x0FFF_FFFF, 28),
+            (24410700, 27),
+            (COG_NOT_AVAILABLE, 12),
+            (HEADING_NOT_AVAILABLE, 9),
+            (20, 6),
+            (0, 29),
+        ]);
+
+        let msg = match Message::decode(&payload) {
+            Some(Message::Position(m)) => m,
+            m => panic!("unexpected {:?}", m),
+        };
+
+        assert_eq!(msg.mmsi, 338087471);
+        assert_eq!(msg.nav_status, None);
+        assert_eq!(msg.sog, Some(1.5));
+        assert!((msg.lon.unwrap() - -74.071667).abs() < 1e-6);
+        assert!((msg.lat.unwrap() - 40.6845).abs() < 1e-6);
+        assert_eq!(msg.cog, None);
+        assert_eq!(msg.heading, None);
+    }
+
+    #[test]
+    fn position_not_available() {
+        let payload = armor(&[
+            (1, 6),
+            (0, 2),
+            (123456789, 30),
+            (15, 4),
+            (0x80, 8),
+            (SOG_NOT_AVAILABLE, 10),
+            (0, 1),
+            (LON_NOT_AVAILABLE as u32, 28),
+            (LAT_NOT_AVAILABLE as u32, 27),
+            (COG_NOT_AVAILABLE, 12),
+            (HEADING_NOT_AVAILABLE, 9),
+            (60, 6),
+            (0, 25),
+        ]);
+
+        assert_eq!(
+            Message::decode(&payload),
+            Some(Message::Position(PositionReport {
+                mmsi: 123456789,
+                nav_status: Some(15),
+                ..Default::default()
+            }))
+        );
+    }
+
+    #[test]
+    fn static_data_class_a() {
+        let mut reassembler = Reassembler::new();
+
+        let first = fragment(
+            2,
+            1,
+            Some(1),
+            "55?MbV02;H;s<HtKR20EHE:0@T4@Dn2222222216L961O5Gf0NSQEp6ClRp8",
+        );
+        let mut second = fragment(2, 2, Some(1), "88888888880");
+        second.fill_bits = 2;
+
+        assert_eq!(reassembler.push(&first), None);
+        let payload = reassembler.push(&second).unwrap();
+
+        assert_eq!(
+            Message::decode(&payload),
+            Some(Message::Static(StaticData {
+                mmsi: 351759000,
+                name: Some(String::from("EVER DIADEM")),
+                callsign: Some(String::from("3FOF8")),
+                ship_type: Some(70),
+                dimensions: Some([225, 70, 1, 31]),
+            }))
+        );
+    }
+
+    #[test]
+    fn static_data_class_b() {
+        let mut part_a = vec![(24, 6), (0, 2), (271041815, 30), (0, 2)];
+        part_a.extend(text("PROGUY", 20));
+        part_a.push((0, 8));
+
+        let mut part_b = vec![(24, 6), (0, 2), (271041815, 30), (1, 2), (60, 8)];
+        part_b.extend(text("1D00014", 7));
+        part_b.extend(text("TC6163", 7));
+        part_b.extend([(10, 9), (5, 9), (2, 6), (3, 6), (0, 6)]);
+
+        let mut targets = Targets::new();
+        for payload in [armor(&part_a), armor(&part_b)] {
+            targets.update(Message::decode(&payload).unwrap(), Instant::now());
+        }
+
+        let target = targets.get(271041815).unwrap();
+        assert_eq!(
+            target.info,
+            StaticData {
+                mmsi: 271041815,
+                name: Some(String::from("PROGUY")),
+                callsign: Some(String::from("TC6163")),
+                ship_type: Some(60),
+                dimensions: Some([10, 5, 2, 3]),
+            }
+        );
+        assert!(!target.has_position());
+    }
+
+    #[test]
+    fn unsupported_message() {
+        // base station report
+        let payload = armor(&[(4, 6), (0, 2), (3669702, 30), (0, 32), (0, 32), (0, 32)]);
+        assert_eq!(Message::decode(&payload), None);
+
+        // truncated position report
+        let payload = armor(&[(1, 6), (0, 2), (123456789, 30), (0, 20)]);
+        assert_eq!(Message::decode(&payload), None);
+
+        // invalid armoring
+        let payload = Payload {
+            data: String::from("1xx"),
+            fill_bits: 0,
+        };
+        assert_eq!(Message::decode(&payload), None);
+    }
+
+    #[test]
+    fn targets() {
+        let mut targets = Targets::new();
+
+        let position = PositionReport {
+            mmsi: 1,
+            lat: Some(41.0),
+            lon: Some(-8.0),
+            ..Default::default()
+        };
+        let now = Instant::now();
+        let target = targets.update(Message::Position(position.clone()), now);
+        assert!(target.has_position());
+        assert_eq!(target.info.name, None);
+
+        targets.update(
+            Message::Static(StaticData {
+                mmsi: 1,
+                name: Some(String::from("NAVIO")),
+                ..Default::default()
+            }),
+            now,
+        );
+        targets.update(
+            Message::Position(PositionReport {
+                mmsi: 2,
+                ..Default::default()
+            }),
+            now,
+        );
+
+        assert_eq!(targets.len(), 2);
+        let target = targets.get(1).unwrap();
+        assert_eq!(target.position, position);
+        assert_eq!(target.info.name.as_deref(), Some("NAVIO"));
+    }
+
+    #[test]
+    fn prune() {
+        let mut targets = Targets::new();
+        let start = Instant::now();
+        let position = |mmsi| {
+            Message::Position(PositionReport {
+                mmsi,
+                ..Default::default()
+            })
+        };
+
+        targets.update(position(1), start);
+        targets.update(position(2), start);
+        // heard again later
+        targets.update(position(1), start + Duration::from_secs(300));
+
+        let max_age = Duration::from_secs(360);
+        assert_eq!(targets.prune(max_age, start + Duration::from_secs(360)), 0);
+        assert_eq!(targets.prune(max_age, start + Duration::from_secs(400)), 1);
+        assert!(targets.get(1).is_some());
+        assert!(targets.get(2).is_none());
+
+        assert_eq!(targets.prune(max_age, start + Duration::from_secs(700)), 1);
+        assert_eq!(targets.len(), 0);
+    }
+}
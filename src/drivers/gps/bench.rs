@@ -0,0 +1,263 @@
+//! Throughput benchmarks of the NMEA parser against the previous char/String
+//! based implementation. Run with:
+//!
+//! cargo test --release bench -- --ignored --nocapture
+
+use std::time::{Duration, Instant};
+
+use ix_nmea as nmea;
+
+/// Sentences fed to both parsers, all supported by the legacy implementation
+const SENTENCES: [&str; 3] = [
+    "$GPGGA,202530.00,5109.0262,N,11401.8407,W,5,40,0.5,1097.36,M,-17.00,M,18,TSTR*61\r\n",
+    "$GPVTG,054.7,T,034.4,M,005.5,N,010.2,K,A*25\r\n",
+    "$GNGGA,092725.00,4717.11399,N,00833.91590,E,1,08,1.01,499.6,M,48.0,M,,*45\r\n",
+];
+
+/// Number of times the input is parsed
+const ITERATIONS: usize = 20_000;
+
+fn input() -> Vec<u8> {
+    SENTENCES.concat().into_bytes()
+}
+
+fn report(name: &str, bytes: usize, sentences: usize, elapsed: Duration) {
+    let secs = elapsed.as_secs_f64();
+    println!(
+        "{:>8}: {:>8.2} MB/s, {:>10.0} sentences/s",
+        name,
+        bytes as f64 / secs / 1e6,
+        sentences as f64 / secs
+    );
+}
+
+#[test]
+#[ignore]
+fn bench_throughput() {
+    let data = input();
+    let total = data.len() * ITERATIONS;
+
+    let mut parser = nmea::Parser::new();
+    let mut parsed = 0;
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        for b in &data {
+            if let Ok(Some(_)) = parser.push(*b) {
+                parsed += 1;
+            }
+        }
+    }
+    report("bytes", total, parsed, start.elapsed());
+    assert_eq!(parsed, SENTENCES.len() * ITERATIONS);
+
+    let mut parser = legacy::Parser::new();
+    let mut parsed = 0;
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        for b in &data {
+            let c = *b as char;
+            if c == '\n' {
+                parser.reset();
+                continue;
+            }
+            if parser.push(c) {
+                parsed += 1;
+            }
+        }
+    }
+    report("legacy", total, parsed, start.elapsed());
+    assert_eq!(parsed, SENTENCES.len() * ITERATIONS);
+}
+
+/// Previous parser implementation, kept as a baseline. The E/W and VTG 'T'
+/// field fixes are applied so that both parsers do the same amount of work
+mod legacy {
+    use std::io::BufRead;
+
+    #[derive(Clone, Default)]
+    pub struct DataGGA {
+        pub utc_time: Option<f64>,
+        pub lat: Option<f64>,
+        pub ns: Option<String>,
+        pub lon: Option<f64>,
+        pub ew: Option<String>,
+        pub validity: u8,
+        pub sat: Option<u8>,
+        pub hdop: Option<f32>,
+        pub alt: Option<f32>,
+        pub units: Option<String>,
+    }
+
+    #[derive(Clone, Default)]
+    pub struct DataVTG {
+        pub cog_true: Option<f32>,
+        pub cog_magnetic: Option<f32>,
+        pub sog_knots: Option<f32>,
+        pub sog_kph: Option<f32>,
+        pub mode: Option<String>,
+    }
+
+    #[derive(Clone)]
+    pub enum Sentence {
+        Invalid,
+        GGA(DataGGA),
+        VTG(DataVTG),
+    }
+
+    struct FieldReader {
+        data: std::io::Cursor<String>,
+        sep: char,
+    }
+
+    impl FieldReader {
+        fn new(data_str: String, separator: char) -> FieldReader {
+            FieldReader {
+                data: std::io::Cursor::new(data_str),
+                sep: separator,
+            }
+        }
+
+        fn skip(&mut self) -> bool {
+            let mut bfr: Vec<u8> = vec![];
+            self.data.read_until(self.sep as u8, &mut bfr).is_ok() && !bfr.is_empty()
+        }
+
+        fn read_optional<T: std::str::FromStr>(&mut self) -> Result<Option<T>, ()> {
+            let mut bfr: Vec<u8> = vec![];
+            match self.data.read_until(self.sep as u8, &mut bfr) {
+                Ok(0) => Ok(None),
+                Ok(_) => {
+                    let mut data_str = String::from_utf8(bfr).map_err(|_| ())?;
+                    if data_str.contains(self.sep) {
+                        data_str.pop();
+                    }
+                    data_str.parse::<T>().map(Some).map_err(|_| ())
+                }
+                Err(_) => Err(()),
+            }
+        }
+
+        fn read<T: std::str::FromStr>(&mut self) -> Result<T, ()> {
+            self.read_optional()?.ok_or(())
+        }
+    }
+
+    fn parse_fields(s: &mut Sentence, fields_str: String) -> bool {
+        let mut fin = FieldReader::new(fields_str, ',');
+        match s {
+            Sentence::GGA(m) => (|| -> Result<(), ()> {
+                m.utc_time = fin.read_optional()?;
+                m.lat = fin.read_optional()?;
+                m.ns = fin.read_optional()?;
+                m.lon = fin.read_optional()?;
+                m.ew = fin.read_optional()?;
+                m.validity = fin.read()?;
+                m.sat = fin.read_optional()?;
+                m.hdop = fin.read_optional()?;
+                m.alt = fin.read_optional()?;
+                m.units = fin.read_optional()?;
+                Ok(())
+            })()
+            .is_ok(),
+            Sentence::VTG(m) => (|| -> Result<(), ()> {
+                m.cog_true = fin.read_optional()?;
+                fin.skip();
+                m.cog_magnetic = fin.read_optional()?;
+                fin.skip();
+                m.sog_knots = fin.read_optional()?;
+                fin.skip();
+                m.sog_kph = fin.read_optional()?;
+                fin.skip();
+                m.mode = fin.read_optional()?;
+                Ok(())
+            })()
+            .is_ok(),
+            Sentence::Invalid => false,
+        }
+    }
+
+    #[derive(PartialEq)]
+    enum Field {
+        Sync,
+        Id,
+        Data,
+        Checksum,
+    }
+
+    pub struct Parser {
+        field: Field,
+        sntc: Sentence,
+        bfr: String,
+        checksum: i32,
+    }
+
+    impl Parser {
+        pub fn new() -> Parser {
+            Parser {
+                field: Field::Sync,
+                sntc: Sentence::Invalid,
+                bfr: String::from(""),
+                checksum: 0,
+            }
+        }
+
+        pub fn reset(&mut self) {
+            self.field = Field::Sync;
+            self.sntc = Sentence::Invalid;
+            self.bfr = String::from("");
+            self.checksum = 0;
+        }
+
+        /// Returns true when a sentence was successfully parsed
+        pub fn push(&mut self, c: char) -> bool {
+            match self.field {
+                Field::Sync => {
+                    self.reset();
+                    if c == '$' {
+                        self.field = Field::Id;
+                    }
+                }
+                Field::Id => {
+                    self.checksum ^= c as i32;
+                    if c == ',' {
+                        self.sntc = if self.bfr.ends_with("GGA") {
+                            Sentence::GGA(Default::default())
+                        } else if self.bfr.ends_with("VTG") {
+                            Sentence::VTG(Default::default())
+                        } else {
+                            Sentence::Invalid
+                        };
+                        self.field = Field::Data;
+                        self.bfr.clear();
+                    } else {
+                        self.bfr.push(c);
+                    }
+                }
+                Field::Data => {
+                    if c == '*' {
+                        if !parse_fields(&mut self.sntc, self.bfr.clone()) {
+                            self.reset();
+                            return false;
+                        }
+                        self.field = Field::Checksum;
+                        self.bfr.clear();
+                    } else {
+                        self.bfr.push(c);
+                        self.checksum ^= c as i32;
+                    }
+                }
+                Field::Checksum => {
+                    self.bfr.push(c);
+                    if self.bfr.len() == 2 {
+                        let ok = i32::from_str_radix(self.bfr.as_str(), 16) == Ok(self.checksum);
+                        let _sentence = self.sntc.clone();
+                        self.reset();
+                        return ok;
+                    }
+                }
+            }
+
+            false
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use ix_nmea::MAX_SENTENCE_LEN;
+
+/// Longest line kept. Lines above it are dropped up to the next terminator
+/// or start delimiter ('$' or '!')
+pub const MAX_LINE_LEN: usize = MAX_SENTENCE_LEN;
+
+/// Outcome of feeding a byte to the framer
+#[derive(Clone, Debug, PartialEq)]
+pub enum Frame {
+    /// Byte belongs to the current line and should be parsed
+    Data,
+    /// Line terminated, without its terminator. Empty lines, e.g. the '\n'
+    /// of a "\r\n" terminator, are not reported
+    Line(String),
+    /// Line went over MAX_LINE_LEN, its bytes are dropped from now on
+    Overflow,
+    /// Byte dropped: part of an overlong line or terminator of an empty one
+    Discarded,
+}
+
+/// Split a byte stream into lines terminated by '\r', '\n' or "\r\n". A
+/// partial line is kept until its terminator arrives, whatever the number
+/// of reads it is split across
+#[derive(Default)]
+pub struct LineFramer {
+    line: Vec<u8>,
+    /// Dropping the bytes of an overlong line
+    discarding: bool,
+}
+
+impl LineFramer {
+    pub fn new() -> LineFramer {
+        Default::default()
+    }
+
+    /// Bytes of the line received so far
+    pub fn pending(&self) -> &[u8] {
+        &self.line
+    }
+
+    /// Feed the next byte
+    pub fn push(&mut self, b: u8) -> Frame {
+        if b == b'\r' || b == b'\n' {
+            let discarded = self.discarding;
+            self.discarding = false;
+
+            if discarded || self.line.is_empty() {
+                self.line.clear();
+                return Frame::Discarded;
+            }
+
+            let line = String::from_utf8_lossy(&self.line).into_owned();
+            self.line.clear();
+            return Frame::Line(line);
+        }
+
+        // a new sentence ends an unterminated or overlong line
+        if b == b'$' || b == b'!' {
+            self.line.clear();
+            self.discarding = false;
+        }
+
+        if self.discarding {
+            return Frame::Discarded;
+        }
+
+        if self.line.len() == MAX_LINE_LEN {
+            self.line.clear();
+            self.discarding = true;
+            return Frame::Overflow;
+        }
+
+        self.line.push(b);
+        Frame::Data
+    }
+}
+
+mod tests {
+    use super::*;
+    use ix_nmea::{Parser, Sentence};
+
+    const GGA: &str =
+        "$GPGGA,202530.00,5109.0262,N,11401.8407,W,5,40,0.5,1097.36,M,-17.00,M,18,TSTR*61";
+    const VTG: &str = "$GPVTG,,T,,M,0.00,N,0.00,K,N*2C";
+
+    /// Feed the stream in chunks of the given size, as successive reads
+    /// would, returning the parsed sentences and the completed lines
+    fn feed(stream: &[u8], chunk: usize) -> (Vec<Sentence>, Vec<String>) {
+        let mut framer = LineFramer::new();
+        let mut parser = Parser::new();
+        let (mut sentences, mut lines) = (vec![], vec![]);
+
+        for read in stream.chunks(chunk) {
+            for b in read {
+                match framer.push(*b) {
+                    Frame::Data => {
+                        if let Ok(Some(sentence)) = parser.push(*b) {
+                            sentences.push(sentence);
+                        }
+                    }
+                    Frame::Line(line) => {
+                        lines.push(line);
+                        parser.reset();
+                    }
+                    Frame::Overflow => parser.reset(),
+                    Frame::Discarded => {}
+                }
+            }
+        }
+
+        (sentences, lines)
+    }
+
+    #[test]
+    fn terminators() {
+        for terminator in ["\r\n", "\n", "\r"] {
+            let stream = format!("{}{}{}{}", GGA, terminator, VTG, terminator);
+            let (sentences, lines) = feed(stream.as_bytes(), stream.len());
+
+            assert_eq!(sentences.len(), 2, "{:?}", terminator);
+            assert_eq!(lines, vec![GGA, VTG]);
+        }
+    }
+
+    #[test]
+    fn chunked() {
+        let stream = format!("{}\r\n{}\r\n", GGA, VTG);
+        for chunk in 1..stream.len() {
+            let (sentences, lines) = feed(stream.as_bytes(), chunk);
+
+            assert!(matches!(sentences[0], Sentence::GGA(_)), "chunk {}", chunk);
+            assert!(matches!(sentences[1], Sentence::VTG(_)), "chunk {}", chunk);
+            assert_eq!(lines, vec![GGA, VTG]);
+        }
+    }
+
+    #[test]
+    fn partial() {
+        let mut framer = LineFramer::new();
+        for b in &GGA.as_bytes()[..20] {
+            assert_eq!(framer.push(*b), Frame::Data);
+        }
+        assert_eq!(framer.pending(), &GGA.as_bytes()[..20]);
+
+        for b in &GGA.as_bytes()[20..] {
+            assert_eq!(framer.push(*b), Frame::Data);
+        }
+        assert_eq!(framer.push(b'\r'), Frame::Line(String::from(GGA)));
+        assert_eq!(framer.push(b'\n'), Frame::Discarded);
+        assert!(framer.pending().is_empty());
+    }
+
+    #[test]
+    fn overlong() {
+        let garbage = "A".repeat(2 * MAX_LINE_LEN);
+
+        // recover on the next terminator
+        let stream = format!("{}\r\n{}\r\n", garbage, VTG);
+        let (sentences, lines) = feed(stream.as_bytes(), 64);
+        assert_eq!(sentences.len(), 1);
+        assert_eq!(lines, vec![VTG]);
+
+        // recover on the next start delimiter, terminator lost
+        let stream = format!("{}{}{}\r\n", garbage, GGA, VTG);
+        let (sentences, lines) = feed(stream.as_bytes(), 64);
+        assert_eq!(sentences.len(), 2);
+        assert_eq!(lines, vec![VTG]);
+
+        let mut framer = LineFramer::new();
+        let results: Vec<Frame> = garbage.bytes().map(|b| framer.push(b)).collect();
+        assert_eq!(results[MAX_LINE_LEN], Frame::Overflow);
+        assert_eq!(results.iter().filter(|f| **f == Frame::Overflow).count(), 1);
+    }
+}
@@ -0,0 +1,374 @@
+use std::fmt;
+use std::fmt::Formatter;
+use std::time::UNIX_EPOCH;
+
+use imc::GpsFix;
+
+use crate::drivers::clock::sync::gnss_time;
+
+/// Mean Earth radius, in meters
+const EARTH_RADIUS: f64 = 6_371_000.0;
+
+/// GpsFix types, as accepted by the quality gate
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FixType {
+    Standalone,
+    Differential,
+    DeadReckoning,
+    ManualInput,
+    Simulation,
+}
+
+impl FixType {
+    /// Type of an IMC GpsFix
+    pub fn from_imc(value: u8) -> Option<FixType> {
+        use imc::messages::GpsFix::TypeEnum;
+
+        match value {
+            v if v == TypeEnum::GFT_STANDALONE as u8 => Some(FixType::Standalone),
+            v if v == TypeEnum::GFT_DIFFERENTIAL as u8 => Some(FixType::Differential),
+            v if v == TypeEnum::GFT_DEAD_RECKONING as u8 => Some(FixType::DeadReckoning),
+            v if v == TypeEnum::GFT_MANUAL_INPUT as u8 => Some(FixType::ManualInput),
+            v if v == TypeEnum::GFT_SIMULATION as u8 => Some(FixType::Simulation),
+            _ => None,
+        }
+    }
+}
+
+/// What happens to fixes failing the gate
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum GateAction {
+    /// Publish them with an invalid position
+    #[default]
+    Mark,
+    /// Do not publish them
+    Drop,
+}
+
+/// Quality requirements of a fix. None disables a check
+#[derive(Clone, Debug, PartialEq)]
+pub struct Limits {
+    /// Not checked on fixes without a satellite count, e.g. from registered
+    /// sentences
+    pub min_satellites: Option<u8>,
+    pub max_hdop: Option<f32>,
+    pub fix_types: Vec<FixType>,
+    /// In m/s, between consecutive accepted fixes. Not checked on fixes
+    /// without a valid time
+    pub max_speed: Option<f32>,
+}
+
+/// No check at all, every fix type accepted
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            min_satellites: None,
+            max_hdop: None,
+            fix_types: vec![
+                FixType::Standalone,
+                FixType::Differential,
+                FixType::DeadReckoning,
+                FixType::ManualInput,
+                FixType::Simulation,
+            ],
+            max_speed: None,
+        }
+    }
+}
+
+/// Reason a fix was rejected
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rejection {
+    Satellites(u8),
+    Hdop(f32),
+    FixType(u8),
+    /// Speed implied by the distance to the previous accepted fix, in m/s
+    Speed(f64),
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Rejection::Satellites(n) => write!(f, "{} satellites in use", n),
+            Rejection::Hdop(hdop) => write!(f, "HDOP {:.1}", hdop),
+            Rejection::FixType(t) => write!(f, "fix type {}", t),
+            Rejection::Speed(speed) => write!(f, "implied speed {:.1} m/s", speed),
+        }
+    }
+}
+
+/// Rejected fixes, per reason. Published as text whenever they change
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Rejections {
+    pub satellites: u64,
+    pub hdop: u64,
+    pub fix_type: u64,
+    pub speed: u64,
+}
+
+impl Rejections {
+    pub fn total(&self) -> u64 {
+        self.satellites + self.hdop + self.fix_type + self.speed
+    }
+}
+
+impl fmt::Display for Rejections {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "rejected fixes: satellites {}, HDOP {}, fix type {}, speed {}",
+            self.satellites, self.hdop, self.fix_type, self.speed
+        )
+    }
+}
+
+/// UTC time of a fix. The day is unknown without a valid date
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Epoch {
+    day: Option<i64>,
+    /// Since midnight
+    seconds: f64,
+}
+
+impl Epoch {
+    fn of(fix: &GpsFix) -> Option<Epoch> {
+        if let Some(time) = gnss_time(fix) {
+            let seconds = time.duration_since(UNIX_EPOCH).ok()?.as_secs_f64();
+            return Some(Epoch {
+                day: Some((seconds / 86400.0).floor() as i64),
+                seconds: seconds.rem_euclid(86400.0),
+            });
+        }
+
+        let time = imc::messages::GpsFix::ValidityBits::GFV_VALID_TIME as u16;
+        if fix._validity & time != 0 {
+            Some(Epoch {
+                day: None,
+                seconds: fix._utc_time as f64,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Seconds elapsed since an earlier epoch. Without both dates, less than
+    /// a day is assumed to have passed, e.g. over midnight
+    fn since(&self, earlier: &Epoch) -> f64 {
+        match (self.day, earlier.day) {
+            (Some(day), Some(earlier_day)) => {
+                (day - earlier_day) as f64 * 86400.0 + self.seconds - earlier.seconds
+            }
+            _ => (self.seconds - earlier.seconds).rem_euclid(86400.0),
+        }
+    }
+}
+
+/// Reject fixes of poor quality and position outliers, e.g. multipath jumps
+pub struct Gate {
+    limits: Limits,
+    /// Last accepted position, in radians, and the time of its fix
+    last: Option<(f64, f64, Option<Epoch>)>,
+    rejections: Rejections,
+}
+
+impl Gate {
+    pub fn new(limits: Limits) -> Gate {
+        Gate {
+            limits,
+            last: None,
+            rejections: Default::default(),
+        }
+    }
+
+    pub fn rejections(&self) -> &Rejections {
+        &self.rejections
+    }
+
+    /// Check a fix with a valid position
+    pub fn check(&mut self, fix: &GpsFix) -> Result<(), Rejection> {
+        let epoch = Epoch::of(fix);
+        let result = self.evaluate(fix, epoch);
+
+        match result {
+            Ok(()) => self.last = Some((fix._lat, fix._lon, epoch)),
+            Err(Rejection::Satellites(_)) => self.rejections.satellites += 1,
+            Err(Rejection::Hdop(_)) => self.rejections.hdop += 1,
+            Err(Rejection::FixType(_)) => self.rejections.fix_type += 1,
+            Err(Rejection::Speed(_)) => self.rejections.speed += 1,
+        }
+
+        result
+    }
+
+    fn evaluate(&self, fix: &GpsFix, epoch: Option<Epoch>) -> Result<(), Rejection> {
+        let limits = &self.limits;
+
+        let accepted = FixType::from_imc(fix._type).is_some_and(|t| limits.fix_types.contains(&t));
+        if !accepted {
+            return Err(Rejection::FixType(fix._type));
+        }
+
+        // 0 when no sentence reported the count
+        if let (Some(min), true) = (limits.min_satellites, fix._satellites > 0) {
+            if fix._satellites < min {
+                return Err(Rejection::Satellites(fix._satellites));
+            }
+        }
+
+        let hdop_valid =
+            fix._validity & (imc::messages::GpsFix::ValidityBits::GFV_VALID_HDOP as u16) != 0;
+        if let (Some(max), true) = (limits.max_hdop, hdop_valid) {
+            if fix._hdop > max {
+                return Err(Rejection::Hdop(fix._hdop));
+            }
+        }
+
+        // a genuine jump ends up accepted, the implied speed dropping as
+        // time goes by
+        if let (Some(max), Some((lat, lon, Some(last))), Some(epoch)) =
+            (limits.max_speed, self.last, epoch)
+        {
+            let elapsed = epoch.since(&last);
+            if elapsed > 0.0 {
+                let speed = distance(lat, lon, fix._lat, fix._lon) / elapsed;
+                if speed > max as f64 {
+                    return Err(Rejection::Speed(speed));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Great circle distance between two positions given in radians, in meters
+fn distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let a = ((lat2 - lat1) / 2.0).sin().powi(2)
+        + lat1.cos() * lat2.cos() * ((lon2 - lon1) / 2.0).sin().powi(2);
+
+    2.0 * EARTH_RADIUS * a.sqrt().asin()
+}
+
+mod tests {
+    use super::*;
+
+    fn limits() -> Limits {
+        Limits {
+            min_satellites: Some(4),
+            max_hdop: Some(5.0),
+            fix_types: vec![FixType::Standalone, FixType::Differential],
+            max_speed: Some(20.0),
+        }
+    }
+
+    /// Fix at the given time of day, in seconds
+    fn fix(lat: f64, lon: f64, time: f32) -> GpsFix {
+        let mut fix = GpsFix::new();
+        fix._type = imc::messages::GpsFix::TypeEnum::GFT_STANDALONE as u8;
+        fix._validity = imc::messages::GpsFix::ValidityBits::GFV_VALID_POS as u16
+            | imc::messages::GpsFix::ValidityBits::GFV_VALID_HDOP as u16
+            | imc::messages::GpsFix::ValidityBits::GFV_VALID_TIME as u16;
+        fix._utc_time = time;
+        fix._satellites = 8;
+        fix._hdop = 0.9;
+        fix._lat = lat.to_radians();
+        fix._lon = lon.to_radians();
+        fix
+    }
+
+    #[test]
+    fn quality() {
+        let mut gate = Gate::new(limits());
+
+        assert_eq!(gate.check(&fix(41.0, -8.0, 0.0)), Ok(()));
+
+        let mut m = fix(41.0, -8.0, 0.0);
+        m._satellites = 3;
+        assert_eq!(gate.check(&m), Err(Rejection::Satellites(3)));
+
+        // nor the satellites when unknown
+        m._satellites = 0;
+        assert_eq!(gate.check(&m), Ok(()));
+
+        let mut m = fix(41.0, -8.0, 0.0);
+        m._hdop = 7.5;
+        assert_eq!(gate.check(&m), Err(Rejection::Hdop(7.5)));
+
+        // HDOP is not checked when unknown
+        m._validity &= !(imc::messages::GpsFix::ValidityBits::GFV_VALID_HDOP as u16);
+        assert_eq!(gate.check(&m), Ok(()));
+
+        let mut m = fix(41.0, -8.0, 0.0);
+        m._type = imc::messages::GpsFix::TypeEnum::GFT_DEAD_RECKONING as u8;
+        assert_eq!(gate.check(&m), Err(Rejection::FixType(m._type)));
+
+        assert_eq!(
+            gate.rejections(),
+            &Rejections {
+                satellites: 1,
+                hdop: 1,
+                fix_type: 1,
+                speed: 0
+            }
+        );
+        assert_eq!(gate.rejections().total(), 3);
+    }
+
+    #[test]
+    fn outlier() {
+        let mut gate = Gate::new(limits());
+
+        assert_eq!(gate.check(&fix(41.0, -8.0, 100.0)), Ok(()));
+
+        // ~11 m north in one second
+        assert_eq!(gate.check(&fix(41.0001, -8.0, 101.0)), Ok(()));
+
+        // multipath jump of ~300 m
+        match gate.check(&fix(41.0028, -8.0, 102.0)) {
+            Err(Rejection::Speed(speed)) => assert!((speed - 300.0).abs() < 5.0, "{}", speed),
+            r => panic!("unexpected {:?}", r),
+        }
+
+        // checked against the last accepted fix
+        assert_eq!(gate.check(&fix(41.0002, -8.0, 103.0)), Ok(()));
+        assert_eq!(gate.rejections().speed, 1);
+
+        // the same jump over midnight
+        assert_eq!(gate.check(&fix(41.0, -8.0, 86399.5)), Ok(()));
+        assert!(gate.check(&fix(41.0028, -8.0, 0.5)).is_err());
+
+        // over days, once dated
+        let dated = |lat: f64, day: u8| {
+            let mut m = fix(lat, -8.0, 43200.0);
+            m._validity |= imc::messages::GpsFix::ValidityBits::GFV_VALID_DATE as u16;
+            m._utc_year = 2024;
+            m._utc_month = 3;
+            m._utc_day = day;
+            m
+        };
+        assert_eq!(gate.check(&dated(41.0, 1)), Ok(()));
+        assert_eq!(gate.check(&dated(42.0, 2)), Ok(()));
+
+        // not checked without the time of the fix
+        let mut m = fix(45.0, -8.0, 0.0);
+        m._validity &= !(imc::messages::GpsFix::ValidityBits::GFV_VALID_TIME as u16);
+        assert_eq!(gate.check(&m), Ok(()));
+
+        // disabled
+        let mut gate = Gate::new(Limits {
+            max_speed: None,
+            ..limits()
+        });
+        assert_eq!(gate.check(&fix(41.0, -8.0, 100.0)), Ok(()));
+        assert_eq!(gate.check(&fix(42.0, -8.0, 101.0)), Ok(()));
+    }
+
+    #[test]
+    fn great_circle() {
+        // one degree of latitude
+        let d = distance(0.0, 0.0, 1.0f64.to_radians(), 0.0);
+        assert!((d - 111_195.0).abs() < 1.0, "{}", d);
+
+        assert_eq!(distance(0.7, -0.1, 0.7, -0.1), 0.0);
+    }
+}
@@ -0,0 +1,166 @@
+use std::fs;
+use std::io;
+use std::io::ErrorKind;
+use std::path::Path;
+
+/// Geoid model, as a grid of geoid separations (undulations) in the NGA
+/// ASCII format of EGM96 (WW15MGH.GRD), which EGM2008 grids can be converted
+/// to: a header "south north west east dlat dlon" in degrees, then the
+/// separations in meters, rows from north to south, each row from west to
+/// east
+#[derive(Debug, PartialEq)]
+pub struct Geoid {
+    north: f64,
+    west: f64,
+    dlat: f64,
+    dlon: f64,
+    rows: usize,
+    cols: usize,
+    /// Grid covers every longitude
+    global: bool,
+    values: Vec<f32>,
+}
+
+impl Geoid {
+    pub fn load(path: &Path) -> io::Result<Geoid> {
+        Geoid::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(text: &str) -> io::Result<Geoid> {
+        let mut numbers = text.split_whitespace().map(|n| n.parse::<f64>());
+
+        let mut header = [0.0; 6];
+        for value in header.iter_mut() {
+            *value = match numbers.next() {
+                Some(Ok(n)) => n,
+                _ => return Err(invalid("invalid header")),
+            };
+        }
+
+        let [south, north, west, east, dlat, dlon] = header;
+        if south >= north || west >= east || dlat <= 0.0 || dlon <= 0.0 {
+            return Err(invalid("invalid grid bounds"));
+        }
+
+        let rows = ((north - south) / dlat).round() as usize + 1;
+        let cols = ((east - west) / dlon).round() as usize + 1;
+
+        let values = numbers
+            .map(|n| n.map(|n| n as f32))
+            .collect::<Result<Vec<f32>, _>>()
+            .map_err(|_| invalid("invalid separation"))?;
+        if values.len() != rows * cols {
+            return Err(invalid(&format!(
+                "{} separations for a {}x{} grid",
+                values.len(),
+                rows,
+                cols
+            )));
+        }
+
+        Ok(Geoid {
+            north,
+            west,
+            dlat,
+            dlon,
+            rows,
+            cols,
+            global: east - west >= 360.0,
+            values,
+        })
+    }
+
+    /// Geoid separation at the given position in degrees, bilinearly
+    /// interpolated. None outside the grid
+    pub fn separation(&self, lat: f64, lon: f64) -> Option<f32> {
+        let lon = if self.global {
+            self.west + (lon - self.west).rem_euclid(360.0)
+        } else {
+            lon
+        };
+
+        let row = (self.north - lat) / self.dlat;
+        let col = (lon - self.west) / self.dlon;
+        let (last_row, last_col) = ((self.rows - 1) as f64, (self.cols - 1) as f64);
+        if !(0.0..=last_row).contains(&row) || !(0.0..=last_col).contains(&col) {
+            return None;
+        }
+
+        // cell whose north west corner is at (i, j)
+        let i = (row.floor() as usize).min(self.rows.saturating_sub(2));
+        let j = (col.floor() as usize).min(self.cols.saturating_sub(2));
+        let value = |i: usize, j: usize| {
+            let i = i.min(self.rows - 1);
+            let j = j.min(self.cols - 1);
+            self.values[i * self.cols + j] as f64
+        };
+
+        let (u, v) = (row - i as f64, col - j as f64);
+        let separation = value(i, j) * (1.0 - u) * (1.0 - v)
+            + value(i, j + 1) * (1.0 - u) * v
+            + value(i + 1, j) * u * (1.0 - v)
+            + value(i + 1, j + 1) * u * v;
+
+        Some(separation as f32)
+    }
+}
+
+fn invalid(reason: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, reason)
+}
+
+mod tests {
+    use super::*;
+
+    /// 10 degree grid, rows at 10N, 0 and 10S, columns at 0, 10E and 20E
+    const GRID: &str = "-10.0 10.0 0.0 20.0 10.0 10.0
+        10.0 20.0 30.0
+        40.0 50.0 60.0
+        70.0 80.0 90.0
+    ";
+
+    #[test]
+    fn interpolate() {
+        let geoid = Geoid::parse(GRID).unwrap();
+
+        // nodes
+        assert_eq!(geoid.separation(10.0, 0.0), Some(10.0));
+        assert_eq!(geoid.separation(0.0, 10.0), Some(50.0));
+        assert_eq!(geoid.separation(-10.0, 20.0), Some(90.0));
+
+        assert_eq!(geoid.separation(5.0, 5.0), Some(30.0));
+        assert_eq!(geoid.separation(0.0, 15.0), Some(55.0));
+        assert_eq!(geoid.separation(-7.5, 0.0), Some(62.5));
+
+        // outside
+        assert_eq!(geoid.separation(10.5, 0.0), None);
+        assert_eq!(geoid.separation(0.0, -0.5), None);
+        assert_eq!(geoid.separation(0.0, 20.5), None);
+    }
+
+    #[test]
+    fn global() {
+        // columns at 0, 180 and 360 degrees
+        let geoid = Geoid::parse("-90 90 0 360 90 180  1 1 1  2 4 2  3 3 3").unwrap();
+
+        assert_eq!(geoid.separation(0.0, 180.0), Some(4.0));
+        // west longitudes
+        assert_eq!(geoid.separation(0.0, -90.0), Some(3.0));
+        assert_eq!(geoid.separation(0.0, -180.0), Some(4.0));
+        assert_eq!(geoid.separation(-90.0, -8.0), Some(3.0));
+    }
+
+    #[test]
+    fn invalid_grid() {
+        assert!(Geoid::parse("").is_err());
+        assert!(Geoid::parse("-10 10 0 20 10").is_err());
+        assert!(Geoid::parse("10 -10 0 20 10 10").is_err());
+
+        // one separation missing
+        let truncated = GRID.trim_end().trim_end_matches("90.0");
+        assert!(Geoid::parse(truncated).is_err());
+
+        let garbage = GRID.replace("50.0", "x");
+        assert!(Geoid::parse(&garbage).is_err());
+    }
+}
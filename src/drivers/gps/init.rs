@@ -0,0 +1,436 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::fmt::Formatter;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use crate::drivers::gps::framer::MAX_LINE_LEN;
+use crate::drivers::gps::ubx;
+
+/// Expected receiver answer to an initialization command
+#[derive(Clone, Debug, PartialEq)]
+pub enum Ack {
+    /// Do not wait for any answer
+    None,
+    /// Wait for a line starting with the given text, e.g. "$PMTK001,220,3"
+    Nmea(String),
+    /// Wait for an UBX ACK-ACK of the given message class and id
+    Ubx { class: u8, id: u8 },
+}
+
+/// Command sent to the receiver after opening the device
+#[derive(Clone, Debug, PartialEq)]
+pub struct InitCommand {
+    /// Raw bytes to send
+    pub data: Vec<u8>,
+    /// Expected acknowledgement
+    pub ack: Ack,
+    /// Time to wait for the acknowledgement, in milliseconds
+    pub timeout: u64,
+}
+
+impl InitCommand {
+    /// Raw NMEA sentence (e.g. "$PMTK220,100*2F") with an optional
+    /// acknowledgement prefix. Line terminator is appended if missing
+    pub fn nmea(sentence: &str, ack: Option<&str>, timeout: u64) -> InitCommand {
+        let mut data = sentence.as_bytes().to_vec();
+        if !sentence.ends_with("\r\n") {
+            data.extend_from_slice(b"\r\n");
+        }
+
+        InitCommand {
+            data,
+            ack: match ack {
+                Some(a) => Ack::Nmea(String::from(a)),
+                None => Ack::None,
+            },
+            timeout,
+        }
+    }
+
+    /// UBX message (usually of class CFG) acknowledged by an ACK-ACK
+    pub fn ubx(class: u8, id: u8, payload: &[u8], timeout: u64) -> InitCommand {
+        InitCommand {
+            data: ubx::encode(class, id, payload),
+            ack: Ack::Ubx { class, id },
+            timeout,
+        }
+    }
+}
+
+impl fmt::Display for InitCommand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.ack {
+            Ack::Ubx { class, id } => write!(f, "UBX 0x{:02X} 0x{:02X}", class, id),
+            _ => f.write_str(String::from_utf8_lossy(&self.data).trim_end()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum InitError {
+    /// Failed to write or read the device
+    Io(std::io::Error),
+    /// No acknowledgement received in time
+    Timeout,
+    /// Receiver explicitly rejected the command (UBX ACK-NAK)
+    Rejected,
+}
+
+impl fmt::Display for InitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            InitError::Io(e) => write!(f, "I/O error: {}", e),
+            InitError::Timeout => f.write_str("acknowledgement timeout"),
+            InitError::Rejected => f.write_str("rejected by receiver"),
+        }
+    }
+}
+
+/// Look for an acknowledgement in the receiver's output
+struct AckMatcher {
+    ack: Ack,
+    /// Start of the current line, enough to match the expected prefix
+    line: String,
+    ubx: ubx::Parser,
+}
+
+impl AckMatcher {
+    fn new(ack: Ack) -> AckMatcher {
+        AckMatcher {
+            ack,
+            line: String::new(),
+            ubx: ubx::Parser::new(),
+        }
+    }
+
+    /// Returns Some(true) on acknowledgement and Some(false) on rejection
+    fn push(&mut self, b: u8) -> Option<bool> {
+        match &self.ack {
+            Ack::None => Some(true),
+            Ack::Nmea(prefix) => {
+                if b == b'\n' {
+                    let matched = self.line.trim_end().starts_with(prefix.as_str());
+                    self.line.clear();
+                    return if matched { Some(true) } else { None };
+                }
+
+                // the rest of a long line cannot change the match
+                if self.line.len() < MAX_LINE_LEN {
+                    self.line.push(b as char);
+                }
+                None
+            }
+            Ack::Ubx { class, id } => match self.ubx.push(b) {
+                Ok(frame) if frame.class == ubx::CLASS_ACK && frame.payload == [*class, *id] => {
+                    Some(frame.id == ubx::ACK_ACK)
+                }
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Outcome of an initialization command
+pub type Outcome = (InitCommand, Result<(), InitError>);
+
+/// Send initialization commands one after the other, each once the previous
+/// one is acknowledged or timed out. Driven by the bytes received and the
+/// passing of time, so that the task never waits on the device
+#[derive(Default)]
+pub struct Sequence {
+    pending: VecDeque<InitCommand>,
+    /// Command waiting for its acknowledgement, until the deadline
+    current: Option<(InitCommand, AckMatcher, Instant)>,
+}
+
+impl Sequence {
+    pub fn new(commands: Vec<InitCommand>) -> Sequence {
+        Sequence {
+            pending: commands.into(),
+            current: None,
+        }
+    }
+
+    /// Whether every command was sent and answered or timed out
+    pub fn is_done(&self) -> bool {
+        self.pending.is_empty() && self.current.is_none()
+    }
+
+    /// Send the first command, and the ones after it up to the first
+    /// waiting for an acknowledgement
+    pub fn start<W: Write + ?Sized>(&mut self, io: &mut W, now: Instant) -> Vec<Outcome> {
+        let mut outcomes = vec![];
+        self.advance(io, now, &mut outcomes);
+        outcomes
+    }
+
+    /// Look for the acknowledgement of the current command in received data
+    pub fn push<W: Write + ?Sized>(
+        &mut self,
+        data: &[u8],
+        io: &mut W,
+        now: Instant,
+    ) -> Vec<Outcome> {
+        let mut outcomes = vec![];
+
+        for &b in data {
+            let answer = match self.current.as_mut() {
+                Some((_, matcher, _)) => matcher.push(b),
+                None => break,
+            };
+
+            let result = match answer {
+                Some(true) => Ok(()),
+                Some(false) => Err(InitError::Rejected),
+                None => continue,
+            };
+
+            if let Some((cmd, _, _)) = self.current.take() {
+                outcomes.push((cmd, result));
+            }
+            self.advance(io, now, &mut outcomes);
+        }
+
+        outcomes
+    }
+
+    /// Give up on the current command once its deadline passed
+    pub fn poll<W: Write + ?Sized>(&mut self, io: &mut W, now: Instant) -> Vec<Outcome> {
+        let mut outcomes = vec![];
+
+        if let Some((_, _, deadline)) = &self.current {
+            if now >= *deadline {
+                if let Some((cmd, _, _)) = self.current.take() {
+                    outcomes.push((cmd, Err(InitError::Timeout)));
+                }
+                self.advance(io, now, &mut outcomes);
+            }
+        }
+
+        outcomes
+    }
+
+    /// Send pending commands until one waits for an acknowledgement
+    fn advance<W: Write + ?Sized>(
+        &mut self,
+        io: &mut W,
+        now: Instant,
+        outcomes: &mut Vec<Outcome>,
+    ) {
+        while self.current.is_none() {
+            let cmd = match self.pending.pop_front() {
+                Some(cmd) => cmd,
+                None => return,
+            };
+
+            if let Err(e) = io.write_all(&cmd.data).and_then(|_| io.flush()) {
+                outcomes.push((cmd, Err(InitError::Io(e))));
+                continue;
+            }
+
+            if cmd.ack == Ack::None {
+                outcomes.push((cmd, Ok(())));
+                continue;
+            }
+
+            let deadline = now + Duration::from_millis(cmd.timeout);
+            let matcher = AckMatcher::new(cmd.ack.clone());
+            self.current = Some((cmd, matcher, deadline));
+        }
+    }
+}
+
+mod tests {
+    use super::*;
+
+    /// Device double recording what was written
+    #[derive(Default)]
+    struct MockDevice {
+        output: Vec<u8>,
+    }
+
+    impl Write for MockDevice {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.output.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Results of the given outcomes, ok or the error
+    fn results(outcomes: Vec<Outcome>) -> Vec<Result<(), String>> {
+        outcomes
+            .into_iter()
+            .map(|(_, r)| r.map_err(|e| e.to_string()))
+            .collect()
+    }
+
+    #[test]
+    /// Line terminator is added to NMEA commands
+    fn nmea_command() {
+        let cmd = InitCommand::nmea("$PMTK220,100*2F", Some("$PMTK001,220,3"), 100);
+
+        assert_eq!(cmd.data, b"$PMTK220,100*2F\r\n".to_vec());
+        assert_eq!(cmd.ack, Ack::Nmea(String::from("$PMTK001,220,3")));
+    }
+
+    #[test]
+    fn nmea_ack() {
+        let cmd = InitCommand::nmea("$PMTK220,100*2F", Some("$PMTK001,220,3"), 100);
+        let mut dev = MockDevice::default();
+        let now = Instant::now();
+
+        let mut sequence = Sequence::new(vec![cmd.clone()]);
+        assert!(sequence.start(&mut dev, now).is_empty());
+        assert_eq!(dev.output, cmd.data);
+
+        // split over reads
+        assert!(sequence
+            .push(b"$GPGGA,,,,,,0,,,,,,,,*66\r\n$PMTK001,", &mut dev, now)
+            .is_empty());
+        assert!(!sequence.is_done());
+        assert_eq!(
+            results(sequence.push(b"220,3*30\r\n", &mut dev, now)),
+            vec![Ok(())]
+        );
+        assert!(sequence.is_done());
+    }
+
+    #[test]
+    fn nmea_timeout() {
+        let cmd = InitCommand::nmea("$PMTK220,100*2F", Some("$PMTK001,220,3"), 10);
+        let mut dev = MockDevice::default();
+        let now = Instant::now();
+
+        let mut sequence = Sequence::new(vec![cmd]);
+        sequence.start(&mut dev, now);
+        assert!(sequence
+            .push(b"$PMTK001,220,2*31\r\n", &mut dev, now)
+            .is_empty());
+        assert!(sequence.poll(&mut dev, now).is_empty());
+
+        let outcomes = sequence.poll(&mut dev, now + Duration::from_millis(10));
+        assert!(matches!(outcomes[..], [(_, Err(InitError::Timeout))]));
+        assert!(sequence.is_done());
+    }
+
+    #[test]
+    /// The line kept for matching is bounded
+    fn long_line() {
+        let cmd = InitCommand::nmea("$PMTK220,100*2F", Some("$PMTK001,220,3"), 100);
+        let mut dev = MockDevice::default();
+        let now = Instant::now();
+
+        let mut sequence = Sequence::new(vec![cmd]);
+        sequence.start(&mut dev, now);
+        assert!(sequence.push(&[b'x'; 10000], &mut dev, now).is_empty());
+        match &sequence.current {
+            Some((_, matcher, _)) => assert_eq!(matcher.line.len(), MAX_LINE_LEN),
+            None => panic!("no command waiting"),
+        }
+
+        assert_eq!(
+            results(sequence.push(b"\r\n$PMTK001,220,3*30\r\n", &mut dev, now)),
+            vec![Ok(())]
+        );
+    }
+
+    #[test]
+    /// Commands without acknowledgement complete right after being written
+    fn no_ack() {
+        let cmd = InitCommand::nmea("$PUBX,40,GSV,0,0,0,0*59", None, 1000);
+        let mut dev = MockDevice::default();
+
+        let mut sequence = Sequence::new(vec![cmd.clone(), cmd]);
+        assert_eq!(
+            results(sequence.start(&mut dev, Instant::now())),
+            vec![Ok(()), Ok(())]
+        );
+        assert_eq!(dev.output, b"$PUBX,40,GSV,0,0,0,0*59\r\n".repeat(2));
+        assert!(sequence.is_done());
+    }
+
+    #[test]
+    /// Each command is sent once the previous one is answered
+    fn sequence() {
+        let rate = InitCommand::nmea("$PMTK220,100*2F", Some("$PMTK001,220,3"), 100);
+        let gsv = InitCommand::nmea("$PUBX,40,GSV,0,0,0,0*59", None, 100);
+        let baud = InitCommand::nmea("$PMTK251,38400*27", Some("$PMTK001,251,3"), 100);
+        let mut dev = MockDevice::default();
+        let now = Instant::now();
+
+        let mut sequence = Sequence::new(vec![rate.clone(), gsv.clone(), baud.clone()]);
+        sequence.start(&mut dev, now);
+        assert_eq!(dev.output, rate.data);
+
+        // the next one waiting for its acknowledgement
+        assert_eq!(
+            results(sequence.push(b"$PMTK001,220,3*30\r\n", &mut dev, now)),
+            vec![Ok(()), Ok(())]
+        );
+        assert_eq!(dev.output, [&rate.data[..], &gsv.data, &baud.data].concat());
+        assert!(!sequence.is_done());
+
+        // timed out from when it was sent
+        let later = now + Duration::from_millis(50);
+        let mut sequence = Sequence::new(vec![rate, baud]);
+        sequence.start(&mut dev, now);
+        sequence.push(b"$PMTK001,220,3*30\r\n", &mut dev, later);
+        assert!(sequence
+            .poll(&mut dev, now + Duration::from_millis(100))
+            .is_empty());
+        assert_eq!(
+            sequence
+                .poll(&mut dev, later + Duration::from_millis(100))
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn ubx_ack() {
+        // CFG-RATE 10 Hz
+        let cmd = InitCommand::ubx(
+            ubx::CLASS_CFG,
+            0x08,
+            &[0x64, 0x00, 0x01, 0x00, 0x01, 0x00],
+            100,
+        );
+
+        // ACK for another message, then ours
+        let mut reply = ubx::encode(ubx::CLASS_ACK, ubx::ACK_ACK, &[ubx::CLASS_CFG, 0x01]);
+        reply.extend(ubx::encode(
+            ubx::CLASS_ACK,
+            ubx::ACK_ACK,
+            &[ubx::CLASS_CFG, 0x08],
+        ));
+
+        let mut dev = MockDevice::default();
+        let mut sequence = Sequence::new(vec![cmd]);
+        sequence.start(&mut dev, Instant::now());
+        assert_eq!(
+            results(sequence.push(&reply, &mut dev, Instant::now())),
+            vec![Ok(())]
+        );
+    }
+
+    #[test]
+    fn ubx_nak() {
+        let cmd = InitCommand::ubx(
+            ubx::CLASS_CFG,
+            0x08,
+            &[0x64, 0x00, 0x01, 0x00, 0x01, 0x00],
+            100,
+        );
+        let reply = ubx::encode(ubx::CLASS_ACK, ubx::ACK_NAK, &[ubx::CLASS_CFG, 0x08]);
+
+        let mut dev = MockDevice::default();
+        let mut sequence = Sequence::new(vec![cmd]);
+        sequence.start(&mut dev, Instant::now());
+        let outcomes = sequence.push(&reply, &mut dev, Instant::now());
+        assert!(matches!(outcomes[..], [(_, Err(InitError::Rejected))]));
+    }
+}
@@ -1,46 +1,154 @@
-use std::io::Read;
+use std::io::Write;
+use std::path::Path;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Barrier};
 use std::thread::Thread;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::{thread, time};
 
 use actix::prelude::*;
 use actix_broker::{BrokerIssue, BrokerSubscribe, SystemBroker};
 use imc::DevDataText;
+use imc::EulerAngles;
 use imc::GpsFix;
 use imc::Message;
-use serialport::SerialPort;
+use imc::RemoteSensorInfo;
+use ix_nmea::registry::{DataCustom, RegisterError, SentenceDefinition};
+use ix_nmea::sentences::DataVDM;
+use ix_nmea::{ErrorKind, Sentence};
 
-use crate::drivers::gps::nmea::{Sentence, State};
+use crate::drivers::gps::framer::{Frame, LineFramer, MAX_LINE_LEN};
+use crate::drivers::gps::gate::{FixType, Gate, GateAction, Limits, Rejections};
+use crate::drivers::gps::geoid::Geoid;
+use crate::drivers::gps::init::{InitCommand, Outcome, Sequence};
+use crate::drivers::gps::quality::{Differential, Quality, SolutionStatus};
+use crate::drivers::gps::registry::{Conversion, ImcConversion, Shared};
+use crate::drivers::gps::rtcm::Deframer;
+use crate::drivers::gps::talker::{TalkerFilter, TalkerPolicy};
+use crate::drivers::gps::watchdog::{Status, Watchdog};
+use crate::drivers::io;
+use crate::drivers::io::reader::{Closed, Received};
+use crate::drivers::io::{Backoff, Stream};
 use crate::ix::Parameter;
+use crate::task::{entity_state, status_text};
 use crate::BrokerType;
 use crate::MessageWrapper;
 use crate::TaskBehaviour;
 use crate::{ix, task};
 
-mod field_reader;
-mod nmea;
-mod sentences;
+mod ais;
+mod bench;
+pub mod framer;
+pub mod gate;
+mod geoid;
+mod init;
+pub mod quality;
+pub mod registry;
+mod rtcm;
+pub mod talker;
 mod tests;
+mod ubx;
+mod watchdog;
+
+/// Protocol(s) expected on the device's byte stream
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Protocol {
+    /// NMEA 0183 sentences only
+    #[default]
+    Nmea,
+    /// u-blox UBX binary frames only
+    Ubx,
+    /// NMEA sentences interleaved with UBX frames
+    Mixed,
+}
+
+/// Period of the talker statistics
+const TALKER_REPORT_PERIOD: Duration = Duration::from_secs(10);
 
 #[derive(Default)]
 pub struct Configuration {
     pub io_dev: Parameter<String>,
     pub baud: Parameter<u32>,
     pub io_timeout: Parameter<u64>,
+    pub reconnect_delay: Parameter<u64>,
+    pub reconnect_max_delay: Parameter<u64>,
+    pub data_timeout: Parameter<u64>,
+    pub fix_timeout: Parameter<u64>,
+    pub min_satellites: Parameter<Option<u8>>,
+    pub max_hdop: Parameter<Option<f32>>,
+    pub fix_types: Parameter<Vec<FixType>>,
+    pub max_speed: Parameter<Option<f32>>,
+    pub gate_action: Parameter<GateAction>,
+    pub talker_policy: Parameter<TalkerPolicy>,
+    pub protocol: Parameter<Protocol>,
+    pub init_cmds: Parameter<Vec<InitCommand>>,
+    pub variation: Parameter<Option<f32>>,
+    pub geoid_model: Parameter<Option<String>>,
+    pub rtcm_input: Parameter<bool>,
+    pub ais_target_age: Parameter<u64>,
 }
 
 // Task fields' definition
 pub struct Task {
     pub ctx: task::Context,
     pub fix: GpsFix,
-    pub parser: nmea::Parser,
-    pub io: Option<Box<dyn SerialPort>>,
-    pub bfr: String,
+    pub heading: EulerAngles,
+    pub parser: ix_nmea::Parser,
+    /// Conversions of the registered sentences, by registry index
+    conversions: Vec<Arc<dyn ImcConversion>>,
+    pub talkers: TalkerFilter,
+    /// When the talker statistics were last published
+    talkers_reported: Instant,
+    pub ubx: ubx::Parser,
+    pub ais: ais::Reassembler,
+    pub targets: ais::Targets,
+    pub io: Option<Box<dyn Stream>>,
+    pub framer: LineFramer,
+    /// Initialization commands of the current connection
+    init: Sequence,
+    /// Delay between attempts to reopen the device
+    backoff: Backoff,
+    /// Detects loss of data and of fix
+    watchdog: Watchdog,
+    /// Rejects poor fixes and outliers
+    gate: Gate,
+    /// Rejections last published
+    rejections: Rejections,
+    /// Last solution quality reported, distinguishing RTK solutions
+    pub quality: Quality,
+    /// Corrections in use, if any
+    pub differential: Option<Differential>,
+    /// Last GNS received, which supersedes GGA
+    last_gns: Option<Instant>,
+    /// Altitude above mean sea level of the last position, in meters
+    pub altitude: Option<f32>,
+    /// Geoid separation of the last position, when known, in meters
+    pub separation: Option<f32>,
+    /// Geoid model, for receivers not reporting the separation
+    geoid: Option<Geoid>,
+    /// Corrections received from the bus
+    rtcm: Deframer,
+    /// Correction bytes written to the receiver
+    rtcm_forwarded: u64,
     cfg: Configuration,
 }
 
+/// Position solution, as reported by GGA or GNS
+struct Solution {
+    /// UTC time of the solution, hhmmss.ss
+    utc_time: Option<f64>,
+    quality: Quality,
+    lat: Option<f64>,
+    ns: Option<char>,
+    lon: Option<f64>,
+    ew: Option<char>,
+    alt: Option<f32>,
+    gsep: Option<f32>,
+    sat: Option<u8>,
+    hdop: Option<f32>,
+    differential: Option<Differential>,
+}
+
 // Task Trait implementation
 
 impl TaskBehaviour for Task {
@@ -59,19 +167,123 @@ impl TaskBehaviour for Task {
             .io_dev
             .name("IO Device")
             .default(String::from("/dev/ttyACM0"))
-            .description("IO port used to connect to the device");
+            .description("Device path or URI, e.g. uart:///dev/ttyUSB0:115200, tcp://host:port, udp://0.0.0.0:5000, file://capture.nmea or pty://");
 
         self.cfg
             .baud
             .name("IO - Baud Rate")
             .default(115200)
-            .description("Baud rate applied to the device");
+            .description("Baud rate applied to serial devices whose URI has none");
 
         self.cfg
             .io_timeout
             .name("IO - Communications Timeout")
             .default(10)
-            .description("In milliseconds");
+            .description(
+                "In milliseconds, read timeout of the device. 0 blocks until data arrives",
+            );
+
+        self.cfg
+            .reconnect_delay
+            .name("IO - Reconnect Delay")
+            .default(500)
+            .description("In milliseconds, delay before reopening a lost device. Doubled on every failed attempt");
+
+        self.cfg
+            .reconnect_max_delay
+            .name("IO - Maximum Reconnect Delay")
+            .default(30000)
+            .description("In milliseconds, upper bound of the reconnect delay");
+
+        self.cfg
+            .data_timeout
+            .name("Data Timeout")
+            .default(2000)
+            .description("In milliseconds, the fix is invalidated when no valid sentence arrives within it. 0 disables it");
+
+        self.cfg
+            .fix_timeout
+            .name("Fix Timeout")
+            .default(5000)
+            .description("In milliseconds, the fix is invalidated when no valid position arrives within it. 0 disables it");
+
+        self.cfg
+            .min_satellites
+            .name("Minimum Satellites")
+            .default(None)
+            .description("Fixes computed with fewer satellites are rejected. Not checked when no sentence reports the count");
+
+        self.cfg
+            .max_hdop
+            .name("Maximum HDOP")
+            .default(None)
+            .description("Fixes with a higher horizontal dilution of precision are rejected");
+
+        self.cfg
+            .fix_types
+            .name("Accepted Fix Types")
+            .default(Limits::default().fix_types)
+            .description("Fixes of other types are rejected");
+
+        self.cfg
+            .max_speed
+            .name("Maximum Speed")
+            .default(None)
+            .description("In m/s. Fixes implying a higher speed since the last accepted one are rejected as outliers");
+
+        self.cfg
+            .gate_action
+            .name("Rejected Fixes")
+            .default(GateAction::Mark)
+            .description(
+                "Publish rejected fixes with an invalid position (Mark) or not at all (Drop)",
+            );
+
+        self.cfg
+            .talker_policy
+            .name("Talker Policy")
+            .default(TalkerPolicy::PreferCombined)
+            .description("Which talkers (constellations) to trust for the navigation solution");
+
+        self.cfg
+            .protocol
+            .name("Protocol")
+            .default(Protocol::Nmea)
+            .description("Parse NMEA, UBX or both (mixed) from the device");
+
+        self.cfg
+            .init_cmds
+            .name("Initialization Commands")
+            .default(vec![])
+            .description("Commands sent to the receiver after opening the device");
+
+        self.cfg
+            .variation
+            .name("Magnetic Variation")
+            .default(None)
+            .description("In degrees, east positive. Used when the receiver does not report it");
+
+        self.cfg
+            .geoid_model
+            .name("Geoid Model")
+            .default(None)
+            .description("Path of a geoid grid in the NGA .GRD format, e.g. EGM96 WW15MGH.GRD. Used when the receiver does not report the geoid separation, the height being the altitude above mean sea level otherwise");
+
+        self.cfg
+            .rtcm_input
+            .name("RTCM Corrections")
+            .default(true)
+            .description(
+                "Write RTCM 3 frames received as DevDataBinary to the receiver, e.g. for RTK",
+            );
+
+        self.cfg
+            .ais_target_age
+            .name("AIS Target Age")
+            .default(360)
+            .description(
+            "In seconds, AIS targets not heard from within it are forgotten. 0 keeps them forever",
+        );
     }
 }
 
@@ -82,19 +294,58 @@ impl Task {
         Task {
             ctx: context,
             fix: Default::default(),
-            parser: nmea::Parser::new(),
+            heading: Default::default(),
+            parser: ix_nmea::Parser::new(),
+            conversions: vec![],
+            talkers: TalkerFilter::new(),
+            talkers_reported: Instant::now(),
+            ubx: ubx::Parser::new(),
+            ais: ais::Reassembler::new(),
+            targets: ais::Targets::new(),
             io: None,
-            bfr: String::from(""),
+            framer: LineFramer::new(),
+            init: Sequence::default(),
+            backoff: Backoff::new(Duration::from_millis(500), Duration::from_secs(30)),
+            watchdog: Watchdog::new(
+                Duration::from_secs(2),
+                Duration::from_secs(5),
+                Instant::now(),
+            ),
+            gate: Gate::new(Default::default()),
+            rejections: Default::default(),
+            quality: Quality::Invalid,
+            differential: None,
+            last_gns: None,
+            altitude: None,
+            separation: None,
+            geoid: None,
+            rtcm: Deframer::new(),
+            rtcm_forwarded: 0,
             cfg: Default::default(),
         }
     }
 
-    fn handle_latitude(&mut self, lat_field: Option<f64>, ns_field: Option<String>) -> bool {
+    /// Register a sentence definition, e.g. for a proprietary sentence,
+    /// consulted before the built-in sentences. Definitions that would shadow
+    /// a built-in sentence are refused
+    pub fn register_sentence<D>(&mut self, definition: D) -> Result<(), RegisterError>
+    where
+        D: SentenceDefinition + ImcConversion + 'static,
+    {
+        let definition = Arc::new(definition);
+        self.parser
+            .registry_mut()
+            .register(Box::new(Shared(Arc::clone(&definition))))?;
+        self.conversions.push(definition);
+        Ok(())
+    }
+
+    fn handle_latitude(&mut self, lat_field: Option<f64>, ns_field: Option<char>) -> bool {
         if lat_field.is_some() && ns_field.is_some() {
             let ns = ns_field.unwrap();
 
-            self.fix._lat = lat_field.unwrap();
-            if ns == "S" {
+            self.fix._lat = nmea_degrees(lat_field.unwrap());
+            if ns == 'S' {
                 self.fix._lat = -self.fix._lat;
             }
 
@@ -103,12 +354,12 @@ impl Task {
         false
     }
 
-    fn handle_longitude(&mut self, lon_field: Option<f64>, ew_field: Option<String>) -> bool {
+    fn handle_longitude(&mut self, lon_field: Option<f64>, ew_field: Option<char>) -> bool {
         if lon_field.is_some() && ew_field.is_some() {
             let ew = ew_field.unwrap();
 
-            self.fix._lon = lon_field.unwrap();
-            if ew == "W" {
+            self.fix._lon = nmea_degrees(lon_field.unwrap());
+            if ew == 'W' {
                 self.fix._lon = -self.fix._lon;
             }
 
@@ -117,114 +368,861 @@ impl Task {
         false
     }
 
+    /// Track the solution quality reported by the receiver, which sets the
+    /// fix type
+    fn handle_quality(&mut self, quality: Quality) {
+        if quality != self.quality {
+            println!("fix quality: {}", quality);
+        }
+
+        self.quality = quality;
+        if let Some(t) = quality.fix_type() {
+            self.fix._type = t;
+        }
+    }
+
+    /// Whether GNS is received, GGA being ignored then. Receivers emitting
+    /// both report the same solution, GNS with the quality of each
+    /// constellation
+    fn gns_preferred(&self) -> bool {
+        let timeout = Duration::from_millis(*self.cfg.data_timeout.get());
+        self.last_gns
+            .is_some_and(|last| timeout.is_zero() || last.elapsed() <= timeout)
+    }
+
+    /// Update the fix with the position solution of GGA or GNS
+    fn handle_solution(&mut self, m: Solution) {
+        // the fix is published on GGA or GNS, usually sent before RMC or ZDA
+        if let Some(utc) = m.utc_time {
+            self.handle_time_of_day(nmea_seconds_of_day(utc));
+        }
+
+        self.handle_quality(m.quality);
+        self.differential = m.differential;
+
+        if self.quality.is_valid()
+            && self.handle_latitude(m.lat, m.ns)
+            && self.handle_longitude(m.lon, m.ew)
+            && m.alt.is_some()
+            && m.sat.is_some()
+        {
+            // ellipsoidal height, from the altitude above mean sea level
+            let separation = m.gsep.or_else(|| {
+                let geoid = self.geoid.as_ref()?;
+                geoid.separation(self.fix._lat, self.fix._lon)
+            });
+            self.fix._height = m.alt.unwrap_or_default() + separation.unwrap_or_default();
+            self.altitude = m.alt;
+            self.separation = separation;
+
+            self.fix._lat = self.fix._lat.to_radians();
+            self.fix._lon = self.fix._lon.to_radians();
+            self.fix._validity |= (imc::messages::GpsFix::ValidityBits::GFV_VALID_POS as u16);
+        } else {
+            self.fix._validity &= !(imc::messages::GpsFix::ValidityBits::GFV_VALID_POS as u16);
+        }
+
+        if let Some(sat) = m.sat {
+            self.fix._satellites = sat;
+        }
+
+        if let Some(hdop) = m.hdop {
+            self.fix._hdop = hdop;
+            self.fix._validity |= (imc::messages::GpsFix::ValidityBits::GFV_VALID_HDOP as u16);
+        }
+    }
+
+    /// Set the time of the fix from a sentence without date. Past midnight
+    /// the date of the last RMC or ZDA is the previous day's, and invalid
+    /// until the next one
+    fn handle_time_of_day(&mut self, time: f32) {
+        let date = imc::messages::GpsFix::ValidityBits::GFV_VALID_DATE as u16;
+        if self.fix._validity & date != 0 && time < self.fix._utc_time {
+            self.fix._validity &= !date;
+        }
+
+        self.fix._utc_time = time;
+        self.fix._validity |= imc::messages::GpsFix::ValidityBits::GFV_VALID_TIME as u16;
+    }
+
     /// Handle sentence and feed corresponding IMC messages
     fn handle_sentence(&mut self, sentence: Sentence) {
+        if !self.talkers.accept(
+            *self.cfg.talker_policy.get(),
+            sentence.talker(),
+            Instant::now(),
+        ) {
+            return;
+        }
+
         match sentence {
             Sentence::Invalid => println!("ERROR: unknown sentence"),
             /// Handle GGA Sentence
+            // GNS supersedes GGA
+            Sentence::GGA(_) if self.gns_preferred() => {}
             Sentence::GGA(m) => {
                 println!("debug: GGA");
-                if m.validity == 1 {
-                    self.fix._type = imc::messages::GpsFix::TypeEnum::GFT_STANDALONE as u8;
+                self.handle_solution(Solution {
+                    utc_time: m.utc_time,
+                    quality: Quality::from_gga(m.validity),
+                    lat: m.lat,
+                    ns: m.ns,
+                    lon: m.lon,
+                    ew: m.ew,
+                    alt: m.alt,
+                    gsep: m.gsep,
+                    sat: m.sat,
+                    hdop: m.hdop,
+                    differential: m.dgps_age.map(|age| Differential {
+                        age,
+                        station: m.dgps_id,
+                    }),
+                });
+            }
+            Sentence::GNS(m) => {
+                self.last_gns = Some(Instant::now());
+                self.handle_solution(Solution {
+                    utc_time: m.utc_time,
+                    quality: m
+                        .mode
+                        .as_deref()
+                        .map_or(Quality::Invalid, Quality::from_modes),
+                    lat: m.lat,
+                    ns: m.ns,
+                    lon: m.lon,
+                    ew: m.ew,
+                    alt: m.alt,
+                    gsep: m.gsep,
+                    sat: m.sat,
+                    hdop: m.hdop,
+                    differential: m.dgps_age.map(|age| Differential {
+                        age,
+                        station: m.dgps_id,
+                    }),
+                });
+            }
+            Sentence::VTG(m) => {
+                let quality = m.mode.map(Quality::from_mode);
+                if let Some(quality) = quality {
+                    self.handle_quality(quality);
+                }
+
+                // without a fix the velocity is meaningless
+                if quality.is_some_and(|q| !q.is_valid()) {
+                    self.fix._validity &=
+                        !(imc::messages::GpsFix::ValidityBits::GFV_VALID_COG as u16);
+                    self.fix._validity &=
+                        !(imc::messages::GpsFix::ValidityBits::GFV_VALID_SOG as u16);
+                    return;
+                }
+
+                // GpsFix course is relative to true north, magnetic course
+                // is only used when the variation is known
+                let cog = m.cog_true.or_else(|| {
+                    m.cog_magnetic
+                        .zip(*self.cfg.variation.get())
+                        .map(|(cog, variation)| cog + variation)
+                });
+
+                if let Some(cog) = cog {
+                    self.fix._cog = cog.rem_euclid(360.0).to_radians();
                     self.fix._validity |=
-                        (imc::messages::GpsFix::ValidityBits::GFV_VALID_POS as u16);
-                } else if m.validity == 2 {
-                    self.fix._type = imc::messages::GpsFix::TypeEnum::GFT_DIFFERENTIAL as u8;
+                        (imc::messages::GpsFix::ValidityBits::GFV_VALID_COG as u16);
+                }
+
+                if let Some(sog) = m.sog_kph {
+                    // to mps
+                    self.fix._sog = sog * 1000.0 / 3600.0;
                     self.fix._validity |=
-                        (imc::messages::GpsFix::ValidityBits::GFV_VALID_POS as u16);
+                        (imc::messages::GpsFix::ValidityBits::GFV_VALID_SOG as u16);
+                }
+            }
+            Sentence::RMC(m) => {
+                // time and date of the solution, both or none
+                if let (Some(utc), Some(date)) = (m.utc_time, m.date) {
+                    self.fix._utc_time = nmea_seconds_of_day(utc);
+                    self.fix._utc_day = (date / 10000) as u8;
+                    self.fix._utc_month = (date / 100 % 100) as u8;
+                    self.fix._utc_year = 2000 + (date % 100) as u16;
+                    self.fix._validity |= (imc::messages::GpsFix::ValidityBits::GFV_VALID_TIME
+                        as u16)
+                        | (imc::messages::GpsFix::ValidityBits::GFV_VALID_DATE as u16);
                 }
 
-                if self.handle_latitude(m.lat, m.ns)
-                    && self.handle_longitude(m.lon, m.ew)
-                    && m.alt.is_some()
-                    && m.sat.is_some()
-                {
-                    if let Some(gsep) = m.gsep {
-                        self.fix._height += gsep;
-                    }
+                // the status covers receivers older than NMEA 2.3
+                let quality = match (m.mode, m.status) {
+                    (Some(mode), _) => Quality::from_mode(mode),
+                    (None, Some('V')) => Quality::Invalid,
+                    (None, _) => return,
+                };
 
-                    self.fix._lat = self.fix._lat.to_radians();
-                    self.fix._lon = self.fix._lon.to_radians();
+                self.handle_quality(quality);
+                if !quality.is_valid() {
+                    self.fix._validity &=
+                        !(imc::messages::GpsFix::ValidityBits::GFV_VALID_POS as u16);
+                }
+            }
+            Sentence::GST(m) => {
+                // horizontal accuracy from the latitude and longitude error components
+                if let (Some(sd_lat), Some(sd_lon)) = (m.sd_lat, m.sd_lon) {
+                    self.fix._hacc = (sd_lat * sd_lat + sd_lon * sd_lon).sqrt();
                     self.fix._validity |=
-                        (imc::messages::GpsFix::ValidityBits::GFV_VALID_POS as u16);
+                        (imc::messages::GpsFix::ValidityBits::GFV_VALID_HACC as u16);
                 } else {
                     self.fix._validity &=
-                        !(imc::messages::GpsFix::ValidityBits::GFV_VALID_POS as u16);
+                        !(imc::messages::GpsFix::ValidityBits::GFV_VALID_HACC as u16);
                 }
 
-                if let Some(hdop) = m.hdop {
-                    self.fix._hdop = hdop;
+                if let Some(sd_alt) = m.sd_alt {
+                    self.fix._vacc = sd_alt;
                     self.fix._validity |=
-                        (imc::messages::GpsFix::ValidityBits::GFV_VALID_HDOP as u16);
+                        (imc::messages::GpsFix::ValidityBits::GFV_VALID_VACC as u16);
+                } else {
+                    self.fix._validity &=
+                        !(imc::messages::GpsFix::ValidityBits::GFV_VALID_VACC as u16);
                 }
             }
-            Sentence::VTG(m) => {
-                // @fixme: magnetic or true?
-                if let Some(cog) = m.cog_true {
-                    //@todo normalize angles
-                    self.fix._cog = cog.to_radians();
+            Sentence::ZDA(m) => {
+                if let Some(utc) = m.utc {
+                    self.fix._utc_time = nmea_seconds_of_day(utc);
                     self.fix._validity |=
-                        (imc::messages::GpsFix::ValidityBits::GFV_VALID_COG as u16);
+                        (imc::messages::GpsFix::ValidityBits::GFV_VALID_TIME as u16);
                 }
 
-                if let Some(sog) = m.sog_kph {
-                    // to mps
-                    self.fix._sog = sog * 1000.0 / 3600.0;
+                if let (Some(day), Some(month), Some(year)) = (m.day, m.month, m.year) {
+                    self.fix._utc_day = day;
+                    self.fix._utc_month = month;
+                    self.fix._utc_year = year;
                     self.fix._validity |=
-                        (imc::messages::GpsFix::ValidityBits::GFV_VALID_SOG as u16);
+                        (imc::messages::GpsFix::ValidityBits::GFV_VALID_DATE as u16);
                 }
             }
-            Sentence::RMC(m) => println!("RMC"),
-            Sentence::ZDA(m) => {
-                if m.utc.is_some() {
+            // handled by handle_heading and handle_ais
+            Sentence::HDT(_) | Sentence::HDG(_) | Sentence::THS(_) => {}
+            Sentence::VDM(_) | Sentence::VDO(_) => {}
+            // handled by handle_custom
+            Sentence::Custom(_) => {}
+            // handled by the instrument driver
+            Sentence::DBT(_)
+            | Sentence::DPT(_)
+            | Sentence::MWV(_)
+            | Sentence::MWD(_)
+            | Sentence::MTW(_)
+            | Sentence::VHW(_) => {}
+        }
+    }
+
+    /// Handle UBX message and feed corresponding IMC messages
+    fn handle_ubx(&mut self, msg: ubx::Message) {
+        match msg {
+            ubx::Message::NavPvt(m) => {
+                if m.valid & ubx::PVT_VALID_DATE != 0 {
+                    self.fix._utc_year = m.year;
+                    self.fix._utc_month = m.month;
+                    self.fix._utc_day = m.day;
                     self.fix._validity |=
-                        (imc::messages::GpsFix::ValidityBits::GFV_VALID_TIME as u16);
+                        (imc::messages::GpsFix::ValidityBits::GFV_VALID_DATE as u16);
+                } else {
+                    self.fix._validity &=
+                        !(imc::messages::GpsFix::ValidityBits::GFV_VALID_DATE as u16);
                 }
 
-                if m.day.is_some() && m.month.is_some() && m.year.is_some() {
+                if m.valid & ubx::PVT_VALID_TIME != 0 {
+                    self.fix._utc_time = ubx::seconds_of_day(m.hour, m.min, m.sec, m.nano);
                     self.fix._validity |=
                         (imc::messages::GpsFix::ValidityBits::GFV_VALID_TIME as u16);
+                } else {
+                    self.fix._validity &=
+                        !(imc::messages::GpsFix::ValidityBits::GFV_VALID_TIME as u16);
+                }
+
+                self.fix._satellites = m.num_sv;
+
+                let has_fix = m.flags & ubx::PVT_GNSS_FIX_OK != 0
+                    && m.fix_type != ubx::FIX_NONE
+                    && m.fix_type != ubx::FIX_TIME_ONLY;
+
+                let bits = imc::messages::GpsFix::ValidityBits::GFV_VALID_POS as u16
+                    | imc::messages::GpsFix::ValidityBits::GFV_VALID_HACC as u16
+                    | imc::messages::GpsFix::ValidityBits::GFV_VALID_VACC as u16
+                    | imc::messages::GpsFix::ValidityBits::GFV_VALID_COG as u16
+                    | imc::messages::GpsFix::ValidityBits::GFV_VALID_SOG as u16;
+
+                if !has_fix {
+                    self.fix._validity &= !bits;
+                    return;
+                }
+
+                self.fix._type = if m.fix_type == ubx::FIX_DEAD_RECKONING {
+                    imc::messages::GpsFix::TypeEnum::GFT_DEAD_RECKONING as u8
+                } else if m.flags & ubx::PVT_DIFF_SOLN != 0 {
+                    imc::messages::GpsFix::TypeEnum::GFT_DIFFERENTIAL as u8
+                } else {
+                    imc::messages::GpsFix::TypeEnum::GFT_STANDALONE as u8
+                };
+
+                self.fix._lat = m.lat.to_radians();
+                self.fix._lon = m.lon.to_radians();
+                self.fix._height = m.height as f32;
+                self.altitude = Some(m.h_msl as f32);
+                self.separation = Some((m.height - m.h_msl) as f32);
+                self.fix._hacc = m.h_acc;
+                self.fix._vacc = m.v_acc;
+                self.fix._sog = m.g_speed;
+                //@todo normalize angles
+                self.fix._cog = m.head_mot.to_radians();
+                self.fix._validity |= bits;
+            }
+            ubx::Message::NavDop(m) => {
+                self.fix._hdop = m.h_dop;
+                self.fix._vdop = m.v_dop;
+                self.fix._validity |= (imc::messages::GpsFix::ValidityBits::GFV_VALID_HDOP as u16)
+                    | (imc::messages::GpsFix::ValidityBits::GFV_VALID_VDOP as u16);
+            }
+            ubx::Message::NavSat(m) => {
+                self.fix._satellites = m.svs.iter().filter(|sv| sv.used()).count() as u8;
+            }
+            ubx::Message::NavTimeUtc(m) => {
+                if m.valid & ubx::TIMEUTC_VALID_UTC != 0 {
+                    self.fix._utc_year = m.year;
+                    self.fix._utc_month = m.month;
+                    self.fix._utc_day = m.day;
+                    self.fix._utc_time = ubx::seconds_of_day(m.hour, m.min, m.sec, m.nano);
+                    self.fix._validity |= (imc::messages::GpsFix::ValidityBits::GFV_VALID_DATE
+                        as u16)
+                        | (imc::messages::GpsFix::ValidityBits::GFV_VALID_TIME as u16);
                 }
             }
         }
     }
 
-    /// Main loop
+    /// Handle an AIS fragment. Returns the updated target once a supported
+    /// message is complete and the target's position is known
+    fn handle_ais(&mut self, m: &DataVDM) -> Option<ais::Target> {
+        let payload = self.ais.push(m)?;
+        let msg = ais::Message::decode(&payload)?;
+
+        let target = self.targets.update(msg, Instant::now());
+        if target.has_position() {
+            Some(target.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Handle heading sentences. Returns true when the true heading was
+    /// updated. Without variation, magnetic heading is assumed to be true
+    /// heading for HDT and THS while HDG is dropped
+    fn handle_heading(&mut self, sentence: Sentence) -> bool {
+        let (heading, variation) = match sentence {
+            Sentence::HDT(m) => (m.heading, *self.cfg.variation.get()),
+            Sentence::THS(m) => {
+                if m.mode == Some('V') {
+                    return false;
+                }
+
+                (m.heading, *self.cfg.variation.get())
+            }
+            Sentence::HDG(m) => {
+                // sensor heading + deviation = magnetic heading
+                // magnetic heading + variation = true heading
+                let deviation = magnetic_offset(m.deviation, m.deviation_dir).unwrap_or(0.0);
+                let variation =
+                    magnetic_offset(m.variation, m.variation_dir).or(*self.cfg.variation.get());
+                let heading = m
+                    .heading
+                    .zip(variation)
+                    .map(|(h, variation)| h + deviation + variation);
+
+                (heading, variation)
+            }
+            _ => return false,
+        };
+
+        let heading = match heading {
+            Some(h) => h,
+            None => return false,
+        };
+
+        let magnetic = heading - variation.unwrap_or(0.0);
+        self.heading._psi = normalize_angle((heading as f64).to_radians());
+        self.heading._psi_magnetic = normalize_angle((magnetic as f64).to_radians());
+        self.heading._time = unix_time();
+        true
+    }
+
+    /// Convert a sentence parsed by a registered definition. Returns whether
+    /// the fix and the heading should be published
+    fn handle_custom(&mut self, m: &DataCustom) -> (bool, bool) {
+        let conversion = match self.conversions.get(m.definition) {
+            Some(definition) => definition,
+            None => return (false, false),
+        };
+
+        let mut out = Conversion {
+            fix: &mut self.fix,
+            attitude: &mut self.heading,
+            dispatch_fix: false,
+            dispatch_attitude: false,
+        };
+        conversion.convert(m, &mut out);
+
+        let (fix, attitude) = (out.dispatch_fix, out.dispatch_attitude);
+        if attitude {
+            self.heading._time = unix_time();
+        }
+
+        (fix, attitude)
+    }
+
+    /// Publish current heading. Heading sensors only report the yaw: roll
+    /// and pitch are left at 0 and only _psi and _psi_magnetic are
+    /// meaningful, unless a registered sentence set the full attitude
+    fn dispatch_heading(&self) {
+        send_message!(self, imc::EulerAngles, self.heading.clone());
+    }
+
+    /// Publish current fix, with the solution details it cannot carry. These
+    /// come first, for subscribers to have them on the fix, and as text for
+    /// the rest of the system
+    fn dispatch_fix(&self) {
+        let status = self.solution_status();
+        send_message!(self, imc::DevDataText, status_text(status.to_string()));
+        send_message!(self, quality::SolutionStatus, status);
+        send_message!(self, imc::GpsFix, self.fix.clone());
+    }
+
+    /// Quality of the current fix, distinguishing RTK solutions, the
+    /// corrections in use and the geoid separation
+    fn solution_status(&self) -> SolutionStatus {
+        SolutionStatus {
+            quality: self.quality,
+            differential: self.differential.clone(),
+            separation: self.separation,
+        }
+    }
+
+    /// Publish the fix after a navigation solution update
+    fn update_fix(&mut self) {
+        if self.gate_fix() {
+            self.dispatch_fix();
+        }
+    }
+
+    /// Pass a valid position through the quality gate, feeding the watchdog
+    /// once accepted. Returns whether the fix is to be published
+    fn gate_fix(&mut self) -> bool {
+        let position = imc::messages::GpsFix::ValidityBits::GFV_VALID_POS as u16;
+
+        if self.fix._validity & position != 0 {
+            match self.gate.check(&self.fix) {
+                Ok(()) => self.watchdog.fix(Instant::now()),
+                Err(rejection) => {
+                    println!("rejected fix: {}", rejection);
+                    match *self.cfg.gate_action.get() {
+                        GateAction::Mark => self.fix._validity &= !position,
+                        GateAction::Drop => return false,
+                    }
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Rejection counters of the gate, when they changed since last
+    /// published
+    fn rejections_update(&mut self) -> Option<Rejections> {
+        if self.gate.rejections() == &self.rejections {
+            return None;
+        }
+
+        self.rejections = self.gate.rejections().clone();
+        Some(self.rejections.clone())
+    }
+
+    /// Handle a watchdog status change. On timeout the fix is invalidated
+    fn handle_watchdog(&mut self, status: Status) {
+        match status {
+            Status::Ok => self.dispatch_state(
+                imc::messages::EntityState::StateEnum::ESTA_NORMAL as u8,
+                &status.to_string(),
+            ),
+            Status::NoData | Status::NoFix => {
+                println!("ERROR: {}", status);
+                self.fix._validity = 0;
+                self.dispatch_fix();
+                self.dispatch_state(
+                    imc::messages::EntityState::StateEnum::ESTA_ERROR as u8,
+                    &status.to_string(),
+                );
+            }
+        }
+    }
+
+    /// Main loop, watches the receiver output
     fn on_main(&mut self, _context: &mut Context<Self>) {
-        let mut serial_buf: Vec<u8> = vec![0; 1024];
-        self.io
-            .as_mut()
-            .unwrap()
-            .read(serial_buf.as_mut_slice())
-            .expect("Found no data!");
-
-        for b in serial_buf {
-            let c = b as char;
-            if c == '\n' {
-                /// Log received sentence
+        self.initialize(&[]);
+
+        if let Some(status) = self.watchdog.check(Instant::now()) {
+            self.handle_watchdog(status);
+        }
+
+        if let Some(rejections) = self.rejections_update() {
+            send_message!(self, imc::DevDataText, status_text(rejections.to_string()));
+        }
+
+        if self.talkers_reported.elapsed() >= TALKER_REPORT_PERIOD {
+            self.talkers_reported = Instant::now();
+            let report = self.talkers.report();
+            send_message!(self, imc::DevDataText, status_text(report.to_string()));
+        }
+
+        let age = *self.cfg.ais_target_age.get();
+        if age > 0 {
+            self.targets.prune(Duration::from_secs(age), Instant::now());
+        }
+    }
+
+    /// Report the state of the device
+    fn dispatch_state(&self, state: u8, description: &str) {
+        send_message!(self, imc::EntityState, entity_state(state, description));
+    }
+
+    /// Publish an AIS target
+    fn dispatch_target(&self, target: &ais::Target) {
+        send_message!(self, imc::RemoteSensorInfo, target_info(target));
+    }
+
+    /// Feed a byte to the NMEA parser, framing lines
+    fn push_nmea(&mut self, b: u8) {
+        match self.framer.push(b) {
+            Frame::Data => self.parse_nmea(b),
+            Frame::Line(line) => {
+                // Log received sentence
                 let mut log = DevDataText::new();
-                log._value = self.bfr.clone();
+                log._value = line;
                 send_message!(self, imc::DevDataText, log);
 
                 // reset state
                 self.parser.reset();
-                self.bfr.clear();
-                continue;
-            }
-
-            self.bfr.push(c);
-            match self.parser.push(c) {
-                Ok(sentence) => self.handle_sentence(sentence),
-                Err(State::InvalidId(id)) => println!("unsupported sentence id: {}", id),
-                Err(State::InvalidFields) => println!("ERROR: invalid message fields"),
-                Err(State::ChecksumMismatch { expected, received }) => println!(
-                    "ERROR: mismatch: expected {}, received {}",
-                    expected, received
+            }
+            Frame::Overflow => {
+                println!("ERROR: line longer than {} bytes, discarding", MAX_LINE_LEN);
+                self.parser.reset();
+            }
+            Frame::Discarded => {}
+        }
+    }
+
+    /// Feed a byte of the current line to the NMEA parser
+    fn parse_nmea(&mut self, b: u8) {
+        let parsed = self.parser.push(b);
+        if let Ok(Some(_)) = parsed {
+            self.watchdog.data(Instant::now());
+        }
+
+        match parsed {
+            // other vessels, own vessel reports are not published
+            Ok(Some(Sentence::VDM(m))) => {
+                if let Some(target) = self.handle_ais(&m) {
+                    self.dispatch_target(&target);
+                }
+            }
+            Ok(Some(Sentence::VDO(m))) => {
+                self.handle_ais(&m);
+            }
+            // heading sensors are not subject to the talker policy
+            Ok(Some(sentence @ (Sentence::HDT(_) | Sentence::HDG(_) | Sentence::THS(_)))) => {
+                if self.handle_heading(sentence) {
+                    self.dispatch_heading();
+                }
+            }
+            Ok(Some(Sentence::Custom(m))) => {
+                let (fix, heading) = self.handle_custom(&m);
+                if fix {
+                    self.update_fix();
+                }
+                if heading {
+                    self.dispatch_heading();
+                }
+            }
+            Ok(Some(sentence)) => {
+                // in UBX capable modes the solution is published on NAV-PVT
+                let solution = match sentence {
+                    Sentence::GGA(_) => !self.gns_preferred(),
+                    Sentence::GNS(_) => true,
+                    _ => false,
+                };
+                let dispatch = solution && *self.cfg.protocol.get() == Protocol::Nmea;
+
+                self.handle_sentence(sentence);
+                if dispatch {
+                    self.update_fix();
+                }
+            }
+            Ok(None) => {}
+            // bytes between sentences
+            Err(ix_nmea::Error {
+                kind: ErrorKind::InvalidSync(_),
+                ..
+            }) => {}
+            Err(e) => println!("ERROR: {}", e),
+        }
+    }
+
+    /// Feed a byte to the UBX parser. Returns false when the byte broke the
+    /// sync sequence, so it may belong to another protocol
+    fn push_ubx(&mut self, b: u8) -> bool {
+        match self.ubx.push(b) {
+            Ok(frame) => {
+                self.watchdog.data(Instant::now());
+
+                if let Some(msg) = ubx::Message::decode(&frame) {
+                    let dispatch = matches!(msg, ubx::Message::NavPvt(_));
+
+                    self.handle_ubx(msg);
+                    if dispatch {
+                        self.update_fix();
+                    }
+                }
+            }
+            Err(ubx::State::InvalidLength(len)) => println!("ERROR: UBX length {}", len),
+            Err(ubx::State::ChecksumMismatch { expected, received }) => println!(
+                "ERROR: UBX mismatch: expected {}, received {}",
+                expected, received
+            ),
+            Err(ubx::State::InvalidSync(_)) => return false,
+            Err(ubx::State::OnGoing) => {}
+        }
+
+        true
+    }
+
+    /// Start sending the initialization commands to the receiver. The
+    /// following ones go out as acknowledgements arrive, see `initialize`
+    fn start_init(&mut self) {
+        self.init = Sequence::new(self.cfg.init_cmds.get().clone());
+
+        if let Some(io) = self.io.as_mut() {
+            let outcomes = self.init.start(io.as_mut(), Instant::now());
+            report_init(outcomes);
+        }
+    }
+
+    /// Carry on with the initialization commands, on received data or once
+    /// the one waiting for its acknowledgement timed out
+    fn initialize(&mut self, data: &[u8]) {
+        let io = match self.io.as_mut() {
+            Some(io) if !self.init.is_done() => io,
+            _ => return,
+        };
+
+        let now = Instant::now();
+        let mut outcomes = self.init.push(data, io.as_mut(), now);
+        outcomes.extend(self.init.poll(io.as_mut(), now));
+        report_init(outcomes);
+    }
+
+    /// Open the device and start reading it. Failures are retried with
+    /// backoff
+    fn connect(&mut self, ctx: &mut Context<Self>) {
+        let device = self.cfg.io_dev.get().clone();
+        println!("opening {} with {}", device, *self.cfg.baud.get());
+
+        let opened = io::open(
+            &device,
+            *self.cfg.baud.get(),
+            Duration::from_millis(*self.cfg.io_timeout.get()),
+        )
+        .and_then(|io| io.try_clone().map(|reader| (io, reader)));
+
+        let (io, reader) = match opened {
+            Ok(streams) => streams,
+            Err(e) => {
+                println!("ERROR: {}: {}", device, e);
+                self.reconnect(ctx, &format!("failed to open device: {}", e));
+                return;
+            }
+        };
+
+        // nothing left over from a previous connection
+        self.parser.reset();
+        self.ubx.reset();
+        self.framer = LineFramer::new();
+        self.talkers.reset();
+        self.io = Some(io);
+
+        self.start_init();
+
+        // go, bytes are handed over as soon as they are read
+        let addr = ctx.address();
+        let closed = ctx.address();
+        io::reader::spawn(
+            &device,
+            reader,
+            Arc::clone(&self.ctx.running),
+            move |data| {
+                addr.do_send(Received(data));
+                addr.connected()
+            },
+            move |e| closed.do_send(Closed(e)),
+        );
+
+        self.backoff.reset();
+        self.dispatch_state(
+            imc::messages::EntityState::StateEnum::ESTA_NORMAL as u8,
+            "active",
+        );
+    }
+
+    /// Close the device and schedule reopening it
+    fn reconnect(&mut self, ctx: &mut Context<Self>, reason: &str) {
+        self.io = None;
+        self.dispatch_state(
+            imc::messages::EntityState::StateEnum::ESTA_FAULT as u8,
+            reason,
+        );
+
+        if !self.is_running() {
+            return;
+        }
+
+        let delay = self.backoff.next();
+        println!("{}: reopening in {:?}", self.cfg.io_dev.get(), delay);
+        ctx.run_later(delay, |task, ctx| task.connect(ctx));
+    }
+
+    /// Write the valid RTCM 3 frames of the given data to the receiver.
+    /// Frames may span several messages
+    fn inject_rtcm(&mut self, data: &[u8]) {
+        for frame in self.rtcm.push(data) {
+            let io = match self.io.as_mut() {
+                Some(io) => io,
+                // corrections are only useful while current
+                None => continue,
+            };
+
+            match io.write_all(&frame) {
+                Ok(()) => self.rtcm_forwarded += frame.len() as u64,
+                Err(e) => println!(
+                    "ERROR: {}: RTCM {}: {}",
+                    self.cfg.io_dev.get(),
+                    rtcm::message_type(&frame).unwrap_or_default(),
+                    e
                 ),
-                _ => {}
             }
         }
     }
+
+    /// Process the bytes of a read, partial sentences are kept until the
+    /// next read completes them
+    fn process(&mut self, data: &[u8]) {
+        for &b in data {
+            match *self.cfg.protocol.get() {
+                Protocol::Nmea => self.push_nmea(b),
+                Protocol::Ubx => {
+                    self.push_ubx(b);
+                }
+                Protocol::Mixed => {
+                    // UBX sync bytes never show up in NMEA text, a lone one
+                    // being noise the next byte is handed back from
+                    let framing = !self.ubx.is_idle() || b == ubx::SYNC_1;
+                    if !framing || !self.push_ubx(b) {
+                        self.push_nmea(b);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Log the outcome of initialization commands
+fn report_init(outcomes: Vec<Outcome>) {
+    for (cmd, result) in outcomes {
+        match result {
+            Ok(()) => println!("init: {}: ok", cmd),
+            Err(e) => println!("ERROR: init: {}: {}", cmd, e),
+        }
+    }
+}
+
+/// Seconds since the Unix epoch, as stamped on the published attitude
+fn unix_time() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |d| d.as_secs_f64())
+}
+
+/// Seconds elapsed since midnight for an NMEA "hhmmss.ss" time
+fn nmea_seconds_of_day(hhmmss: f64) -> f32 {
+    let hours = (hhmmss / 10000.0).floor();
+    let minutes = (hhmmss / 100.0).floor() % 100.0;
+    let seconds = hhmmss % 100.0;
+
+    (hours * 3600.0 + minutes * 60.0 + seconds) as f32
+}
+
+/// Decimal degrees of an NMEA "ddmm.mmmm" or "dddmm.mmmm" coordinate
+fn nmea_degrees(ddmm: f64) -> f64 {
+    let degrees = (ddmm / 100.0).floor();
+
+    degrees + (ddmm - degrees * 100.0) / 60.0
+}
+
+/// Signed magnetic deviation or variation, east being positive
+fn magnetic_offset(value: Option<f32>, direction: Option<char>) -> Option<f32> {
+    match (value, direction) {
+        (Some(v), Some('E')) => Some(v),
+        (Some(v), Some('W')) => Some(-v),
+        // direction is irrelevant when there is no offset
+        (Some(v), _) if v == 0.0 => Some(0.0),
+        _ => None,
+    }
+}
+
+/// Normalize angle to [-PI, PI[
+fn normalize_angle(angle: f64) -> f64 {
+    use std::f64::consts::PI;
+
+    (angle + PI).rem_euclid(2.0 * PI) - PI
+}
+
+/// Describe an AIS target. Heading falls back to the course over ground
+/// and static data, when known, is listed in the tuple list
+fn target_info(target: &ais::Target) -> RemoteSensorInfo {
+    let position = &target.position;
+
+    let mut info = RemoteSensorInfo::new();
+    info._id = target.mmsi.to_string();
+    info._sensor_class = String::from("AIS");
+    info._lat = position.lat.unwrap_or_default().to_radians();
+    info._lon = position.lon.unwrap_or_default().to_radians();
+    info._heading = match (position.heading, position.cog) {
+        (Some(heading), _) => (heading as f32).to_radians(),
+        (None, Some(cog)) => cog.to_radians(),
+        (None, None) => 0.0,
+    };
+
+    let mut data: Vec<String> = vec![];
+    if let Some(name) = &target.info.name {
+        data.push(format!("name={}", name));
+    }
+    if let Some(callsign) = &target.info.callsign {
+        data.push(format!("callsign={}", callsign));
+    }
+    if let Some(ship_type) = target.info.ship_type {
+        data.push(format!("type={}", ship_type));
+    }
+    if let Some(sog) = position.sog {
+        data.push(format!("sog={}", sog));
+    }
+    if let Some(cog) = position.cog {
+        data.push(format!("cog={}", cog));
+    }
+    info._data = data.join(";");
+
+    info
 }
 
 // Task lifecycle
@@ -233,20 +1231,84 @@ impl Actor for Task {
     type Context = Context<Self>;
 
     fn started(&mut self, ctx: &mut Self::Context) {
-        println!(
-            "opening {} with {}",
-            self.cfg.io_dev.get(),
-            *self.cfg.baud.get()
+        self.backoff = Backoff::new(
+            Duration::from_millis(*self.cfg.reconnect_delay.get()),
+            Duration::from_millis(*self.cfg.reconnect_max_delay.get()),
         );
+        self.watchdog = Watchdog::new(
+            Duration::from_millis(*self.cfg.data_timeout.get()),
+            Duration::from_millis(*self.cfg.fix_timeout.get()),
+            Instant::now(),
+        );
+
+        self.gate = Gate::new(Limits {
+            min_satellites: *self.cfg.min_satellites.get(),
+            max_hdop: *self.cfg.max_hdop.get(),
+            fix_types: self.cfg.fix_types.get().clone(),
+            max_speed: *self.cfg.max_speed.get(),
+        });
+
+        if let Some(path) = self.cfg.geoid_model.get() {
+            match Geoid::load(Path::new(path)) {
+                Ok(geoid) => self.geoid = Some(geoid),
+                Err(e) => println!("ERROR: geoid model {}: {}", path, e),
+            }
+        }
+
+        if *self.cfg.rtcm_input.get() {
+            subscribe_to!(imc::DevDataBinary, self, ctx);
+        }
+
+        self.connect(ctx);
 
-        self.io = Some(
-            serialport::new(self.cfg.io_dev.get(), *self.cfg.baud.get())
-                .timeout(Duration::from_millis(*self.cfg.io_timeout.get()))
-                .open()
-                .expect("Failed to open port"),
+        start_main_loop!(100, ctx);
+    }
+
+    fn stopped(&mut self, _ctx: &mut Self::Context) {
+        println!("{}", self.talkers.report());
+        println!("{}", self.gate.rejections());
+        let rtcm = self.rtcm.stats();
+        println!(
+            "RTCM: {} frames, {} bytes forwarded, {} CRC errors, {} bytes dropped",
+            rtcm.frames, self.rtcm_forwarded, rtcm.crc_errors, rtcm.dropped
         );
+        println!("{} stopped", self.get_name());
+    }
+}
+
+impl Handler<Received> for Task {
+    type Result = ();
+
+    fn handle(&mut self, msg: Received, _ctx: &mut Self::Context) {
+        self.initialize(&msg.0);
+        self.process(&msg.0);
+    }
+}
+
+impl Handler<MessageWrapper<imc::DevDataBinary>> for Task {
+    type Result = ();
+
+    fn handle(&mut self, msg: MessageWrapper<imc::DevDataBinary>, _ctx: &mut Self::Context) {
+        self.inject_rtcm(&msg.0._value);
+    }
+}
+
+impl Handler<Closed> for Task {
+    type Result = ();
+
+    fn handle(&mut self, msg: Closed, ctx: &mut Self::Context) {
+        // a replayed capture is over, not lost
+        if msg.end_of_file() && io::is_capture(self.cfg.io_dev.get()) {
+            println!("{}: end of capture", self.cfg.io_dev.get());
+            self.io = None;
+            self.dispatch_state(
+                imc::messages::EntityState::StateEnum::ESTA_FAULT as u8,
+                "end of capture",
+            );
+            return;
+        }
 
-        /// go
-        start_main_loop!(1000, ctx);
+        println!("ERROR: {}: {}", self.cfg.io_dev.get(), msg.0);
+        self.reconnect(ctx, &format!("device lost: {}", msg.0));
     }
 }
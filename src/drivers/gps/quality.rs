@@ -0,0 +1,242 @@
+use std::fmt;
+use std::fmt::Formatter;
+
+use imc::messages::GpsFix::TypeEnum;
+
+/// Quality of the navigation solution, as reported by the GGA quality
+/// indicator and the NMEA 2.3+ mode indicators (RMC, VTG, GNS)
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Quality {
+    /// No fix
+    #[default]
+    Invalid,
+    Autonomous,
+    Differential,
+    /// Precise positioning service, or precise mode
+    Precise,
+    RtkFixed,
+    RtkFloat,
+    /// Dead reckoning
+    Estimated,
+    Manual,
+    Simulator,
+}
+
+impl Quality {
+    /// From the GGA quality indicator. Unknown values are invalid
+    pub fn from_gga(value: u8) -> Quality {
+        match value {
+            1 => Quality::Autonomous,
+            2 => Quality::Differential,
+            3 => Quality::Precise,
+            4 => Quality::RtkFixed,
+            5 => Quality::RtkFloat,
+            6 => Quality::Estimated,
+            7 => Quality::Manual,
+            8 => Quality::Simulator,
+            _ => Quality::Invalid,
+        }
+    }
+
+    /// From a mode indicator. Unknown values are invalid
+    pub fn from_mode(mode: char) -> Quality {
+        match mode {
+            'A' => Quality::Autonomous,
+            'D' => Quality::Differential,
+            'P' => Quality::Precise,
+            'R' => Quality::RtkFixed,
+            'F' => Quality::RtkFloat,
+            'E' => Quality::Estimated,
+            'M' => Quality::Manual,
+            'S' => Quality::Simulator,
+            _ => Quality::Invalid,
+        }
+    }
+
+    /// From the per constellation mode indicators of GNS, the best of the
+    /// constellations contributing to the solution
+    pub fn from_modes(modes: &str) -> Quality {
+        modes
+            .chars()
+            .map(Quality::from_mode)
+            .max_by_key(|q| q.rank())
+            .unwrap_or_default()
+    }
+
+    /// Order of preference among the solutions
+    fn rank(&self) -> u8 {
+        match self {
+            Quality::Invalid => 0,
+            Quality::Simulator => 1,
+            Quality::Manual => 2,
+            Quality::Estimated => 3,
+            Quality::Autonomous => 4,
+            Quality::Precise => 5,
+            Quality::Differential => 6,
+            Quality::RtkFloat => 7,
+            Quality::RtkFixed => 8,
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        *self != Quality::Invalid
+    }
+
+    /// Corresponding GpsFix type, None when there is no fix. GpsFix has no
+    /// RTK types, RTK being a differential solution
+    pub fn fix_type(&self) -> Option<u8> {
+        let t = match self {
+            Quality::Invalid => return None,
+            Quality::Autonomous | Quality::Precise => TypeEnum::GFT_STANDALONE,
+            Quality::Differential | Quality::RtkFixed | Quality::RtkFloat => {
+                TypeEnum::GFT_DIFFERENTIAL
+            }
+            Quality::Estimated => TypeEnum::GFT_DEAD_RECKONING,
+            Quality::Manual => TypeEnum::GFT_MANUAL_INPUT,
+            Quality::Simulator => TypeEnum::GFT_SIMULATION,
+        };
+
+        Some(t as u8)
+    }
+}
+
+impl fmt::Display for Quality {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Quality::Invalid => "no fix",
+            Quality::Autonomous => "autonomous",
+            Quality::Differential => "differential",
+            Quality::Precise => "precise",
+            Quality::RtkFixed => "RTK fixed",
+            Quality::RtkFloat => "RTK float",
+            Quality::Estimated => "dead reckoning",
+            Quality::Manual => "manual input",
+            Quality::Simulator => "simulator",
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// Differential corrections in use, as reported by GGA and GNS
+#[derive(Clone, Debug, PartialEq)]
+pub struct Differential {
+    /// Age of the corrections, in seconds
+    pub age: f32,
+    /// Reference station id
+    pub station: Option<String>,
+}
+
+/// Solution details GpsFix cannot carry, published along with every fix:
+/// RTK fixed and float solutions, the corrections in use and the geoid
+/// separation
+#[derive(Clone, Debug, PartialEq)]
+pub struct SolutionStatus {
+    pub quality: Quality,
+    pub differential: Option<Differential>,
+    /// Height of the geoid above the ellipsoid, in meters. The altitude
+    /// above mean sea level is the height of the fix minus the separation
+    pub separation: Option<f32>,
+}
+
+impl fmt::Display for SolutionStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "solution: {}", self.quality)?;
+
+        if let Some(differential) = &self.differential {
+            write!(f, ", corrections {:.1} s old", differential.age)?;
+            if let Some(station) = &differential.station {
+                write!(f, " from station {}", station)?;
+            }
+        }
+
+        if let Some(separation) = self.separation {
+            write!(f, ", geoid separation {:.2} m", separation)?;
+        }
+
+        Ok(())
+    }
+}
+
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_text() {
+        let mut status = SolutionStatus {
+            quality: Quality::Autonomous,
+            differential: None,
+            separation: None,
+        };
+        assert_eq!(status.to_string(), "solution: autonomous");
+
+        status.quality = Quality::RtkFixed;
+        status.differential = Some(Differential {
+            age: 1.5,
+            station: Some(String::from("0031")),
+        });
+        status.separation = Some(-25.669);
+        assert_eq!(
+            status.to_string(),
+            "solution: RTK fixed, corrections 1.5 s old from station 0031, geoid separation -25.67 m"
+        );
+    }
+
+    #[test]
+    fn gga_quality() {
+        let qualities: Vec<Quality> = (0..=9).map(Quality::from_gga).collect();
+        assert_eq!(
+            qualities,
+            vec![
+                Quality::Invalid,
+                Quality::Autonomous,
+                Quality::Differential,
+                Quality::Precise,
+                Quality::RtkFixed,
+                Quality::RtkFloat,
+                Quality::Estimated,
+                Quality::Manual,
+                Quality::Simulator,
+                Quality::Invalid,
+            ]
+        );
+    }
+
+    #[test]
+    fn mode_indicator() {
+        assert_eq!(Quality::from_mode('A'), Quality::Autonomous);
+        assert_eq!(Quality::from_mode('D'), Quality::Differential);
+        assert_eq!(Quality::from_mode('R'), Quality::RtkFixed);
+        assert_eq!(Quality::from_mode('F'), Quality::RtkFloat);
+        assert_eq!(Quality::from_mode('E'), Quality::Estimated);
+        assert_eq!(Quality::from_mode('N'), Quality::Invalid);
+        assert_eq!(Quality::from_mode('X'), Quality::Invalid);
+
+        // GNS, one per constellation
+        assert_eq!(Quality::from_modes("RF"), Quality::RtkFixed);
+        assert_eq!(Quality::from_modes("NAD"), Quality::Differential);
+        assert_eq!(Quality::from_modes("NN"), Quality::Invalid);
+        assert_eq!(Quality::from_modes(""), Quality::Invalid);
+    }
+
+    #[test]
+    fn fix_type() {
+        assert_eq!(Quality::Invalid.fix_type(), None);
+        assert_eq!(
+            Quality::Autonomous.fix_type(),
+            Some(TypeEnum::GFT_STANDALONE as u8)
+        );
+        assert_eq!(
+            Quality::RtkFloat.fix_type(),
+            Some(TypeEnum::GFT_DIFFERENTIAL as u8)
+        );
+        assert_eq!(
+            Quality::Estimated.fix_type(),
+            Some(TypeEnum::GFT_DEAD_RECKONING as u8)
+        );
+        assert_eq!(
+            Quality::Simulator.fix_type(),
+            Some(TypeEnum::GFT_SIMULATION as u8)
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use imc::{EulerAngles, GpsFix};
+use ix_nmea::field_reader::FieldReader;
+use ix_nmea::registry::{DataCustom, FieldKind, SentenceDefinition, Value};
+use ix_nmea::sentences::FieldError;
+
+/// IMC state a custom sentence is converted into
+pub struct Conversion<'a> {
+    pub fix: &'a mut GpsFix,
+    pub attitude: &'a mut EulerAngles,
+    /// Publish the fix once converted
+    pub dispatch_fix: bool,
+    /// Publish the attitude once converted
+    pub dispatch_attitude: bool,
+}
+
+/// Conversion of a sentence parsed by a registered definition. Kept apart
+/// from SentenceDefinition, which lives in the no_std parser crate
+pub trait ImcConversion: Send + Sync {
+    /// Convert the parsed sentence into IMC
+    fn convert(&self, data: &DataCustom, out: &mut Conversion);
+}
+
+/// Definition shared between the parser registry and the task conversions
+pub struct Shared<D>(pub Arc<D>);
+
+impl<D: SentenceDefinition + Sync> SentenceDefinition for Shared<D> {
+    fn id(&self) -> &str {
+        self.0.id()
+    }
+
+    fn fields(&self) -> &[(&'static str, FieldKind)] {
+        self.0.fields()
+    }
+
+    fn parse(&self, fin: &mut FieldReader, values: &mut Vec<Value>) -> Result<(), FieldError> {
+        self.0.parse(fin, values)
+    }
+}
+
+mod tests {
+    use ix_nmea::registry::Registry;
+    use ix_nmea::{Sentence, SentenceId, Talker};
+
+    use super::*;
+
+    /// Hypothetical proprietary sentence carrying a depth
+    struct Depth;
+
+    impl SentenceDefinition for Depth {
+        fn id(&self) -> &str {
+            "PXDEP"
+        }
+
+        fn fields(&self) -> &[(&'static str, FieldKind)] {
+            &[("depth", FieldKind::Float), ("status", FieldKind::Char)]
+        }
+    }
+
+    impl ImcConversion for Depth {
+        fn convert(&self, data: &DataCustom, out: &mut Conversion) {
+            if let Some(depth) = data.float(0) {
+                out.fix._height = -depth as f32;
+                out.dispatch_fix = true;
+            }
+        }
+    }
+
+    #[test]
+    fn shared() {
+        let depth = Arc::new(Depth);
+        let mut registry = Registry::new();
+        registry
+            .register(Box::new(Shared(Arc::clone(&depth))))
+            .unwrap();
+
+        let mut data = match registry.sentence(&SentenceId::new(b"PXDEP")) {
+            Some(Sentence::Custom(m)) => m,
+            s => panic!("unexpected {:?}", s),
+        };
+        assert_eq!(data.talker, Talker::Unknown);
+
+        registry.parse_fields(&mut data, b"12.5,A").unwrap();
+        assert_eq!(data.values, vec![Value::Float(12.5), Value::Char('A')]);
+    }
+
+    #[test]
+    fn convert() {
+        let data = DataCustom {
+            talker: Talker::Unknown,
+            id: SentenceId::new(b"PXDEP"),
+            definition: 0,
+            values: vec![Value::Float(12.5)],
+        };
+
+        let mut fix = GpsFix::new();
+        let mut attitude = EulerAngles::new();
+        let mut out = Conversion {
+            fix: &mut fix,
+            attitude: &mut attitude,
+            dispatch_fix: false,
+            dispatch_attitude: false,
+        };
+
+        Depth.convert(&data, &mut out);
+        assert!(out.dispatch_fix);
+        assert!(!out.dispatch_attitude);
+        assert_eq!(fix._height, -12.5);
+    }
+}
@@ -0,0 +1,166 @@
+/// RTCM 3 frame preamble
+pub const PREAMBLE: u8 = 0xD3;
+/// Preamble, reserved bits and length
+const HEADER_LEN: usize = 3;
+/// CRC-24Q
+const CRC_LEN: usize = 3;
+
+/// CRC-24Q, as used by RTCM 3 and SBAS
+pub fn crc24q(data: &[u8]) -> u32 {
+    let mut crc = 0u32;
+    for b in data {
+        crc ^= (*b as u32) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x100_0000 != 0 {
+                crc ^= 0x186_4CFB;
+            }
+        }
+    }
+
+    crc & 0xFF_FFFF
+}
+
+/// Message number of a complete frame
+pub fn message_type(frame: &[u8]) -> Option<u16> {
+    let payload = frame.get(HEADER_LEN..HEADER_LEN + 2)?;
+    Some(((payload[0] as u16) << 4) | (payload[1] as u16 >> 4))
+}
+
+/// Deframer counters
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+    /// Valid frames
+    pub frames: u64,
+    /// Frames failing the CRC
+    pub crc_errors: u64,
+    /// Bytes outside valid frames
+    pub dropped: u64,
+}
+
+/// Extract checksum validated RTCM 3 frames from a byte stream, whatever
+/// the way it is split in chunks
+#[derive(Default)]
+pub struct Deframer {
+    bfr: Vec<u8>,
+    stats: Stats,
+}
+
+impl Deframer {
+    pub fn new() -> Deframer {
+        Default::default()
+    }
+
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// Feed the next chunk, returning the frames it completes
+    pub fn push(&mut self, data: &[u8]) -> Vec<Vec<u8>> {
+        self.bfr.extend_from_slice(data);
+        let mut frames = vec![];
+
+        loop {
+            match self.bfr.iter().position(|b| *b == PREAMBLE) {
+                Some(n) => self.drop(n),
+                None => {
+                    self.drop(self.bfr.len());
+                    break;
+                }
+            }
+
+            if self.bfr.len() < HEADER_LEN {
+                break;
+            }
+
+            // reserved bits must be zero
+            if self.bfr[1] & 0xFC != 0 {
+                self.drop(1);
+                continue;
+            }
+
+            let len = (((self.bfr[1] & 0x03) as usize) << 8) | self.bfr[2] as usize;
+            let total = HEADER_LEN + len + CRC_LEN;
+            if self.bfr.len() < total {
+                break;
+            }
+
+            let crc = &self.bfr[HEADER_LEN + len..total];
+            let received = ((crc[0] as u32) << 16) | ((crc[1] as u32) << 8) | crc[2] as u32;
+            if crc24q(&self.bfr[..HEADER_LEN + len]) != received {
+                // the preamble was likely payload, resynchronize past it
+                self.stats.crc_errors += 1;
+                self.drop(1);
+                continue;
+            }
+
+            self.stats.frames += 1;
+            frames.push(self.bfr.drain(..total).collect());
+        }
+
+        frames
+    }
+
+    fn drop(&mut self, n: usize) {
+        self.stats.dropped += n as u64;
+        self.bfr.drain(..n);
+    }
+}
+
+mod tests {
+    use super::*;
+
+    /// Station coordinates (1005)
+    const FRAME: [u8; 25] = [
+        0xD3, 0x00, 0x13, 0x3E, 0xD7, 0xD3, 0x02, 0x02, 0x98, 0x0E, 0xDE, 0xEF, 0x34, 0xB4, 0xBD,
+        0x62, 0xAC, 0x09, 0x41, 0x98, 0x6F, 0x33, 0x36, 0x0B, 0x98,
+    ];
+
+    #[test]
+    fn crc() {
+        assert_eq!(crc24q(b"123456789"), 0xCDE703);
+        assert_eq!(crc24q(&FRAME[..22]), 0x360B98);
+        assert_eq!(message_type(&FRAME), Some(1005));
+    }
+
+    #[test]
+    fn deframe() {
+        let mut deframer = Deframer::new();
+        assert_eq!(deframer.push(&FRAME), vec![FRAME.to_vec()]);
+
+        // garbage around and split frames
+        let mut stream = vec![0x00, 0xD3, 0xFF];
+        stream.extend_from_slice(&FRAME);
+        stream.extend_from_slice(&FRAME);
+        stream.push(0x42);
+
+        let mut deframer = Deframer::new();
+        let mut frames = vec![];
+        for chunk in stream.chunks(7) {
+            frames.extend(deframer.push(chunk));
+        }
+
+        assert_eq!(frames, vec![FRAME.to_vec(), FRAME.to_vec()]);
+        assert_eq!(deframer.stats().frames, 2);
+        assert_eq!(deframer.stats().dropped, 4);
+    }
+
+    #[test]
+    fn corrupted() {
+        let mut frame = FRAME;
+        frame[10] ^= 0x01;
+
+        let mut deframer = Deframer::new();
+        assert!(deframer.push(&frame).is_empty());
+        assert_eq!(deframer.stats().crc_errors, 1);
+
+        // resynchronized within the next frames, a preamble in the payload
+        // of the corrupted frame may announce up to 1023 bytes
+        let mut frames = vec![];
+        for _ in 0..40 {
+            frames.extend(deframer.push(&FRAME));
+        }
+        assert!(frames.len() >= 30, "{}", frames.len());
+        assert!(frames.iter().all(|f| f[..] == FRAME));
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Formatter;
+use std::time::{Duration, Instant};
+
+use ix_nmea::Talker;
+
+/// Time without combined (GN) sentences after which single constellation
+/// talkers are trusted again
+pub const COMBINED_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Which talkers are trusted for the navigation solution
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TalkerPolicy {
+    /// Accept sentences from every talker
+    Any,
+    /// Accept every talker until a combined (GN) solution is seen, and then
+    /// ignore single constellation talkers while combined solutions keep
+    /// coming
+    #[default]
+    PreferCombined,
+    /// Only accept sentences from the given talker
+    Only(Talker),
+}
+
+/// Per talker (constellation) sentence counters
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TalkerStats {
+    /// Sentences received from this talker
+    pub received: u64,
+    /// Sentences used for the navigation solution
+    pub accepted: u64,
+    /// Sentences discarded by the talker policy
+    pub rejected: u64,
+}
+
+/// Statistics of every talker sentences were received from, by talker
+/// name. Published periodically, as text
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TalkerReport(pub Vec<(Talker, TalkerStats)>);
+
+impl fmt::Display for TalkerReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("talkers:")?;
+
+        for (i, (talker, stats)) in self.0.iter().enumerate() {
+            let separator = if i == 0 { " " } else { "; " };
+            write!(
+                f,
+                "{}{:?} received {}, accepted {}, rejected {}",
+                separator, talker, stats.received, stats.accepted, stats.rejected
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Apply a talker policy to incoming sentences and keep statistics
+#[derive(Default)]
+pub struct TalkerFilter {
+    /// When the last combined solution was received
+    last_combined: Option<Instant>,
+    stats: HashMap<Talker, TalkerStats>,
+}
+
+impl TalkerFilter {
+    pub fn new() -> TalkerFilter {
+        Default::default()
+    }
+
+    /// Check if a sentence from the given talker, received at `now`, should
+    /// be used under the given policy and update its statistics
+    pub fn accept(&mut self, policy: TalkerPolicy, talker: Talker, now: Instant) -> bool {
+        if talker.is_combined() {
+            self.last_combined = Some(now);
+        }
+
+        let accepted = match policy {
+            TalkerPolicy::Any => true,
+            TalkerPolicy::PreferCombined => {
+                !self.combined(now) || talker.is_combined() || talker == Talker::Unknown
+            }
+            TalkerPolicy::Only(t) => t == talker,
+        };
+
+        let entry = self.stats.entry(talker).or_default();
+        entry.received += 1;
+        if accepted {
+            entry.accepted += 1;
+        } else {
+            entry.rejected += 1;
+        }
+
+        accepted
+    }
+
+    /// Whether combined solutions were received lately
+    fn combined(&self, now: Instant) -> bool {
+        self.last_combined
+            .is_some_and(|last| now.saturating_duration_since(last) < COMBINED_TIMEOUT)
+    }
+
+    /// Forget about combined solutions, e.g. on reconnect where the receiver
+    /// may have been reconfigured. Statistics are kept
+    pub fn reset(&mut self) {
+        self.last_combined = None;
+    }
+
+    /// Statistics of the given talker, if any sentence was received from it
+    pub fn stats(&self, talker: Talker) -> Option<&TalkerStats> {
+        self.stats.get(&talker)
+    }
+
+    /// Statistics of every talker
+    pub fn report(&self) -> TalkerReport {
+        let mut stats: Vec<_> = self.stats.iter().map(|(t, s)| (*t, s.clone())).collect();
+        stats.sort_by_key(|(talker, _)| format!("{:?}", talker));
+        TalkerReport(stats)
+    }
+}
+
+mod tests {
+    use super::*;
+
+    #[test]
+    /// Every talker is accepted and counted
+    fn policy_any() {
+        let mut filter = TalkerFilter::new();
+        let now = Instant::now();
+
+        assert!(filter.accept(TalkerPolicy::Any, Talker::GP, now));
+        assert!(filter.accept(TalkerPolicy::Any, Talker::GN, now));
+        assert!(filter.accept(TalkerPolicy::Any, Talker::GL, now));
+
+        assert_eq!(filter.stats(Talker::GP).unwrap().accepted, 1);
+        assert_eq!(filter.stats(Talker::GN).unwrap().accepted, 1);
+        assert!(filter.stats(Talker::GA).is_none());
+    }
+
+    #[test]
+    /// Single constellation talkers are used until a combined solution arrives
+    fn policy_prefer_combined() {
+        let mut filter = TalkerFilter::new();
+        let policy = TalkerPolicy::PreferCombined;
+        let now = Instant::now();
+
+        assert!(filter.accept(policy, Talker::GP, now));
+        assert!(filter.accept(policy, Talker::GN, now));
+        assert!(!filter.accept(policy, Talker::GP, now));
+        assert!(!filter.accept(policy, Talker::GL, now));
+        assert!(filter.accept(policy, Talker::GN, now));
+
+        let gp = filter.stats(Talker::GP).unwrap();
+        assert_eq!(gp.received, 2);
+        assert_eq!(gp.accepted, 1);
+        assert_eq!(gp.rejected, 1);
+
+        let gn = filter.stats(Talker::GN).unwrap();
+        assert_eq!(gn.received, 2);
+        assert_eq!(gn.rejected, 0);
+    }
+
+    #[test]
+    /// Single constellation talkers are used again once combined solutions
+    /// stop or after a reset
+    fn policy_prefer_combined_expiry() {
+        let mut filter = TalkerFilter::new();
+        let policy = TalkerPolicy::PreferCombined;
+        let now = Instant::now();
+
+        assert!(filter.accept(policy, Talker::GN, now));
+        assert!(!filter.accept(policy, Talker::GP, now + COMBINED_TIMEOUT / 2));
+        assert!(filter.accept(policy, Talker::GP, now + COMBINED_TIMEOUT));
+
+        // combined solutions are back
+        let later = now + COMBINED_TIMEOUT * 2;
+        assert!(filter.accept(policy, Talker::GN, later));
+        assert!(!filter.accept(policy, Talker::GP, later));
+
+        filter.reset();
+        assert!(filter.accept(policy, Talker::GP, later));
+        assert_eq!(filter.stats(Talker::GP).unwrap().received, 4);
+    }
+
+    #[test]
+    fn report() {
+        let mut filter = TalkerFilter::new();
+        let policy = TalkerPolicy::PreferCombined;
+        let now = Instant::now();
+
+        filter.accept(policy, Talker::GP, now);
+        filter.accept(policy, Talker::GN, now);
+        filter.accept(policy, Talker::GP, now);
+
+        let gn = TalkerStats {
+            received: 1,
+            accepted: 1,
+            rejected: 0,
+        };
+        let gp = TalkerStats {
+            received: 2,
+            accepted: 1,
+            rejected: 1,
+        };
+        assert_eq!(
+            filter.report(),
+            TalkerReport(vec![(Talker::GN, gn), (Talker::GP, gp)])
+        );
+        assert_eq!(
+            filter.report().to_string(),
+            "talkers: GN received 1, accepted 1, rejected 0; GP received 2, accepted 1, rejected 1"
+        );
+    }
+
+    #[test]
+    /// Only the configured talker is accepted
+    fn policy_only() {
+        let mut filter = TalkerFilter::new();
+        let policy = TalkerPolicy::Only(Talker::GA);
+        let now = Instant::now();
+
+        assert!(!filter.accept(policy, Talker::GN, now));
+        assert!(!filter.accept(policy, Talker::GP, now));
+        assert!(filter.accept(policy, Talker::GA, now));
+
+        assert_eq!(filter.stats(Talker::GN).unwrap().rejected, 1);
+        assert_eq!(filter.stats(Talker::GA).unwrap().accepted, 1);
+    }
+}
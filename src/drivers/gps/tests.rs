@@ -1,9 +1,24 @@
-use crate::drivers::gps::nmea::Sentence;
-use crate::drivers::gps::sentences::DataGGA;
+use crate::drivers::gps::entity_state;
+use crate::drivers::gps::gate::{Gate, GateAction, Limits, Rejections};
+use crate::drivers::gps::geoid::Geoid;
+use crate::drivers::gps::quality::{Differential, Quality, SolutionStatus};
+use crate::drivers::gps::registry::{Conversion, ImcConversion};
+use crate::drivers::gps::target_info;
+use crate::drivers::gps::ubx;
+use crate::drivers::gps::Protocol;
 use crate::drivers::gps::Task;
+use crate::drivers::io::Stream;
+use crate::drivers::ntrip;
+use crate::TaskBehaviour;
 use crate::{drivers, task};
+use ix_nmea::registry::{DataCustom, FieldKind, SentenceDefinition, Value};
+use ix_nmea::sentences::{
+    DataGGA, DataGST, DataHDG, DataHDT, DataRMC, DataTHS, DataVDM, DataVTG, DataZDA,
+};
+use ix_nmea::{Sentence, Talker};
+use std::io::{Read, Write};
 use std::sync::atomic::AtomicBool;
-use std::sync::{Arc, Barrier};
+use std::sync::{Arc, Barrier, Mutex};
 
 #[test]
 fn handle_latitude() {
@@ -15,11 +30,14 @@ fn handle_latitude() {
         barrier: Arc::clone(&task_barrier),
     });
 
-    gps_task.handle_latitude(Some(12.02), Some(String::from("N")));
-    assert_eq!(gps_task.fix._lat, 12.02);
+    gps_task.handle_latitude(Some(4124.8963), Some('N'));
+    assert!((gps_task.fix._lat - 41.414938).abs() < 1e-6);
 
-    gps_task.handle_latitude(Some(12.02), Some(String::from("S")));
-    assert_eq!(gps_task.fix._lat, -12.02);
+    gps_task.handle_latitude(Some(4124.8963), Some('S'));
+    assert!((gps_task.fix._lat + 41.414938).abs() < 1e-6);
+
+    gps_task.handle_latitude(Some(12.0), Some('N'));
+    assert!((gps_task.fix._lat - 0.2).abs() < 1e-9);
 }
 
 #[test]
@@ -32,11 +50,11 @@ fn handle_longitude() {
         barrier: Arc::clone(&task_barrier),
     });
 
-    gps_task.handle_longitude(Some(12.02), Some(String::from("E")));
-    assert_eq!(gps_task.fix._lon, 12.02);
+    gps_task.handle_longitude(Some(12202.26957864), Some('E'));
+    assert!((gps_task.fix._lon - 122.037826).abs() < 1e-6);
 
-    gps_task.handle_longitude(Some(12.02), Some(String::from("W")));
-    assert_eq!(gps_task.fix._lon, -12.02);
+    gps_task.handle_longitude(Some(831.54), Some('W'));
+    assert!((gps_task.fix._lon + 8.525667).abs() < 1e-6);
 }
 
 #[test]
@@ -50,11 +68,12 @@ fn handle_gga() {
     });
 
     let gga = Sentence::GGA(DataGGA {
+        talker: Talker::GP,
         utc_time: Some(165035.0),
         lat: Some(28.608389),
-        ns: Some(String::from("N")),
+        ns: Some('N'),
         lon: Some(80.604333),
-        ew: Some(String::from("W")),
+        ew: Some('W'),
         validity: 1,
         sat: Some(14),
         hdop: Some(0.7),
@@ -84,3 +103,936 @@ fn handle_gga() {
         (imc::GpsFix::ValidityBits::GFV_VALID_HDOP as u16)
     );
 }
+
+#[test]
+fn handle_quality() {
+    let task_barrier = Arc::new(Barrier::new(1));
+    let mut task_flag = Arc::new(AtomicBool::new(true));
+
+    let mut task = Task::new(task::Context {
+        running: Arc::clone(&task_flag),
+        barrier: Arc::clone(&task_barrier),
+    });
+
+    let pos_valid =
+        |task: &Task| task.fix._validity & (imc::GpsFix::ValidityBits::GFV_VALID_POS as u16) != 0;
+
+    let gga = DataGGA {
+        talker: Talker::GP,
+        lat: Some(5109.0262),
+        ns: Some('N'),
+        lon: Some(11401.8407),
+        ew: Some('W'),
+        validity: 4,
+        sat: Some(12),
+        alt: Some(1097.36),
+        dgps_age: Some(1.5),
+        dgps_id: Some(String::from("0042")),
+        ..Default::default()
+    };
+
+    task.handle_sentence(Sentence::GGA(gga.clone()));
+    assert_eq!(task.quality, Quality::RtkFixed);
+    assert_eq!(
+        task.fix._type,
+        imc::GpsFix::TypeEnum::GFT_DIFFERENTIAL as u8
+    );
+    assert_eq!(
+        task.differential,
+        Some(Differential {
+            age: 1.5,
+            station: Some(String::from("0042"))
+        })
+    );
+    assert!(pos_valid(&task));
+
+    // published along with the fix
+    assert_eq!(
+        task.solution_status(),
+        SolutionStatus {
+            quality: Quality::RtkFixed,
+            differential: task.differential.clone(),
+            separation: None,
+        }
+    );
+
+    // dead reckoning
+    task.handle_sentence(Sentence::GGA(DataGGA {
+        validity: 6,
+        dgps_age: None,
+        dgps_id: None,
+        ..gga.clone()
+    }));
+    assert_eq!(
+        task.fix._type,
+        imc::GpsFix::TypeEnum::GFT_DEAD_RECKONING as u8
+    );
+    assert_eq!(task.differential, None);
+    assert!(pos_valid(&task));
+
+    // position fields are kept by some receivers after losing the fix
+    task.handle_sentence(Sentence::GGA(DataGGA {
+        validity: 0,
+        ..gga.clone()
+    }));
+    assert_eq!(task.quality, Quality::Invalid);
+    assert!(!pos_valid(&task));
+
+    // mode indicators
+    task.handle_sentence(Sentence::GGA(gga));
+    task.handle_sentence(Sentence::RMC(DataRMC {
+        status: Some('A'),
+        mode: Some('F'),
+        ..Default::default()
+    }));
+    assert_eq!(task.quality, Quality::RtkFloat);
+    assert!(pos_valid(&task));
+
+    task.handle_sentence(Sentence::VTG(DataVTG {
+        cog_true: Some(120.0),
+        sog_kph: Some(3.6),
+        mode: Some('N'),
+        ..Default::default()
+    }));
+    assert_eq!(
+        task.fix._validity & (imc::GpsFix::ValidityBits::GFV_VALID_COG as u16),
+        0
+    );
+
+    // status only, before NMEA 2.3
+    task.handle_sentence(Sentence::RMC(DataRMC {
+        status: Some('V'),
+        ..Default::default()
+    }));
+    assert!(!pos_valid(&task));
+}
+
+#[test]
+fn handle_gns() {
+    let task_barrier = Arc::new(Barrier::new(1));
+    let mut task_flag = Arc::new(AtomicBool::new(true));
+
+    let mut task = Task::new(task::Context {
+        running: Arc::clone(&task_flag),
+        barrier: Arc::clone(&task_barrier),
+    });
+
+    let gga = Sentence::GGA(DataGGA {
+        talker: Talker::GN,
+        lat: Some(4332.0),
+        ns: Some('S'),
+        lon: Some(17235.0),
+        ew: Some('E'),
+        validity: 1,
+        sat: Some(8),
+        alt: Some(20.0),
+        ..Default::default()
+    });
+
+    // GGA alone is used
+    task.handle_sentence(gga.clone());
+    assert_eq!(task.quality, Quality::Autonomous);
+    assert!((task.fix._lat - (-(43.0 + 32.0 / 60.0f64)).to_radians()).abs() < 1e-9);
+
+    task.handle_sentence(Sentence::GNS(DataGNS {
+        talker: Talker::GN,
+        lat: Some(4332.69262),
+        ns: Some('S'),
+        lon: Some(17235.48549),
+        ew: Some('E'),
+        mode: Some(String::from("RF")),
+        sat: Some(13),
+        hdop: Some(0.9),
+        alt: Some(25.63),
+        dgps_age: Some(1.2),
+        dgps_id: Some(String::from("0021")),
+        ..Default::default()
+    }));
+    assert_eq!(task.quality, Quality::RtkFixed);
+    assert_eq!(task.fix._satellites, 13);
+    assert_eq!(
+        task.fix._validity & (imc::GpsFix::ValidityBits::GFV_VALID_POS as u16),
+        imc::GpsFix::ValidityBits::GFV_VALID_POS as u16
+    );
+    assert_eq!(task.differential.as_ref().map(|d| d.age), Some(1.2));
+
+    // GNS is preferred from then on
+    task.handle_sentence(gga);
+    assert_eq!(task.quality, Quality::RtkFixed);
+    assert_eq!(task.fix._satellites, 13);
+    assert!((task.fix._lat - (-(43.0 + 32.69262 / 60.0f64)).to_radians()).abs() < 1e-9);
+
+    // no fix on any constellation
+    task.handle_sentence(Sentence::GNS(DataGNS {
+        talker: Talker::GN,
+        mode: Some(String::from("NN")),
+        ..Default::default()
+    }));
+    assert_eq!(
+        task.fix._validity & (imc::GpsFix::ValidityBits::GFV_VALID_POS as u16),
+        0
+    );
+}
+
+#[test]
+fn handle_height() {
+    let task_barrier = Arc::new(Barrier::new(1));
+    let mut task_flag = Arc::new(AtomicBool::new(true));
+
+    let mut task = Task::new(task::Context {
+        running: Arc::clone(&task_flag),
+        barrier: Arc::clone(&task_barrier),
+    });
+
+    let gga = parse(
+        "$GPGGA,172814.0,3723.46587704,N,12202.26957864,W,2,6,1.2,18.893,M,-25.669,M,2.0,0031*4F",
+    );
+
+    // not accumulated over sentences
+    for _ in 0..2 {
+        task.handle_sentence(gga.clone());
+        assert!(
+            (task.fix._height - -6.776).abs() < 1e-3,
+            "{}",
+            task.fix._height
+        );
+    }
+    assert_eq!(task.altitude, Some(18.893));
+    assert_eq!(task.separation, Some(-25.669));
+    assert!((task.fix._lat - 37.391098f64.to_radians()).abs() < 1e-8);
+    assert!((task.fix._lon - -122.037826f64.to_radians()).abs() < 1e-8);
+
+    // separation unknown, height above mean sea level
+    let gga =
+        parse("$GPGGA,172814.0,3723.46587704,N,12202.26957864,W,2,6,1.2,18.893,M,,M,2.0,0031*72");
+    task.handle_sentence(gga.clone());
+    assert!((task.fix._height - 18.893).abs() < 1e-3);
+    assert_eq!(task.separation, None);
+
+    // from the geoid model, looked up in degrees
+    task.geoid = Some(Geoid::parse("37 38 -123 -122 1 1  -20 -20  -30 -30").unwrap());
+    task.handle_sentence(gga);
+    let separation = -30.0 + 10.0 * 0.391098;
+    assert!((task.separation.unwrap() - separation).abs() < 1e-3);
+    assert!((task.fix._height - (18.893 + separation)).abs() < 1e-3);
+}
+
+#[test]
+fn handle_time() {
+    let task_barrier = Arc::new(Barrier::new(1));
+    let mut task_flag = Arc::new(AtomicBool::new(true));
+
+    let mut task = Task::new(task::Context {
+        running: Arc::clone(&task_flag),
+        barrier: Arc::clone(&task_barrier),
+    });
+
+    let time = imc::GpsFix::ValidityBits::GFV_VALID_TIME as u16;
+    let date = imc::GpsFix::ValidityBits::GFV_VALID_DATE as u16;
+
+    task.handle_sentence(Sentence::ZDA(DataZDA {
+        talker: Talker::GP,
+        utc: Some(201530.5),
+        ..Default::default()
+    }));
+    assert_eq!(task.fix._utc_time, 72930.5);
+    assert_eq!(task.fix._validity & (time | date), time);
+
+    task.handle_sentence(Sentence::ZDA(DataZDA {
+        talker: Talker::GP,
+        utc: Some(201530.5),
+        day: Some(4),
+        month: Some(7),
+        year: Some(2002),
+        ..Default::default()
+    }));
+    assert_eq!(
+        (task.fix._utc_day, task.fix._utc_month, task.fix._utc_year),
+        (4, 7, 2002)
+    );
+    assert_eq!(task.fix._validity & (time | date), time | date);
+
+    // date as ddmmyy
+    task.handle_sentence(Sentence::RMC(DataRMC {
+        utc_time: Some(235959.0),
+        date: Some(290224),
+        status: Some('A'),
+        mode: Some('A'),
+        ..Default::default()
+    }));
+    assert_eq!(task.fix._utc_time, 86399.0);
+    assert_eq!(
+        (task.fix._utc_day, task.fix._utc_month, task.fix._utc_year),
+        (29, 2, 2024)
+    );
+
+    // the fix is published on GGA, which comes first in the next epoch
+    let gga = |utc| {
+        Sentence::GGA(DataGGA {
+            talker: Talker::GP,
+            utc_time: Some(utc),
+            validity: 1,
+            ..Default::default()
+        })
+    };
+    task.handle_sentence(Sentence::RMC(DataRMC {
+        utc_time: Some(120000.0),
+        date: Some(290224),
+        status: Some('A'),
+        mode: Some('A'),
+        ..Default::default()
+    }));
+    task.handle_sentence(gga(120001.0));
+    assert_eq!(task.fix._utc_time, 43201.0);
+    assert_eq!(task.fix._validity & (time | date), time | date);
+
+    // past midnight, the date is the previous day's until the next RMC
+    task.handle_sentence(Sentence::RMC(DataRMC {
+        utc_time: Some(235959.0),
+        date: Some(290224),
+        status: Some('A'),
+        mode: Some('A'),
+        ..Default::default()
+    }));
+    task.handle_sentence(gga(0.0));
+    assert_eq!(task.fix._utc_time, 0.0);
+    assert_eq!(task.fix._validity & (time | date), time);
+}
+
+#[test]
+fn handle_gst() {
+    let task_barrier = Arc::new(Barrier::new(1));
+    let mut task_flag = Arc::new(AtomicBool::new(true));
+
+    let mut task = Task::new(task::Context {
+        running: Arc::clone(&task_flag),
+        barrier: Arc::clone(&task_barrier),
+    });
+
+    let gst = Sentence::GST(DataGST {
+        talker: Talker::GP,
+        utc: Some(172814.0),
+        rms: Some(0.006),
+        sd_major: Some(0.023),
+        sd_minor: Some(0.020),
+        orientation: Some(273.6),
+        sd_lat: Some(3.0),
+        sd_lon: Some(4.0),
+        sd_alt: Some(0.031),
+    });
+
+    task.handle_sentence(gst);
+    assert_eq!(task.fix._hacc, 5.0);
+    assert_eq!(task.fix._vacc, 0.031);
+
+    // valid horizontal and vertical accuracy
+    assert_eq!(
+        task.fix._validity & (imc::GpsFix::ValidityBits::GFV_VALID_HACC as u16),
+        (imc::GpsFix::ValidityBits::GFV_VALID_HACC as u16)
+    );
+    assert_eq!(
+        task.fix._validity & (imc::GpsFix::ValidityBits::GFV_VALID_VACC as u16),
+        (imc::GpsFix::ValidityBits::GFV_VALID_VACC as u16)
+    );
+
+    // missing altitude error invalidates vertical accuracy only
+    task.handle_sentence(Sentence::GST(DataGST {
+        sd_lat: Some(3.0),
+        sd_lon: Some(4.0),
+        ..Default::default()
+    }));
+    assert_ne!(
+        task.fix._validity & (imc::GpsFix::ValidityBits::GFV_VALID_HACC as u16),
+        0
+    );
+    assert_eq!(
+        task.fix._validity & (imc::GpsFix::ValidityBits::GFV_VALID_VACC as u16),
+        0
+    );
+}
+
+#[test]
+fn talker_policy() {
+    let task_barrier = Arc::new(Barrier::new(1));
+    let mut task_flag = Arc::new(AtomicBool::new(true));
+
+    let mut task = Task::new(task::Context {
+        running: Arc::clone(&task_flag),
+        barrier: Arc::clone(&task_barrier),
+    });
+    task.register_configuration();
+
+    let gga = |talker: Talker, hdop: f32| {
+        Sentence::GGA(DataGGA {
+            talker,
+            hdop: Some(hdop),
+            ..Default::default()
+        })
+    };
+
+    // single constellation solution is used while no combined one exists
+    task.handle_sentence(gga(Talker::GP, 1.5));
+    assert_eq!(task.fix._hdop, 1.5);
+
+    task.handle_sentence(gga(Talker::GN, 0.7));
+    assert_eq!(task.fix._hdop, 0.7);
+
+    // combined solution takes precedence from now on
+    task.handle_sentence(gga(Talker::GP, 1.5));
+    assert_eq!(task.fix._hdop, 0.7);
+
+    assert_eq!(task.talkers.stats(Talker::GP).unwrap().rejected, 1);
+    assert_eq!(task.talkers.stats(Talker::GN).unwrap().accepted, 1);
+}
+
+#[test]
+fn handle_ubx_pvt() {
+    let task_barrier = Arc::new(Barrier::new(1));
+    let mut task_flag = Arc::new(AtomicBool::new(true));
+
+    let mut task = Task::new(task::Context {
+        running: Arc::clone(&task_flag),
+        barrier: Arc::clone(&task_barrier),
+    });
+
+    let pvt = ubx::NavPvt {
+        year: 2022,
+        month: 10,
+        day: 12,
+        hour: 1,
+        min: 2,
+        sec: 3,
+        valid: ubx::PVT_VALID_DATE | ubx::PVT_VALID_TIME,
+        fix_type: ubx::FIX_3D,
+        flags: ubx::PVT_GNSS_FIX_OK | ubx::PVT_DIFF_SOLN,
+        num_sv: 12,
+        lat: 41.185021,
+        lon: -8.637485,
+        height: 120.5,
+        h_msl: 70.25,
+        h_acc: 1.5,
+        v_acc: 2.5,
+        g_speed: 3.0,
+        head_mot: 90.0,
+        ..Default::default()
+    };
+
+    task.handle_ubx(ubx::Message::NavPvt(pvt.clone()));
+    assert_eq!(
+        task.fix._type,
+        (imc::GpsFix::TypeEnum::GFT_DIFFERENTIAL as u8)
+    );
+    assert_eq!(task.fix._utc_year, 2022);
+    assert_eq!(task.fix._utc_time, 3723.0);
+    assert_eq!(task.fix._lat, 41.185021f64.to_radians());
+    assert_eq!(task.fix._lon, -8.637485f64.to_radians());
+    assert_eq!(task.fix._height, 120.5);
+    assert_eq!(task.altitude, Some(70.25));
+    assert_eq!(task.separation, Some(50.25));
+    assert_eq!(task.fix._hacc, 1.5);
+    assert_eq!(task.fix._sog, 3.0);
+    assert_eq!(task.fix._satellites, 12);
+
+    let valid = (imc::GpsFix::ValidityBits::GFV_VALID_POS as u16)
+        | (imc::GpsFix::ValidityBits::GFV_VALID_TIME as u16)
+        | (imc::GpsFix::ValidityBits::GFV_VALID_DATE as u16)
+        | (imc::GpsFix::ValidityBits::GFV_VALID_HACC as u16);
+    assert_eq!(task.fix._validity & valid, valid);
+
+    // losing the fix invalidates the position
+    task.handle_ubx(ubx::Message::NavPvt(ubx::NavPvt {
+        fix_type: ubx::FIX_NONE,
+        flags: 0,
+        ..pvt
+    }));
+    assert_eq!(
+        task.fix._validity & (imc::GpsFix::ValidityBits::GFV_VALID_POS as u16),
+        0
+    );
+
+    // nor time and date once the receiver reports them invalid
+    task.handle_ubx(ubx::Message::NavPvt(ubx::NavPvt { valid: 0, ..pvt }));
+    let time = (imc::GpsFix::ValidityBits::GFV_VALID_TIME as u16)
+        | (imc::GpsFix::ValidityBits::GFV_VALID_DATE as u16);
+    assert_eq!(task.fix._validity & time, 0);
+}
+
+#[test]
+fn mixed_protocols() {
+    let task_barrier = Arc::new(Barrier::new(1));
+    let mut task_flag = Arc::new(AtomicBool::new(true));
+
+    let mut task = Task::new(task::Context {
+        running: Arc::clone(&task_flag),
+        barrier: Arc::clone(&task_barrier),
+    });
+    task.cfg.protocol.set(Protocol::Mixed);
+
+    // a stray UBX sync byte right before a sentence
+    let mut data = vec![ubx::SYNC_1];
+    data.extend_from_slice(
+        b"$GPGGA,202530.00,5109.0262,N,11401.8407,W,5,40,0.5,1097.36,M,-17.00,M,18,TSTR*61",
+    );
+    task.process(&data);
+
+    assert_eq!(task.fix._satellites, 40);
+    assert!(task.ubx.is_idle());
+}
+
+#[test]
+fn gate_rejections() {
+    let task_barrier = Arc::new(Barrier::new(1));
+    let mut task_flag = Arc::new(AtomicBool::new(true));
+
+    let mut task = Task::new(task::Context {
+        running: Arc::clone(&task_flag),
+        barrier: Arc::clone(&task_barrier),
+    });
+
+    // gating is opt-in
+    task.register_configuration();
+    assert_eq!(*task.cfg.min_satellites.get(), None);
+    assert_eq!(*task.cfg.max_hdop.get(), None);
+    assert_eq!(*task.cfg.max_speed.get(), None);
+    assert_eq!(*task.cfg.fix_types.get(), Limits::default().fix_types);
+
+    task.gate = Gate::new(Limits {
+        min_satellites: Some(4),
+        ..Default::default()
+    });
+    assert_eq!(task.rejections_update(), None);
+
+    let mut fix = imc::GpsFix::new();
+    fix._type = imc::GpsFix::TypeEnum::GFT_STANDALONE as u8;
+    fix._satellites = 3;
+    assert!(task.gate.check(&fix).is_err());
+
+    // published once per change
+    assert_eq!(
+        task.rejections_update(),
+        Some(Rejections {
+            satellites: 1,
+            ..Default::default()
+        })
+    );
+    assert_eq!(task.rejections_update(), None);
+}
+
+#[test]
+fn gate_speed() {
+    let task_barrier = Arc::new(Barrier::new(1));
+    let task_flag = Arc::new(AtomicBool::new(true));
+
+    let mut task = Task::new(task::Context {
+        running: Arc::clone(&task_flag),
+        barrier: Arc::clone(&task_barrier),
+    });
+    task.register_configuration();
+    task.gate = Gate::new(Limits {
+        max_speed: Some(20.0),
+        ..Default::default()
+    });
+
+    let pos_valid =
+        |task: &Task| task.fix._validity & (imc::GpsFix::ValidityBits::GFV_VALID_POS as u16) != 0;
+
+    // ~11 m north in one second, sentences being handled back to back
+    task.handle_sentence(parse(
+        "$GPGGA,120000.00,4100.0000,N,00800.0000,W,1,08,0.9,10.00,M,50.00,M,,*45",
+    ));
+    assert!(task.gate_fix());
+    task.handle_sentence(parse(
+        "$GPGGA,120001.00,4100.0060,N,00800.0000,W,1,08,0.9,10.00,M,50.00,M,,*42",
+    ));
+    assert!(task.gate_fix());
+    assert!(pos_valid(&task));
+
+    // ~300 m jump a second later
+    let jump = parse("$GPGGA,120002.00,4100.1680,N,00800.0000,W,1,08,0.9,10.00,M,50.00,M,,*48");
+    task.handle_sentence(jump.clone());
+    assert!(task.gate_fix());
+    assert!(!pos_valid(&task));
+    assert_eq!(task.gate.rejections().speed, 1);
+
+    // dropped instead
+    task.cfg.gate_action.set(GateAction::Drop);
+    task.handle_sentence(jump);
+    assert!(!task.gate_fix());
+    assert_eq!(task.gate.rejections().speed, 2);
+
+    // checked against the last accepted fix, a minute later
+    task.handle_sentence(parse(
+        "$GPGGA,120101.00,4100.1680,N,00800.0000,W,1,08,0.9,10.00,M,50.00,M,,*4A",
+    ));
+    assert!(task.gate_fix());
+    assert!(pos_valid(&task));
+}
+
+#[test]
+fn handle_ubx_dop() {
+    let task_barrier = Arc::new(Barrier::new(1));
+    let mut task_flag = Arc::new(AtomicBool::new(true));
+
+    let mut task = Task::new(task::Context {
+        running: Arc::clone(&task_flag),
+        barrier: Arc::clone(&task_barrier),
+    });
+
+    task.handle_ubx(ubx::Message::NavDop(ubx::NavDop {
+        h_dop: 0.8,
+        v_dop: 1.2,
+        ..Default::default()
+    }));
+    assert_eq!(task.fix._hdop, 0.8);
+    assert_eq!(task.fix._vdop, 1.2);
+    assert_ne!(
+        task.fix._validity & (imc::GpsFix::ValidityBits::GFV_VALID_VDOP as u16),
+        0
+    );
+}
+
+#[test]
+fn handle_ais() {
+    let task_barrier = Arc::new(Barrier::new(1));
+    let mut task_flag = Arc::new(AtomicBool::new(true));
+
+    let mut task = Task::new(task::Context {
+        running: Arc::clone(&task_flag),
+        barrier: Arc::clone(&task_barrier),
+    });
+
+    // static data only, position unknown
+    let first = DataVDM {
+        fragments: 2,
+        fragment: 1,
+        sequence: Some(1),
+        channel: Some('A'),
+        payload: String::from("55?MbV02;H;s<HtKR20EHE:0@T4@Dn2222222216L961O5Gf0NSQEp6ClRp8"),
+        ..Default::default()
+    };
+    let second = DataVDM {
+        fragment: 2,
+        payload: String::from("88888888880"),
+        fill_bits: 2,
+        ..first.clone()
+    };
+    assert_eq!(task.handle_ais(&first), None);
+    assert_eq!(task.handle_ais(&second), None);
+
+    let position = DataVDM {
+        fragments: 1,
+        fragment: 1,
+        channel: Some('B'),
+        payload: String::from("177KQJ5000G?tO`K>RA1wUbN0TKH"),
+        ..Default::default()
+    };
+    let target = task.handle_ais(&position).unwrap();
+    assert_eq!(target.mmsi, 477553000);
+    assert_eq!(task.targets.len(), 2);
+
+    let info = target_info(&target);
+    assert_eq!(info._id, "477553000");
+    assert_eq!(info._sensor_class, "AIS");
+    assert!((info._lat - 47.582833f64.to_radians()).abs() < 1e-8);
+    assert!((info._lon - -122.345833f64.to_radians()).abs() < 1e-8);
+    assert_eq!(info._heading, 181f32.to_radians());
+    assert_eq!(info._data, "sog=0;cog=51");
+}
+
+#[test]
+fn handle_heading() {
+    let task_barrier = Arc::new(Barrier::new(1));
+    let mut task_flag = Arc::new(AtomicBool::new(true));
+
+    let mut task = Task::new(task::Context {
+        running: Arc::clone(&task_flag),
+        barrier: Arc::clone(&task_barrier),
+    });
+
+    assert!(task.handle_heading(Sentence::HDT(DataHDT {
+        heading: Some(274.0),
+        ..Default::default()
+    })));
+    assert!((task.heading._psi - (-86.0f64).to_radians()).abs() < 1e-6);
+    assert_eq!(task.heading._psi, task.heading._psi_magnetic);
+    // stamped on reception
+    assert!(task.heading._time > 0.0);
+
+    // sensor 98.3 + deviation 1.5 E = magnetic 99.8, - variation 12.6 W
+    assert!(task.handle_heading(Sentence::HDG(DataHDG {
+        heading: Some(98.3),
+        deviation: Some(1.5),
+        deviation_dir: Some('E'),
+        variation: Some(12.6),
+        variation_dir: Some('W'),
+        ..Default::default()
+    })));
+    assert!((task.heading._psi - 87.2f64.to_radians()).abs() < 1e-6);
+    assert!((task.heading._psi_magnetic - 99.8f64.to_radians()).abs() < 1e-6);
+
+    // true heading unknown without variation
+    let hdg = DataHDG {
+        heading: Some(10.0),
+        ..Default::default()
+    };
+    assert!(!task.handle_heading(Sentence::HDG(hdg.clone())));
+
+    task.cfg.variation.set(Some(-2.0));
+    assert!(task.handle_heading(Sentence::HDG(hdg)));
+    assert!((task.heading._psi - 8.0f64.to_radians()).abs() < 1e-6);
+    assert!((task.heading._psi_magnetic - 10.0f64.to_radians()).abs() < 1e-6);
+
+    assert!(task.handle_heading(Sentence::THS(DataTHS {
+        heading: Some(359.0),
+        mode: Some('A'),
+        ..Default::default()
+    })));
+    assert!((task.heading._psi - (-1.0f64).to_radians()).abs() < 1e-6);
+    assert!((task.heading._psi_magnetic - 1.0f64.to_radians()).abs() < 1e-6);
+
+    // invalid heading is dropped
+    assert!(!task.handle_heading(Sentence::THS(DataTHS {
+        heading: Some(12.0),
+        mode: Some('V'),
+        ..Default::default()
+    })));
+    assert!(!task.handle_heading(Sentence::HDT(Default::default())));
+    assert!((task.heading._psi - (-1.0f64).to_radians()).abs() < 1e-6);
+}
+
+#[test]
+fn handle_vtg_course() {
+    let task_barrier = Arc::new(Barrier::new(1));
+    let mut task_flag = Arc::new(AtomicBool::new(true));
+
+    let mut task = Task::new(task::Context {
+        running: Arc::clone(&task_flag),
+        barrier: Arc::clone(&task_barrier),
+    });
+
+    let vtg = DataVTG {
+        cog_magnetic: Some(359.0),
+        ..Default::default()
+    };
+
+    // magnetic course is not used without variation
+    task.handle_sentence(Sentence::VTG(vtg.clone()));
+    assert_eq!(
+        task.fix._validity & (imc::GpsFix::ValidityBits::GFV_VALID_COG as u16),
+        0
+    );
+
+    task.cfg.variation.set(Some(3.0));
+    task.handle_sentence(Sentence::VTG(vtg));
+    assert!((task.fix._cog - 2.0f32.to_radians()).abs() < 1e-6);
+
+    // true course is preferred
+    task.handle_sentence(Sentence::VTG(DataVTG {
+        cog_true: Some(120.0),
+        cog_magnetic: Some(359.0),
+        ..Default::default()
+    }));
+    assert!((task.fix._cog - 120.0f32.to_radians()).abs() < 1e-6);
+}
+
+/// Attitude from $PASHR, as an external crate would define it
+struct Pashr;
+
+impl SentenceDefinition for Pashr {
+    fn id(&self) -> &str {
+        "PASHR"
+    }
+
+    fn fields(&self) -> &[(&'static str, FieldKind)] {
+        &[
+            ("utc", FieldKind::Float),
+            ("heading", FieldKind::Float),
+            ("T", FieldKind::Char),
+            ("roll", FieldKind::Float),
+            ("pitch", FieldKind::Float),
+            ("heave", FieldKind::Float),
+            ("roll accuracy", FieldKind::Float),
+            ("pitch accuracy", FieldKind::Float),
+            ("heading accuracy", FieldKind::Float),
+            ("quality", FieldKind::Integer),
+            ("status", FieldKind::Integer),
+        ]
+    }
+}
+
+impl ImcConversion for Pashr {
+    fn convert(&self, data: &DataCustom, out: &mut Conversion) {
+        if let (Some(heading), Some(roll), Some(pitch)) =
+            (data.float(1), data.float(3), data.float(4))
+        {
+            out.attitude._psi = heading.to_radians();
+            out.attitude._phi = roll.to_radians();
+            out.attitude._theta = pitch.to_radians();
+            out.dispatch_attitude = true;
+        }
+    }
+}
+
+#[test]
+fn handle_custom() {
+    let task_barrier = Arc::new(Barrier::new(1));
+    let mut task_flag = Arc::new(AtomicBool::new(true));
+
+    let mut task = Task::new(task::Context {
+        running: Arc::clone(&task_flag),
+        barrier: Arc::clone(&task_barrier),
+    });
+    task.register_sentence(Pashr).unwrap();
+
+    let sentence = "$PASHR,085335.000,224.19,T,-01.26,+00.83,+00.00,0.101,0.113,0.267,1,0*06";
+    let mut parsed = None;
+    for b in sentence.bytes() {
+        if let Ok(Some(s)) = task.parser.push(b) {
+            parsed = Some(s);
+        }
+    }
+
+    let m = match parsed {
+        Some(Sentence::Custom(m)) => m,
+        s => panic!("unexpected {:?}", s),
+    };
+    assert_eq!(m.values[9], Value::Integer(1));
+
+    assert_eq!(task.handle_custom(&m), (false, true));
+    assert_eq!(task.heading._psi, 224.19f64.to_radians());
+    assert_eq!(task.heading._phi, (-1.26f64).to_radians());
+    assert_eq!(task.heading._theta, 0.83f64.to_radians());
+
+    // unknown definition
+    let m = DataCustom { definition: 1, ..m };
+    assert_eq!(task.handle_custom(&m), (false, false));
+}
+
+#[test]
+fn device_state() {
+    let state = entity_state(
+        imc::messages::EntityState::StateEnum::ESTA_FAULT as u8,
+        "device lost: broken pipe",
+    );
+    assert_eq!(
+        state._state,
+        imc::messages::EntityState::StateEnum::ESTA_FAULT as u8
+    );
+    assert_eq!(state._description, "device lost: broken pipe");
+}
+
+/// Device recording what is written to it
+#[derive(Clone, Default)]
+struct Recorder {
+    written: Arc<Mutex<Vec<u8>>>,
+}
+
+impl Read for Recorder {
+    fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+        Ok(0)
+    }
+}
+
+impl Write for Recorder {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.written.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Stream for Recorder {
+    fn try_clone(&self) -> std::io::Result<Box<dyn Stream>> {
+        Ok(Box::new(self.clone()))
+    }
+}
+
+#[test]
+fn rtcm_injection() {
+    let task_barrier = Arc::new(Barrier::new(1));
+    let mut task_flag = Arc::new(AtomicBool::new(true));
+
+    let mut task = Task::new(task::Context {
+        running: Arc::clone(&task_flag),
+        barrier: Arc::clone(&task_barrier),
+    });
+
+    // station coordinates (1005)
+    let frame = [
+        0xD3, 0x00, 0x13, 0x3E, 0xD7, 0xD3, 0x02, 0x02, 0x98, 0x0E, 0xDE, 0xEF, 0x34, 0xB4, 0xBD,
+        0x62, 0xAC, 0x09, 0x41, 0x98, 0x6F, 0x33, 0x36, 0x0B, 0x98,
+    ];
+
+    // dropped while the device is not open
+    task.inject_rtcm(&frame);
+    assert_eq!(task.rtcm_forwarded, 0);
+
+    let device = Recorder::default();
+    task.io = Some(Box::new(device.clone()));
+
+    // split over two messages, with garbage in between
+    let mut data = vec![0x00, 0x42];
+    data.extend_from_slice(&frame);
+    task.inject_rtcm(&data[..10]);
+    assert!(device.written.lock().unwrap().is_empty());
+    task.inject_rtcm(&data[10..]);
+    assert_eq!(*device.written.lock().unwrap(), frame);
+
+    // corrupted frames are not written
+    let mut corrupted = frame;
+    corrupted[24] ^= 0x01;
+    task.inject_rtcm(&corrupted);
+    assert_eq!(device.written.lock().unwrap().len(), frame.len());
+
+    assert_eq!(task.rtcm_forwarded, frame.len() as u64);
+    assert_eq!(task.rtcm.stats().crc_errors, 1);
+}
+
+#[test]
+fn ntrip_gga() {
+    let task_barrier = Arc::new(Barrier::new(1));
+    let task_flag = Arc::new(AtomicBool::new(true));
+
+    let mut task = Task::new(task::Context {
+        running: Arc::clone(&task_flag),
+        barrier: Arc::clone(&task_barrier),
+    });
+    let mut client = ntrip::Task::new(task::Context {
+        running: Arc::clone(&task_flag),
+        barrier: Arc::clone(&task_barrier),
+    });
+
+    // nothing to send before a fix
+    assert_eq!(client.gga(), None);
+
+    // received from the receiver, then sent upstream to the caster
+    task.handle_sentence(parse(
+        "$GPGGA,172814.0,3723.46587704,N,12202.26957864,W,2,6,1.2,18.893,M,-25.669,M,2.0,0031*4F",
+    ));
+    client.handle_status(&task.solution_status());
+    client.handle_fix(task.fix.clone());
+    assert_eq!(
+        client.gga().unwrap(),
+        "$GPGGA,172814.00,3723.46588,N,12202.26958,W,2,06,1.2,18.89,M,-25.67,M,,*63\r\n"
+    );
+
+    // the last valid position is kept
+    task.handle_sentence(parse("$GPGGA,172815.0,,,,,0,0,,,M,,M,,*40"));
+    client.handle_fix(task.fix.clone());
+    assert!(client
+        .gga()
+        .unwrap()
+        .starts_with("$GPGGA,172814.00,3723.46588,N,"));
+}
+
+/// Sentence of a complete NMEA line
+fn parse(line: &str) -> Sentence {
+    let mut parser = ix_nmea::Parser::new();
+    line.bytes()
+        .filter_map(|b| parser.push(b).ok().flatten())
+        .last()
+        .expect(line)
+}
@@ -0,0 +1,630 @@
+/// First synchronization byte of a UBX frame
+pub const SYNC_1: u8 = 0xB5;
+/// Second synchronization byte of a UBX frame
+pub const SYNC_2: u8 = 0x62;
+
+/// Navigation results class
+pub const CLASS_NAV: u8 = 0x01;
+/// Navigation position velocity time solution
+pub const NAV_PVT: u8 = 0x07;
+/// Dilution of precision
+pub const NAV_DOP: u8 = 0x04;
+/// Satellite information
+pub const NAV_SAT: u8 = 0x35;
+/// UTC time solution
+pub const NAV_TIMEUTC: u8 = 0x21;
+
+/// Acknowledgement class
+pub const CLASS_ACK: u8 = 0x05;
+/// Message acknowledged
+pub const ACK_ACK: u8 = 0x01;
+/// Message not acknowledged
+pub const ACK_NAK: u8 = 0x00;
+
+/// Configuration input class
+pub const CLASS_CFG: u8 = 0x06;
+
+/// Largest payload accepted by the parser
+pub const MAX_PAYLOAD: usize = 4096;
+
+#[derive(Debug, PartialEq)]
+pub enum State {
+    /// Parsing on going
+    OnGoing,
+    /// Invalid sync byte (should be 0xB5 0x62)
+    InvalidSync(u8),
+    /// Payload length above MAX_PAYLOAD
+    InvalidLength(u16),
+    /// Checksum mismatch between parser and frame (CK_A in the low byte)
+    ChecksumMismatch { expected: u16, received: u16 },
+}
+
+#[derive(Debug, PartialEq)]
+enum Field {
+    Sync1,
+    Sync2,
+    Class,
+    Id,
+    Length,
+    Payload,
+    ChecksumA,
+    ChecksumB,
+}
+
+/// A checksum validated UBX frame
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Frame {
+    pub class: u8,
+    pub id: u8,
+    pub payload: Vec<u8>,
+}
+
+pub struct Parser {
+    /// Current UBX field being parsed
+    field: Field,
+    /// Frame being parsed
+    frame: Frame,
+    /// Declared payload length
+    length: u16,
+    /// Number of length bytes read so far
+    length_bytes: u8,
+    /// Running Fletcher checksum
+    ck_a: u8,
+    ck_b: u8,
+    /// First checksum byte read from the frame
+    read_ck_a: u8,
+}
+
+impl Parser {
+    pub fn new() -> Parser {
+        Parser {
+            field: Field::Sync1,
+            frame: Default::default(),
+            length: 0,
+            length_bytes: 0,
+            ck_a: 0,
+            ck_b: 0,
+            read_ck_a: 0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.field = Field::Sync1;
+        self.frame = Default::default();
+        self.length = 0;
+        self.length_bytes = 0;
+        self.ck_a = 0;
+        self.ck_b = 0;
+        self.read_ck_a = 0;
+    }
+
+    /// True when the parser is waiting for the start of a new frame
+    pub fn is_idle(&self) -> bool {
+        self.field == Field::Sync1
+    }
+
+    pub fn fail_with(&mut self, e: State) -> Result<Frame, State> {
+        self.reset();
+        Err(e)
+    }
+
+    fn update_checksum(&mut self, b: u8) {
+        self.ck_a = self.ck_a.wrapping_add(b);
+        self.ck_b = self.ck_b.wrapping_add(self.ck_a);
+    }
+
+    pub fn push(&mut self, b: u8) -> Result<Frame, State> {
+        match self.field {
+            Field::Sync1 => {
+                if b == SYNC_1 {
+                    self.reset();
+                    self.field = Field::Sync2;
+                } else {
+                    return self.fail_with(State::InvalidSync(b));
+                }
+            }
+            Field::Sync2 => {
+                if b == SYNC_2 {
+                    self.field = Field::Class;
+                } else {
+                    return self.fail_with(State::InvalidSync(b));
+                }
+            }
+            Field::Class => {
+                self.update_checksum(b);
+                self.frame.class = b;
+                self.field = Field::Id;
+            }
+            Field::Id => {
+                self.update_checksum(b);
+                self.frame.id = b;
+                self.field = Field::Length;
+            }
+            Field::Length => {
+                self.update_checksum(b);
+                // little endian
+                self.length |= (b as u16) << (8 * self.length_bytes);
+                self.length_bytes += 1;
+
+                if self.length_bytes == 2 {
+                    if self.length as usize > MAX_PAYLOAD {
+                        let length = self.length;
+                        return self.fail_with(State::InvalidLength(length));
+                    }
+
+                    self.frame.payload.reserve(self.length as usize);
+                    self.field = if self.length == 0 {
+                        Field::ChecksumA
+                    } else {
+                        Field::Payload
+                    };
+                }
+            }
+            Field::Payload => {
+                self.update_checksum(b);
+                self.frame.payload.push(b);
+
+                if self.frame.payload.len() == self.length as usize {
+                    self.field = Field::ChecksumA;
+                }
+            }
+            Field::ChecksumA => {
+                self.read_ck_a = b;
+                self.field = Field::ChecksumB;
+            }
+            Field::ChecksumB => {
+                let expected = u16::from_le_bytes([self.ck_a, self.ck_b]);
+                let received = u16::from_le_bytes([self.read_ck_a, b]);
+
+                return if expected == received {
+                    let frame = std::mem::take(&mut self.frame);
+                    self.reset();
+                    Ok(frame)
+                } else {
+                    self.fail_with(State::ChecksumMismatch { expected, received })
+                };
+            }
+        }
+
+        Err(State::OnGoing)
+    }
+}
+
+/// Compute the 8-bit Fletcher checksum over the given bytes
+pub fn checksum(data: &[u8]) -> (u8, u8) {
+    data.iter().fold((0u8, 0u8), |(a, b), &v| {
+        let a = a.wrapping_add(v);
+        (a, b.wrapping_add(a))
+    })
+}
+
+/// Build a complete UBX frame, sync bytes and checksum included
+pub fn encode(class: u8, id: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(payload.len() + 8);
+    frame.extend_from_slice(&[SYNC_1, SYNC_2, class, id]);
+    frame.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+    frame.extend_from_slice(payload);
+
+    let (ck_a, ck_b) = checksum(&frame[2..]);
+    frame.push(ck_a);
+    frame.push(ck_b);
+
+    frame
+}
+
+// Decoded messages
+
+/// NAV-PVT valid flags
+pub const PVT_VALID_DATE: u8 = 0x01;
+pub const PVT_VALID_TIME: u8 = 0x02;
+/// NAV-PVT fix status flags
+pub const PVT_GNSS_FIX_OK: u8 = 0x01;
+pub const PVT_DIFF_SOLN: u8 = 0x02;
+
+/// NAV-PVT fix types
+pub const FIX_NONE: u8 = 0;
+pub const FIX_DEAD_RECKONING: u8 = 1;
+pub const FIX_2D: u8 = 2;
+pub const FIX_3D: u8 = 3;
+pub const FIX_GNSS_DEAD_RECKONING: u8 = 4;
+pub const FIX_TIME_ONLY: u8 = 5;
+
+/// NAV-TIMEUTC valid flags
+pub const TIMEUTC_VALID_UTC: u8 = 0x04;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NavPvt {
+    /// GPS time of week (ms)
+    pub itow: u32,
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub min: u8,
+    pub sec: u8,
+    /// Validity flags (PVT_VALID_*)
+    pub valid: u8,
+    /// Time accuracy estimate (ns)
+    pub t_acc: u32,
+    /// Fraction of second (ns), may be negative
+    pub nano: i32,
+    /// Fix type (FIX_*)
+    pub fix_type: u8,
+    /// Fix status flags (PVT_*)
+    pub flags: u8,
+    /// Satellites used in the solution
+    pub num_sv: u8,
+    /// Longitude (degrees)
+    pub lon: f64,
+    /// Latitude (degrees)
+    pub lat: f64,
+    /// Height above ellipsoid (meters)
+    pub height: f64,
+    /// Height above mean sea level (meters)
+    pub h_msl: f64,
+    /// Horizontal accuracy estimate (meters)
+    pub h_acc: f32,
+    /// Vertical accuracy estimate (meters)
+    pub v_acc: f32,
+    /// NED velocity (m/s)
+    pub vel_n: f32,
+    pub vel_e: f32,
+    pub vel_d: f32,
+    /// Ground speed (m/s)
+    pub g_speed: f32,
+    /// Heading of motion (degrees)
+    pub head_mot: f32,
+    /// Position DOP
+    pub p_dop: f32,
+}
+
+impl NavPvt {
+    /// Carrier phase range solution status (0: none, 1: float, 2: fixed)
+    pub fn carrier_solution(&self) -> u8 {
+        (self.flags >> 6) & 0x03
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NavDop {
+    /// GPS time of week (ms)
+    pub itow: u32,
+    pub g_dop: f32,
+    pub p_dop: f32,
+    pub t_dop: f32,
+    pub v_dop: f32,
+    pub h_dop: f32,
+    pub n_dop: f32,
+    pub e_dop: f32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SatInfo {
+    /// GNSS identifier (0: GPS, 1: SBAS, 2: Galileo, 3: BeiDou, 5: QZSS, 6: GLONASS)
+    pub gnss_id: u8,
+    pub sv_id: u8,
+    /// Carrier to noise ratio (dBHz)
+    pub cno: u8,
+    /// Elevation (degrees)
+    pub elev: i8,
+    /// Azimuth (degrees)
+    pub azim: i16,
+    /// Pseudorange residual (meters)
+    pub pr_res: f32,
+    pub flags: u32,
+}
+
+impl SatInfo {
+    /// Satellite is being used for navigation
+    pub fn used(&self) -> bool {
+        self.flags & 0x08 != 0
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NavSat {
+    /// GPS time of week (ms)
+    pub itow: u32,
+    pub svs: Vec<SatInfo>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NavTimeUtc {
+    /// GPS time of week (ms)
+    pub itow: u32,
+    /// Time accuracy estimate (ns)
+    pub t_acc: u32,
+    /// Fraction of second (ns), may be negative
+    pub nano: i32,
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub min: u8,
+    pub sec: u8,
+    /// Validity flags (TIMEUTC_VALID_*)
+    pub valid: u8,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message {
+    NavPvt(NavPvt),
+    NavDop(NavDop),
+    NavSat(NavSat),
+    NavTimeUtc(NavTimeUtc),
+}
+
+impl Message {
+    /// Decode a supported message from the given frame.
+    /// Returns None for unsupported messages or truncated payloads
+    pub fn decode(frame: &Frame) -> Option<Message> {
+        let p = frame.payload.as_slice();
+        if frame.class != CLASS_NAV {
+            return None;
+        }
+
+        match frame.id {
+            NAV_PVT if p.len() >= 92 => Some(Message::NavPvt(NavPvt {
+                itow: u32_at(p, 0),
+                year: u16_at(p, 4),
+                month: p[6],
+                day: p[7],
+                hour: p[8],
+                min: p[9],
+                sec: p[10],
+                valid: p[11],
+                t_acc: u32_at(p, 12),
+                nano: i32_at(p, 16),
+                fix_type: p[20],
+                flags: p[21],
+                num_sv: p[23],
+                lon: i32_at(p, 24) as f64 * 1e-7,
+                lat: i32_at(p, 28) as f64 * 1e-7,
+                height: i32_at(p, 32) as f64 / 1e3,
+                h_msl: i32_at(p, 36) as f64 / 1e3,
+                h_acc: u32_at(p, 40) as f32 / 1e3,
+                v_acc: u32_at(p, 44) as f32 / 1e3,
+                vel_n: i32_at(p, 48) as f32 / 1e3,
+                vel_e: i32_at(p, 52) as f32 / 1e3,
+                vel_d: i32_at(p, 56) as f32 / 1e3,
+                g_speed: i32_at(p, 60) as f32 / 1e3,
+                head_mot: i32_at(p, 64) as f32 / 1e5,
+                p_dop: u16_at(p, 76) as f32 / 100.0,
+            })),
+            NAV_DOP if p.len() >= 18 => Some(Message::NavDop(NavDop {
+                itow: u32_at(p, 0),
+                g_dop: u16_at(p, 4) as f32 / 100.0,
+                p_dop: u16_at(p, 6) as f32 / 100.0,
+                t_dop: u16_at(p, 8) as f32 / 100.0,
+                v_dop: u16_at(p, 10) as f32 / 100.0,
+                h_dop: u16_at(p, 12) as f32 / 100.0,
+                n_dop: u16_at(p, 14) as f32 / 100.0,
+                e_dop: u16_at(p, 16) as f32 / 100.0,
+            })),
+            NAV_SAT if p.len() >= 8 && p.len() >= 8 + 12 * p[5] as usize => {
+                let svs = p[8..8 + 12 * p[5] as usize]
+                    .chunks_exact(12)
+                    .map(|sv| SatInfo {
+                        gnss_id: sv[0],
+                        sv_id: sv[1],
+                        cno: sv[2],
+                        elev: sv[3] as i8,
+                        azim: i16_at(sv, 4),
+                        pr_res: i16_at(sv, 6) as f32 / 10.0,
+                        flags: u32_at(sv, 8),
+                    })
+                    .collect();
+
+                Some(Message::NavSat(NavSat {
+                    itow: u32_at(p, 0),
+                    svs,
+                }))
+            }
+            NAV_TIMEUTC if p.len() >= 20 => Some(Message::NavTimeUtc(NavTimeUtc {
+                itow: u32_at(p, 0),
+                t_acc: u32_at(p, 4),
+                nano: i32_at(p, 8),
+                year: u16_at(p, 12),
+                month: p[14],
+                day: p[15],
+                hour: p[16],
+                min: p[17],
+                sec: p[18],
+                valid: p[19],
+            })),
+            _ => None,
+        }
+    }
+}
+
+/// Seconds elapsed since midnight for the given UTC time of day
+pub fn seconds_of_day(hour: u8, min: u8, sec: u8, nano: i32) -> f32 {
+    (hour as u32 * 3600 + min as u32 * 60 + sec as u32) as f32 + nano as f32 * 1e-9
+}
+
+// Little endian field readers. Callers guarantee bounds.
+
+fn u16_at(p: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([p[offset], p[offset + 1]])
+}
+
+fn i16_at(p: &[u8], offset: usize) -> i16 {
+    i16::from_le_bytes([p[offset], p[offset + 1]])
+}
+
+fn u32_at(p: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([p[offset], p[offset + 1], p[offset + 2], p[offset + 3]])
+}
+
+fn i32_at(p: &[u8], offset: usize) -> i32 {
+    i32::from_le_bytes([p[offset], p[offset + 1], p[offset + 2], p[offset + 3]])
+}
+
+mod tests {
+    use super::*;
+
+    fn parse_all(parser: &mut Parser, bytes: &[u8]) -> Result<Frame, State> {
+        let mut ret = Err(State::OnGoing);
+        for b in bytes {
+            ret = parser.push(*b);
+        }
+
+        ret
+    }
+
+    #[test]
+    /// Checksum of a CFG-MSG poll as given in the u-blox protocol description
+    fn fletcher_checksum() {
+        let frame = encode(0x06, 0x01, &[0xF0, 0x05]);
+        assert_eq!(
+            frame,
+            vec![0xB5, 0x62, 0x06, 0x01, 0x02, 0x00, 0xF0, 0x05, 0xFE, 0x16]
+        );
+    }
+
+    #[test]
+    fn invalid_sync() {
+        let mut parser = Parser::new();
+
+        assert_eq!(parser.push(b'$'), Err(State::InvalidSync(b'$')));
+        assert!(parser.is_idle());
+
+        assert_eq!(parser.push(SYNC_1), Err(State::OnGoing));
+        assert_eq!(parser.push(0x00), Err(State::InvalidSync(0x00)));
+        assert!(parser.is_idle());
+    }
+
+    #[test]
+    fn valid_frame() {
+        let mut parser = Parser::new();
+        let ret = parse_all(&mut parser, &encode(CLASS_NAV, NAV_DOP, &[1, 2, 3]));
+
+        assert_eq!(
+            ret,
+            Ok(Frame {
+                class: CLASS_NAV,
+                id: NAV_DOP,
+                payload: vec![1, 2, 3],
+            })
+        );
+        assert!(parser.is_idle());
+    }
+
+    #[test]
+    fn empty_payload() {
+        let mut parser = Parser::new();
+        let ret = parse_all(&mut parser, &encode(0x05, 0x01, &[]));
+
+        assert!(ret.is_ok());
+        assert!(ret.unwrap().payload.is_empty());
+    }
+
+    #[test]
+    fn checksum_mismatch() {
+        let mut parser = Parser::new();
+        let mut frame = encode(CLASS_NAV, NAV_DOP, &[1, 2, 3]);
+        frame[7] = 4;
+
+        let ret = parse_all(&mut parser, &frame);
+        assert!(matches!(ret, Err(State::ChecksumMismatch { .. })));
+        assert!(parser.is_idle());
+    }
+
+    #[test]
+    fn oversized_length() {
+        let mut parser = Parser::new();
+        let ret = parse_all(
+            &mut parser,
+            &[SYNC_1, SYNC_2, CLASS_NAV, NAV_SAT, 0xFF, 0xFF],
+        );
+
+        assert_eq!(ret, Err(State::InvalidLength(0xFFFF)));
+        assert!(parser.is_idle());
+    }
+
+    #[test]
+    fn decode_nav_pvt() {
+        let mut payload = vec![0u8; 92];
+        payload[4..6].copy_from_slice(&2022u16.to_le_bytes());
+        payload[6] = 10;
+        payload[7] = 12;
+        payload[8] = 13;
+        payload[9] = 30;
+        payload[10] = 15;
+        payload[11] = PVT_VALID_DATE | PVT_VALID_TIME;
+        payload[20] = FIX_3D;
+        payload[21] = PVT_GNSS_FIX_OK | (2 << 6);
+        payload[23] = 17;
+        payload[24..28].copy_from_slice(&(-86374850i32).to_le_bytes());
+        payload[28..32].copy_from_slice(&411850210i32.to_le_bytes());
+        payload[32..36].copy_from_slice(&123456i32.to_le_bytes());
+        payload[40..44].copy_from_slice(&1500u32.to_le_bytes());
+        payload[60..64].copy_from_slice(&2500i32.to_le_bytes());
+        payload[64..68].copy_from_slice(&9000000i32.to_le_bytes());
+
+        let frame = Frame {
+            class: CLASS_NAV,
+            id: NAV_PVT,
+            payload,
+        };
+
+        match Message::decode(&frame) {
+            Some(Message::NavPvt(m)) => {
+                assert_eq!(m.year, 2022);
+                assert_eq!(m.month, 10);
+                assert_eq!(m.day, 12);
+                assert_eq!(m.fix_type, FIX_3D);
+                assert_eq!(m.carrier_solution(), 2);
+                assert_eq!(m.num_sv, 17);
+                assert!((m.lon - -8.637485).abs() < 1e-9);
+                assert!((m.lat - 41.185021).abs() < 1e-9);
+                assert!((m.height - 123.456).abs() < 1e-9);
+                assert_eq!(m.h_acc, 1.5);
+                assert_eq!(m.g_speed, 2.5);
+                assert_eq!(m.head_mot, 90.0);
+            }
+            _ => panic!("expected NAV-PVT"),
+        }
+    }
+
+    #[test]
+    fn decode_nav_sat() {
+        let mut payload = vec![0u8; 8 + 2 * 12];
+        payload[5] = 2;
+        // first satellite used, second one not
+        payload[8] = 0;
+        payload[9] = 12;
+        payload[16] = 0x08;
+        payload[20] = 6;
+        payload[21] = 3;
+
+        let frame = Frame {
+            class: CLASS_NAV,
+            id: NAV_SAT,
+            payload,
+        };
+
+        match Message::decode(&frame) {
+            Some(Message::NavSat(m)) => {
+                assert_eq!(m.svs.len(), 2);
+                assert_eq!(m.svs[0].sv_id, 12);
+                assert!(m.svs[0].used());
+                assert_eq!(m.svs[1].gnss_id, 6);
+                assert!(!m.svs[1].used());
+            }
+            _ => panic!("expected NAV-SAT"),
+        }
+    }
+
+    #[test]
+    /// Truncated payloads are not decoded
+    fn decode_truncated() {
+        let frame = Frame {
+            class: CLASS_NAV,
+            id: NAV_PVT,
+            payload: vec![0u8; 40],
+        };
+
+        assert!(Message::decode(&frame).is_none());
+    }
+}
@@ -0,0 +1,142 @@
+use std::fmt;
+use std::fmt::Formatter;
+use std::time::{Duration, Instant};
+
+/// Health of the receiver output
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Status {
+    /// Data and valid positions arriving in time
+    Ok,
+    /// No valid sentence within the data timeout
+    NoData,
+    /// Data arriving but no valid position within the fix timeout
+    NoFix,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Status::Ok => f.write_str("active"),
+            Status::NoData => f.write_str("no data received"),
+            Status::NoFix => f.write_str("no valid position"),
+        }
+    }
+}
+
+/// Detect a receiver that stopped talking or lost its fix. A zero timeout
+/// disables the corresponding check
+pub struct Watchdog {
+    data_timeout: Duration,
+    fix_timeout: Duration,
+    /// Last valid sentence, or start
+    last_data: Instant,
+    /// Last valid position, or start
+    last_fix: Instant,
+    status: Status,
+}
+
+impl Watchdog {
+    pub fn new(data_timeout: Duration, fix_timeout: Duration, now: Instant) -> Watchdog {
+        Watchdog {
+            data_timeout,
+            fix_timeout,
+            last_data: now,
+            last_fix: now,
+            status: Status::Ok,
+        }
+    }
+
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    /// A valid sentence or frame was received
+    pub fn data(&mut self, now: Instant) {
+        self.last_data = now;
+    }
+
+    /// A valid position was received
+    pub fn fix(&mut self, now: Instant) {
+        self.last_data = now;
+        self.last_fix = now;
+    }
+
+    /// Evaluate the timeouts. Returns the new status when it changed
+    pub fn check(&mut self, now: Instant) -> Option<Status> {
+        let expired = |last: Instant, timeout: Duration| {
+            !timeout.is_zero() && now.saturating_duration_since(last) > timeout
+        };
+
+        let status = if expired(self.last_data, self.data_timeout) {
+            Status::NoData
+        } else if expired(self.last_fix, self.fix_timeout) {
+            Status::NoFix
+        } else {
+            Status::Ok
+        };
+
+        if status == self.status {
+            return None;
+        }
+
+        self.status = status;
+        Some(status)
+    }
+}
+
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn no_data() {
+        let start = Instant::now();
+        let mut watchdog = Watchdog::new(ms(1000), ms(3000), start);
+
+        assert_eq!(watchdog.check(start + ms(1000)), None);
+        assert_eq!(watchdog.check(start + ms(1001)), Some(Status::NoData));
+        // reported once
+        assert_eq!(watchdog.check(start + ms(1500)), None);
+        assert_eq!(watchdog.status(), Status::NoData);
+
+        // data is back, with a valid position
+        watchdog.fix(start + ms(2000));
+        assert_eq!(watchdog.check(start + ms(2000)), Some(Status::Ok));
+    }
+
+    #[test]
+    fn no_fix() {
+        let start = Instant::now();
+        let mut watchdog = Watchdog::new(ms(1000), ms(3000), start);
+
+        // sentences keep arriving, without a position
+        for t in (0..=3000).step_by(500) {
+            watchdog.data(start + ms(t));
+            assert_eq!(watchdog.check(start + ms(t)), None);
+        }
+
+        watchdog.data(start + ms(3500));
+        assert_eq!(watchdog.check(start + ms(3500)), Some(Status::NoFix));
+
+        // the receiver then goes silent
+        assert_eq!(watchdog.check(start + ms(4600)), Some(Status::NoData));
+
+        watchdog.data(start + ms(5000));
+        assert_eq!(watchdog.check(start + ms(5000)), Some(Status::NoFix));
+
+        watchdog.fix(start + ms(5500));
+        assert_eq!(watchdog.check(start + ms(5500)), Some(Status::Ok));
+    }
+
+    #[test]
+    fn disabled() {
+        let start = Instant::now();
+        let mut watchdog = Watchdog::new(ms(0), ms(0), start);
+
+        assert_eq!(watchdog.check(start + Duration::from_secs(3600)), None);
+        assert_eq!(watchdog.status(), Status::Ok);
+    }
+}
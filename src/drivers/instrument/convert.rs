@@ -0,0 +1,238 @@
+use imc::{Depth, RelativeWind, Temperature, WaterVelocity, WindSpeed};
+
+use ix_nmea::Sentence;
+
+const FEET_TO_METERS: f32 = 0.3048;
+const FATHOMS_TO_METERS: f32 = 1.8288;
+const KNOTS_TO_MPS: f32 = 1852.0 / 3600.0;
+const KPH_TO_MPS: f32 = 1.0 / 3.6;
+const MPH_TO_MPS: f32 = 0.44704;
+
+/// IMC message produced from an instrument sentence
+pub enum Measurement {
+    Depth(Depth),
+    RelativeWind(RelativeWind),
+    WindSpeed(WindSpeed),
+    Temperature(Temperature),
+    WaterVelocity(WaterVelocity),
+}
+
+/// Convert an instrument sentence. Returns None for other sentences and for
+/// sentences without valid data
+pub fn convert(sentence: Sentence) -> Option<Measurement> {
+    match sentence {
+        Sentence::DBT(m) => {
+            let depth = m
+                .depth_meters
+                .or(m.depth_feet.map(|v| v * FEET_TO_METERS))
+                .or(m.depth_fathoms.map(|v| v * FATHOMS_TO_METERS))?;
+
+            Some(Measurement::Depth(depth_message(depth)))
+        }
+        Sentence::DPT(m) => {
+            // the offset gives the depth below the waterline or the keel
+            let depth = m.depth? + m.offset.unwrap_or(0.0);
+
+            Some(Measurement::Depth(depth_message(depth)))
+        }
+        Sentence::MWV(m) => {
+            // true (theoretical) wind is relative to the bow, not to north,
+            // and has no IMC counterpart
+            if m.status != Some('A') || m.reference != Some('R') {
+                return None;
+            }
+
+            let mut wind = RelativeWind::new();
+            wind._angle = m.angle?.to_radians();
+            wind._speed = speed(m.speed?, m.units?)?;
+
+            Some(Measurement::RelativeWind(wind))
+        }
+        Sentence::MWD(m) => {
+            let mut wind = WindSpeed::new();
+            wind._direction = m.direction_true?.to_radians();
+            wind._speed = m.speed_mps.or(m.speed_knots.map(|v| v * KNOTS_TO_MPS))?;
+
+            Some(Measurement::WindSpeed(wind))
+        }
+        Sentence::MTW(m) => {
+            let value = match m.units {
+                Some('C') | None => m.temperature?,
+                Some('F') => (m.temperature? - 32.0) * 5.0 / 9.0,
+                Some(_) => return None,
+            };
+
+            let mut temperature = Temperature::new();
+            temperature._value = value;
+
+            Some(Measurement::Temperature(temperature))
+        }
+        Sentence::VHW(m) => {
+            let speed = m
+                .speed_knots
+                .map(|v| v * KNOTS_TO_MPS)
+                .or(m.speed_kph.map(|v| v * KPH_TO_MPS))?;
+
+            // speed through water is measured along the longitudinal axis
+            let mut velocity = WaterVelocity::new();
+            velocity._x = speed as f64;
+            velocity._validity = imc::messages::WaterVelocity::ValidityBits::VAL_VEL_X as u8;
+
+            Some(Measurement::WaterVelocity(velocity))
+        }
+        _ => None,
+    }
+}
+
+fn depth_message(value: f32) -> Depth {
+    let mut depth = Depth::new();
+    depth._value = value;
+    depth
+}
+
+/// Speed in m/s from a value and its NMEA unit
+fn speed(value: f32, units: char) -> Option<f32> {
+    match units {
+        'M' => Some(value),
+        'K' => Some(value * KPH_TO_MPS),
+        'N' => Some(value * KNOTS_TO_MPS),
+        'S' => Some(value * MPH_TO_MPS),
+        _ => None,
+    }
+}
+
+mod tests {
+    use super::*;
+    use ix_nmea::sentences::{DataDBT, DataDPT, DataMTW, DataMWD, DataMWV, DataVHW};
+
+    #[test]
+    fn depth() {
+        let dbt = DataDBT {
+            depth_feet: Some(10.0),
+            ..Default::default()
+        };
+        match convert(Sentence::DBT(dbt.clone())) {
+            Some(Measurement::Depth(m)) => assert_eq!(m._value, 3.048),
+            _ => panic!("expected depth"),
+        }
+
+        // meters are preferred
+        match convert(Sentence::DBT(DataDBT {
+            depth_meters: Some(3.0),
+            ..dbt
+        })) {
+            Some(Measurement::Depth(m)) => assert_eq!(m._value, 3.0),
+            _ => panic!("expected depth"),
+        }
+
+        match convert(Sentence::DPT(DataDPT {
+            depth: Some(2.5),
+            offset: Some(0.5),
+            ..Default::default()
+        })) {
+            Some(Measurement::Depth(m)) => assert_eq!(m._value, 3.0),
+            _ => panic!("expected depth"),
+        }
+
+        assert!(convert(Sentence::DBT(Default::default())).is_none());
+        assert!(convert(Sentence::DPT(Default::default())).is_none());
+    }
+
+    #[test]
+    fn wind() {
+        let mwv = DataMWV {
+            angle: Some(90.0),
+            reference: Some('R'),
+            speed: Some(10.0),
+            units: Some('N'),
+            status: Some('A'),
+            ..Default::default()
+        };
+        match convert(Sentence::MWV(mwv.clone())) {
+            Some(Measurement::RelativeWind(m)) => {
+                assert_eq!(m._angle, 90.0f32.to_radians());
+                assert!((m._speed - 5.144444).abs() < 1e-5);
+            }
+            _ => panic!("expected relative wind"),
+        }
+
+        // invalid, true wind and unknown units are dropped
+        for m in [
+            DataMWV {
+                status: Some('V'),
+                ..mwv.clone()
+            },
+            DataMWV {
+                reference: Some('T'),
+                ..mwv.clone()
+            },
+            DataMWV {
+                units: Some('X'),
+                ..mwv
+            },
+        ] {
+            assert!(convert(Sentence::MWV(m)).is_none());
+        }
+
+        match convert(Sentence::MWD(DataMWD {
+            direction_true: Some(180.0),
+            direction_magnetic: Some(183.0),
+            speed_knots: Some(12.4),
+            speed_mps: Some(6.4),
+            ..Default::default()
+        })) {
+            Some(Measurement::WindSpeed(m)) => {
+                assert_eq!(m._direction, 180.0f32.to_radians());
+                assert_eq!(m._speed, 6.4);
+            }
+            _ => panic!("expected wind speed"),
+        }
+    }
+
+    #[test]
+    fn temperature() {
+        match convert(Sentence::MTW(DataMTW {
+            temperature: Some(17.75),
+            units: Some('C'),
+            ..Default::default()
+        })) {
+            Some(Measurement::Temperature(m)) => assert_eq!(m._value, 17.75),
+            _ => panic!("expected temperature"),
+        }
+
+        match convert(Sentence::MTW(DataMTW {
+            temperature: Some(50.0),
+            units: Some('F'),
+            ..Default::default()
+        })) {
+            Some(Measurement::Temperature(m)) => assert_eq!(m._value, 10.0),
+            _ => panic!("expected temperature"),
+        }
+    }
+
+    #[test]
+    fn water_velocity() {
+        match convert(Sentence::VHW(DataVHW {
+            speed_kph: Some(36.0),
+            ..Default::default()
+        })) {
+            Some(Measurement::WaterVelocity(m)) => {
+                assert_eq!(m._x, 10.0);
+                assert_eq!(
+                    m._validity,
+                    imc::messages::WaterVelocity::ValidityBits::VAL_VEL_X as u8
+                );
+            }
+            _ => panic!("expected water velocity"),
+        }
+
+        assert!(convert(Sentence::VHW(Default::default())).is_none());
+    }
+
+    #[test]
+    /// GNSS sentences are not converted
+    fn other_sentences() {
+        assert!(convert(Sentence::GGA(Default::default())).is_none());
+        assert!(convert(Sentence::Invalid).is_none());
+    }
+}
@@ -0,0 +1,352 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix::prelude::*;
+use actix_broker::{BrokerIssue, BrokerSubscribe, SystemBroker};
+use ix_nmea::ErrorKind;
+
+use crate::drivers::gps::framer::{Frame, LineFramer, MAX_LINE_LEN};
+use crate::drivers::io;
+use crate::drivers::io::reader::{Closed, Received};
+use crate::drivers::io::{Backoff, Stream};
+use crate::ix::Parameter;
+use crate::task;
+use crate::task::entity_state;
+use crate::BrokerType;
+use crate::MessageWrapper;
+use crate::TaskBehaviour;
+
+mod convert;
+
+use convert::Measurement;
+
+/// In milliseconds, period of the parse error report
+const ERROR_REPORT_PERIOD: u64 = 10000;
+
+#[derive(Default)]
+pub struct Configuration {
+    pub enabled: Parameter<bool>,
+    pub io_dev: Parameter<String>,
+    pub baud: Parameter<u32>,
+    pub io_timeout: Parameter<u64>,
+    pub reconnect_delay: Parameter<u64>,
+    pub reconnect_max_delay: Parameter<u64>,
+}
+
+/// Driver for NMEA 0183 instruments: echo sounders, anemometers, water
+/// temperature and speed log sensors
+pub struct Task {
+    pub ctx: task::Context,
+    pub parser: ix_nmea::Parser,
+    pub framer: LineFramer,
+    pub io: Option<Box<dyn Stream>>,
+    /// Lines that failed to parse since last reported
+    errors: u64,
+    /// Last of these failures
+    last_error: Option<String>,
+    /// Delay between attempts to reopen the device
+    backoff: Backoff,
+    cfg: Configuration,
+}
+
+impl TaskBehaviour for Task {
+    fn get_ctx(&self) -> &task::Context {
+        &self.ctx
+    }
+
+    fn get_name(&self) -> &str {
+        "NMEA Instrument"
+    }
+
+    fn register_configuration(&mut self) {
+        self.cfg
+            .enabled
+            .name("Enabled")
+            .default(false)
+            .description("Read measurements from the instrument");
+
+        self.cfg
+            .io_dev
+            .name("IO Device")
+            .default(String::from("/dev/ttyUSB1"))
+            .description("Device path or URI used to connect to the instrument");
+
+        self.cfg
+            .baud
+            .name("IO - Baud Rate")
+            .default(4800)
+            .description("Baud rate applied to serial devices whose URI has none");
+
+        self.cfg
+            .io_timeout
+            .name("IO - Communications Timeout")
+            .default(10)
+            .description(
+                "In milliseconds, read timeout of the device. 0 blocks until data arrives",
+            );
+
+        self.cfg
+            .reconnect_delay
+            .name("IO - Reconnect Delay")
+            .default(500)
+            .description("In milliseconds, delay before reopening a lost device. Doubled on every failed attempt");
+
+        self.cfg
+            .reconnect_max_delay
+            .name("IO - Maximum Reconnect Delay")
+            .default(30000)
+            .description("In milliseconds, upper bound of the reconnect delay");
+    }
+}
+
+impl Task {
+    pub fn new(context: task::Context) -> Task {
+        Task {
+            ctx: context,
+            parser: ix_nmea::Parser::new(),
+            framer: LineFramer::new(),
+            io: None,
+            errors: 0,
+            last_error: None,
+            backoff: Backoff::new(Duration::from_millis(500), Duration::from_secs(30)),
+            cfg: Default::default(),
+        }
+    }
+
+    /// Publish a measurement
+    fn dispatch(&self, measurement: Measurement) {
+        match measurement {
+            Measurement::Depth(m) => send_message!(self, imc::Depth, m),
+            Measurement::RelativeWind(m) => send_message!(self, imc::RelativeWind, m),
+            Measurement::WindSpeed(m) => send_message!(self, imc::WindSpeed, m),
+            Measurement::Temperature(m) => send_message!(self, imc::Temperature, m),
+            Measurement::WaterVelocity(m) => send_message!(self, imc::WaterVelocity, m),
+        }
+    }
+
+    /// Report the state of the instrument
+    fn dispatch_state(&self, state: u8, description: &str) {
+        send_message!(self, imc::EntityState, entity_state(state, description));
+    }
+
+    /// Open the device and start reading it. Failures are retried with
+    /// backoff
+    fn connect(&mut self, ctx: &mut Context<Self>) {
+        let device = self.cfg.io_dev.get().clone();
+        println!("opening {} with {}", device, *self.cfg.baud.get());
+
+        let opened = io::open(
+            &device,
+            *self.cfg.baud.get(),
+            Duration::from_millis(*self.cfg.io_timeout.get()),
+        )
+        .and_then(|io| io.try_clone().map(|reader| (io, reader)));
+
+        let (io, reader) = match opened {
+            Ok(streams) => streams,
+            Err(e) => {
+                println!("ERROR: {}: {}", device, e);
+                self.reconnect(ctx, &format!("failed to open device: {}", e));
+                return;
+            }
+        };
+
+        // nothing left over from a previous connection
+        self.parser.reset();
+        self.framer = LineFramer::new();
+        self.io = Some(io);
+
+        let addr = ctx.address();
+        let closed = ctx.address();
+        io::reader::spawn(
+            &device,
+            reader,
+            Arc::clone(&self.ctx.running),
+            move |data| {
+                addr.do_send(Received(data));
+                addr.connected()
+            },
+            move |e| closed.do_send(Closed(e)),
+        );
+
+        self.backoff.reset();
+        self.dispatch_state(
+            imc::messages::EntityState::StateEnum::ESTA_NORMAL as u8,
+            "active",
+        );
+    }
+
+    /// Close the device and schedule reopening it
+    fn reconnect(&mut self, ctx: &mut Context<Self>, reason: &str) {
+        self.io = None;
+        self.dispatch_state(
+            imc::messages::EntityState::StateEnum::ESTA_FAULT as u8,
+            reason,
+        );
+
+        if !self.is_running() {
+            return;
+        }
+
+        let delay = self.backoff.next();
+        println!("{}: reopening in {:?}", self.cfg.io_dev.get(), delay);
+        ctx.run_later(delay, |task, ctx| task.connect(ctx));
+    }
+
+    /// Feed a byte to the NMEA parser, framing lines. Returns the
+    /// measurement of a completed sentence, if any. A line ending without a
+    /// checksum is dropped
+    pub fn push(&mut self, b: u8) -> Option<Measurement> {
+        match self.framer.push(b) {
+            Frame::Data => {}
+            Frame::Line(_) => {
+                self.parser.reset();
+                return None;
+            }
+            Frame::Overflow => {
+                self.error(format!("line longer than {} bytes", MAX_LINE_LEN));
+                self.parser.reset();
+                return None;
+            }
+            Frame::Discarded => return None,
+        }
+
+        match self.parser.push(b) {
+            Ok(Some(sentence)) => convert::convert(sentence),
+            Ok(None) => None,
+            // bytes between sentences
+            Err(ix_nmea::Error {
+                kind: ErrorKind::InvalidSync(_),
+                ..
+            }) => None,
+            Err(e) => {
+                self.error(e.to_string());
+                None
+            }
+        }
+    }
+
+    /// Count a parse error, they are reported periodically
+    fn error(&mut self, description: String) {
+        self.errors += 1;
+        self.last_error = Some(description);
+    }
+
+    /// Main loop, reports parse errors
+    fn on_main(&mut self, _context: &mut Context<Self>) {
+        if let Some(last) = self.last_error.take() {
+            println!(
+                "ERROR: {} parse errors in {} ms, last: {}",
+                self.errors, ERROR_REPORT_PERIOD, last
+            );
+            self.errors = 0;
+        }
+    }
+}
+
+impl Actor for Task {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        if !*self.cfg.enabled.get() {
+            println!("{}: disabled", self.get_name());
+            return;
+        }
+
+        self.backoff = Backoff::new(
+            Duration::from_millis(*self.cfg.reconnect_delay.get()),
+            Duration::from_millis(*self.cfg.reconnect_max_delay.get()),
+        );
+
+        self.connect(ctx);
+
+        start_main_loop!(ERROR_REPORT_PERIOD, ctx);
+    }
+
+    fn stopped(&mut self, _ctx: &mut Self::Context) {
+        println!("{} stopped", self.get_name());
+    }
+}
+
+impl Handler<Received> for Task {
+    type Result = ();
+
+    fn handle(&mut self, msg: Received, _ctx: &mut Self::Context) {
+        for b in msg.0 {
+            if let Some(measurement) = self.push(b) {
+                self.dispatch(measurement);
+            }
+        }
+    }
+}
+
+impl Handler<Closed> for Task {
+    type Result = ();
+
+    fn handle(&mut self, msg: Closed, ctx: &mut Self::Context) {
+        // a replayed capture is over, not lost
+        if msg.end_of_file() && io::is_capture(self.cfg.io_dev.get()) {
+            println!("{}: end of capture", self.cfg.io_dev.get());
+            self.io = None;
+            self.dispatch_state(
+                imc::messages::EntityState::StateEnum::ESTA_FAULT as u8,
+                "end of capture",
+            );
+            return;
+        }
+
+        println!("ERROR: {}: {}", self.cfg.io_dev.get(), msg.0);
+        self.reconnect(ctx, &format!("device lost: {}", msg.0));
+    }
+}
+
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Barrier;
+
+    fn task() -> Task {
+        Task::new(task::Context {
+            running: Arc::new(AtomicBool::new(true)),
+            barrier: Arc::new(Barrier::new(1)),
+        })
+    }
+
+    fn push(task: &mut Task, data: &[u8]) -> Vec<Measurement> {
+        data.iter().filter_map(|&b| task.push(b)).collect()
+    }
+
+    #[test]
+    /// A sentence without checksum ends with its line, not with the next one
+    fn framing() {
+        let mut task = task();
+
+        let measurements = push(
+            &mut task,
+            b"$SDDBT,10.0,f,3.0,M,1.6,F\r\n$SDDBT,32.8,f,10.0,M,5.5,F*0E\r\n",
+        );
+        assert_eq!(measurements.len(), 1);
+        match &measurements[0] {
+            Measurement::Depth(m) => assert_eq!(m._value, 10.0),
+            _ => panic!("expected depth"),
+        }
+        assert_eq!(task.errors, 0);
+    }
+
+    #[test]
+    /// Errors are counted, not printed one by one
+    fn errors() {
+        let mut task = task();
+
+        let measurements = push(
+            &mut task,
+            b"$SDDBT,32.8,f,10.0,M,5.5,F*0F\r\n$SDDBT,32.8,f,10.0,M,5.5,F*0F\r\n",
+        );
+        assert!(measurements.is_empty());
+        assert_eq!(task.errors, 2);
+        assert!(task
+            .last_error
+            .unwrap()
+            .contains("checksum mismatch: expected 0E, received 0F"));
+    }
+}
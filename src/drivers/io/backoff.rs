@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+/// Exponential backoff between attempts, e.g. to reopen a device
+#[derive(Clone, Debug, PartialEq)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    current: Duration,
+}
+
+impl Backoff {
+    pub fn new(initial: Duration, max: Duration) -> Backoff {
+        Backoff {
+            initial,
+            max,
+            current: initial,
+        }
+    }
+
+    /// Delay before the next attempt, doubled every time up to the maximum
+    pub fn next(&mut self) -> Duration {
+        let delay = self.current;
+        self.current = (self.current * 2).min(self.max);
+        delay
+    }
+
+    /// Start over after a successful attempt
+    pub fn reset(&mut self) {
+        self.current = self.initial;
+    }
+}
+
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff() {
+        let mut backoff = Backoff::new(Duration::from_millis(500), Duration::from_secs(3));
+
+        let delays: Vec<u64> = (0..6).map(|_| backoff.next().as_millis() as u64).collect();
+        assert_eq!(delays, vec![500, 1000, 2000, 3000, 3000, 3000]);
+
+        backoff.reset();
+        assert_eq!(backoff.next(), Duration::from_millis(500));
+    }
+}
@@ -0,0 +1,338 @@
+use std::fs::File;
+use std::io;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs, UdpSocket};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serialport::SerialPort;
+
+mod backoff;
+pub mod reader;
+mod uri;
+
+pub use backoff::Backoff;
+pub use uri::{Uri, UriError};
+
+/// Time to establish TCP connections
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Read timeout standing for blocking reads. Reads still return now and then
+/// for the reader thread to notice it was stopped
+const BLOCKING_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Byte stream shared by serial-style drivers, whatever the transport
+pub trait Stream: Read + Write + Send {
+    /// Independent handle on the same stream, e.g. for a reader thread
+    fn try_clone(&self) -> io::Result<Box<dyn Stream>>;
+}
+
+/// Open the stream at the given URI. The baud rate applies to serial ports
+/// that do not specify one, the timeout bounds blocking reads. A zero
+/// timeout blocks until data arrives
+pub fn open(uri: &str, baud: u32, timeout: Duration) -> io::Result<Box<dyn Stream>> {
+    let uri = parse(uri)?;
+
+    // serial ports would not wait at all, sockets reject it
+    let timeout = if timeout.is_zero() {
+        BLOCKING_TIMEOUT
+    } else {
+        timeout
+    };
+
+    match uri {
+        Uri::Uart { path, baud: rate } => {
+            let port = serialport::new(path, rate.unwrap_or(baud))
+                .timeout(timeout)
+                .open()?;
+            Ok(Box::new(port))
+        }
+        Uri::Tcp { address } => {
+            let stream = connect(&address)?;
+            stream.set_read_timeout(Some(timeout))?;
+            Ok(Box::new(stream))
+        }
+        Uri::Udp { address } => {
+            let socket = UdpSocket::bind(address)?;
+            socket.set_read_timeout(Some(timeout))?;
+            Ok(Box::new(UdpStream {
+                socket,
+                peer: Arc::new(Mutex::new(None)),
+            }))
+        }
+        Uri::File { path } => Ok(Box::new(File::open(path)?)),
+        Uri::Pty { link } => open_pty(link),
+    }
+}
+
+/// Whether the URI is a capture replayed from a file. Its end is final, it
+/// is not reopened
+pub fn is_capture(uri: &str) -> bool {
+    matches!(parse(uri), Ok(Uri::File { .. }))
+}
+
+fn parse(uri: &str) -> io::Result<Uri> {
+    uri.parse()
+        .map_err(|e: UriError| io::Error::new(ErrorKind::InvalidInput, e.to_string()))
+}
+
+/// Connect to the first address the host resolves to, within
+/// CONNECT_TIMEOUT
+fn connect(address: &str) -> io::Result<TcpStream> {
+    let address = address
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(ErrorKind::NotFound, "no address for host"))?;
+
+    TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)
+}
+
+impl Stream for Box<dyn SerialPort> {
+    fn try_clone(&self) -> io::Result<Box<dyn Stream>> {
+        Ok(Box::new(SerialPort::try_clone(self.as_ref())?))
+    }
+}
+
+impl Stream for TcpStream {
+    fn try_clone(&self) -> io::Result<Box<dyn Stream>> {
+        Ok(Box::new(TcpStream::try_clone(self)?))
+    }
+}
+
+/// Captures are read only, writes fail
+impl Stream for File {
+    fn try_clone(&self) -> io::Result<Box<dyn Stream>> {
+        Ok(Box::new(File::try_clone(self)?))
+    }
+}
+
+/// UDP socket answering the last peer data was received from
+pub struct UdpStream {
+    socket: UdpSocket,
+    peer: Arc<Mutex<Option<SocketAddr>>>,
+}
+
+impl Read for UdpStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let (n, peer) = self.socket.recv_from(buf)?;
+        *self.peer.lock().unwrap() = Some(peer);
+        Ok(n)
+    }
+}
+
+impl Write for UdpStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let peer = *self.peer.lock().unwrap();
+        match peer {
+            Some(peer) => self.socket.send_to(buf, peer),
+            None => Err(io::Error::new(ErrorKind::NotConnected, "no peer yet")),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Stream for UdpStream {
+    fn try_clone(&self) -> io::Result<Box<dyn Stream>> {
+        Ok(Box::new(UdpStream {
+            socket: self.socket.try_clone()?,
+            peer: Arc::clone(&self.peer),
+        }))
+    }
+}
+
+/// Create a pseudo terminal, the slave side being left for a simulator or a
+/// capture replay tool
+#[cfg(unix)]
+fn open_pty(link: Option<String>) -> io::Result<Box<dyn Stream>> {
+    let (master, slave) = serialport::TTYPort::pair()?;
+    let name = slave.name().unwrap_or_default();
+
+    if let Some(link) = link {
+        // replace a link left over by a previous run
+        let _ = std::fs::remove_file(&link);
+        std::os::unix::fs::symlink(&name, &link)?;
+        println!("pty {} linked from {}", name, link);
+    } else {
+        println!("pty {}", name);
+    }
+
+    Ok(Box::new(PtyStream {
+        master,
+        _slave: Arc::new(slave),
+    }))
+}
+
+#[cfg(not(unix))]
+fn open_pty(_link: Option<String>) -> io::Result<Box<dyn Stream>> {
+    Err(io::Error::new(
+        ErrorKind::Unsupported,
+        "pseudo terminals are only available on unix",
+    ))
+}
+
+/// Master side of a pseudo terminal. The slave side is kept open, otherwise
+/// reads fail until a client opens it
+#[cfg(unix)]
+pub struct PtyStream {
+    master: serialport::TTYPort,
+    _slave: Arc<serialport::TTYPort>,
+}
+
+#[cfg(unix)]
+impl Read for PtyStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.master.read(buf)
+    }
+}
+
+#[cfg(unix)]
+impl Write for PtyStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.master.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.master.flush()
+    }
+}
+
+#[cfg(unix)]
+impl Stream for PtyStream {
+    fn try_clone(&self) -> io::Result<Box<dyn Stream>> {
+        Ok(Box::new(PtyStream {
+            master: self.master.try_clone_native()?,
+            _slave: Arc::clone(&self._slave),
+        }))
+    }
+}
+
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let uri = format!("tcp://{}", listener.local_addr().unwrap());
+
+        let mut stream = open(&uri, 0, Duration::from_millis(100)).unwrap();
+        let (mut client, _) = listener.accept().unwrap();
+
+        client.write_all(b"$GPVTG").unwrap();
+        let mut bfr = [0u8; 16];
+        let n = stream.read(&mut bfr).unwrap();
+        assert_eq!(&bfr[..n], b"$GPVTG");
+
+        // the clone shares the connection
+        let mut clone = stream.try_clone().unwrap();
+        clone.write_all(b"RTCM").unwrap();
+        let n = client.read(&mut bfr).unwrap();
+        assert_eq!(&bfr[..n], b"RTCM");
+
+        // nothing to read within the timeout
+        let e = stream.read(&mut bfr).err().unwrap();
+        assert!(matches!(
+            e.kind(),
+            ErrorKind::WouldBlock | ErrorKind::TimedOut
+        ));
+    }
+
+    #[test]
+    /// A zero timeout blocks instead of failing or returning at once
+    fn blocking() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let uri = format!("tcp://{}", listener.local_addr().unwrap());
+
+        let mut stream = open(&uri, 0, Duration::ZERO).unwrap();
+        let (mut client, _) = listener.accept().unwrap();
+
+        let writer = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            client.write_all(b"$GPVTG").unwrap();
+        });
+
+        let mut bfr = [0u8; 16];
+        let n = stream.read(&mut bfr).unwrap();
+        assert_eq!(&bfr[..n], b"$GPVTG");
+        writer.join().unwrap();
+    }
+
+    #[test]
+    fn udp() {
+        let port = UdpSocket::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let uri = format!("udp://127.0.0.1:{}", port);
+
+        let mut stream = open(&uri, 0, Duration::from_millis(100)).unwrap();
+        assert_eq!(
+            stream.write(b"early").err().unwrap().kind(),
+            ErrorKind::NotConnected
+        );
+
+        let peer = UdpSocket::bind("127.0.0.1:0").unwrap();
+        peer.send_to(b"$GPVTG", ("127.0.0.1", port)).unwrap();
+
+        let mut bfr = [0u8; 16];
+        let n = stream.read(&mut bfr).unwrap();
+        assert_eq!(&bfr[..n], b"$GPVTG");
+
+        // answers go to the last peer
+        stream.write_all(b"RTCM").unwrap();
+        let n = peer.recv(&mut bfr).unwrap();
+        assert_eq!(&bfr[..n], b"RTCM");
+    }
+
+    #[test]
+    fn file() {
+        let path = std::env::temp_dir().join("ix-io-file.nmea");
+        std::fs::write(&path, b"$GPVTG,,T,,M,0.00,N,0.00,K,N*2C\r\n").unwrap();
+
+        let uri = format!("file://{}", path.display());
+        assert!(is_capture(&uri));
+        assert!(!is_capture("tcp://127.0.0.1:4001"));
+        assert!(!is_capture("/dev/ttyUSB0"));
+
+        let mut stream = open(&uri, 0, Duration::from_millis(100)).unwrap();
+        let mut data = vec![];
+        stream.read_to_end(&mut data).unwrap();
+        assert_eq!(data, b"$GPVTG,,T,,M,0.00,N,0.00,K,N*2C\r\n");
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn pty() {
+        let link = std::env::temp_dir().join("ix-io-pty");
+        let uri = format!("pty://{}", link.display());
+        let mut stream = open(&uri, 0, Duration::from_millis(100)).unwrap();
+
+        // a simulator writing to the linked slave side
+        let mut slave = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&link)
+            .unwrap();
+        slave.write_all(b"$GPVTG").unwrap();
+
+        let mut bfr = [0u8; 16];
+        let n = stream.read(&mut bfr).unwrap();
+        assert_eq!(&bfr[..n], b"$GPVTG");
+
+        std::fs::remove_file(link).unwrap();
+    }
+
+    #[test]
+    fn invalid() {
+        let e = open("ftp://host", 4800, Duration::from_millis(10))
+            .err()
+            .unwrap();
+        assert_eq!(e.kind(), ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,188 @@
+use std::io::{ErrorKind, Read};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use actix::prelude::*;
+
+/// Size of a single read
+const READ_LEN: usize = 1024;
+
+/// Bytes read from the device, in the order they arrived
+#[derive(Clone, Debug, Message)]
+#[rtype(result = "()")]
+pub struct Received(pub Vec<u8>);
+
+/// Reading the device failed or reached end of file, the reader is gone
+#[derive(Debug, Message)]
+#[rtype(result = "()")]
+pub struct Closed(pub std::io::Error);
+
+impl Closed {
+    /// Whether the reader reached end of file, e.g. the end of a capture or
+    /// a connection closed by the peer
+    pub fn end_of_file(&self) -> bool {
+        self.0.kind() == ErrorKind::UnexpectedEof
+    }
+}
+
+/// Read the device on a dedicated thread, so that the actor is never blocked
+/// on I/O and bytes are handed over as soon as a read returns. Every read is
+/// passed to `forward`, which returns false once nobody is listening. The
+/// thread ends then or when `running` is cleared. On I/O errors and end of
+/// file it ends as well, after handing the error to `closed`
+pub fn spawn<R, F, C>(name: &str, mut io: R, running: Arc<AtomicBool>, mut forward: F, closed: C)
+where
+    R: Read + Send + 'static,
+    F: FnMut(Vec<u8>) -> bool + Send + 'static,
+    C: FnOnce(std::io::Error) + Send + 'static,
+{
+    let builder = thread::Builder::new().name(format!("{} reader", name));
+
+    let spawned = builder.spawn(move || {
+        let mut bfr = [0u8; READ_LEN];
+
+        let error = loop {
+            if !running.load(Ordering::Relaxed) {
+                return;
+            }
+
+            match io.read(&mut bfr) {
+                Ok(0) => break std::io::Error::new(ErrorKind::UnexpectedEof, "end of file"),
+                Ok(n) => {
+                    if !forward(bfr[..n].to_vec()) {
+                        return;
+                    }
+                }
+                // nothing received within the device timeout
+                Err(e) if timed_out(&e) => {}
+                Err(e) => break e,
+            }
+        };
+
+        closed(error);
+    });
+
+    if let Err(e) = spawned {
+        println!("ERROR: {}: failed to start reader: {}", name, e);
+    }
+}
+
+/// Whether a read returned without data, sockets report timeouts as
+/// WouldBlock
+pub fn timed_out(e: &std::io::Error) -> bool {
+    matches!(
+        e.kind(),
+        ErrorKind::TimedOut | ErrorKind::WouldBlock | ErrorKind::Interrupted
+    )
+}
+
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::sync::mpsc;
+
+    /// Reader returning the data in fixed size chunks
+    struct Chunked {
+        data: Cursor<Vec<u8>>,
+        chunk: usize,
+    }
+
+    impl Read for Chunked {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = buf.len().min(self.chunk);
+            self.data.read(&mut buf[..len])
+        }
+    }
+
+    #[test]
+    fn forward() {
+        let data: Vec<u8> = (0..=255).cycle().take(3000).collect();
+        let io = Chunked {
+            data: Cursor::new(data.clone()),
+            chunk: 700,
+        };
+
+        let (tx, rx) = mpsc::channel();
+        let (closed_tx, closed_rx) = mpsc::channel();
+        spawn(
+            "test",
+            io,
+            Arc::new(AtomicBool::new(true)),
+            move |bytes| tx.send(bytes).is_ok(),
+            move |e| closed_tx.send(Closed(e)).unwrap(),
+        );
+
+        // the thread drops the sender on end of file
+        let reads: Vec<Vec<u8>> = rx.iter().collect();
+        assert_eq!(
+            reads.iter().map(|r| r.len()).collect::<Vec<_>>(),
+            vec![700, 700, 700, 700, 200]
+        );
+        assert_eq!(reads.concat(), data);
+        assert!(closed_rx.recv().unwrap().end_of_file());
+    }
+
+    /// Reader timing out once, then failing like an unplugged device
+    struct Unplugged {
+        reads: usize,
+    }
+
+    impl Read for Unplugged {
+        fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+            self.reads += 1;
+            match self.reads {
+                1 => Err(ErrorKind::TimedOut.into()),
+                _ => Err(ErrorKind::BrokenPipe.into()),
+            }
+        }
+    }
+
+    #[test]
+    fn closed() {
+        let (tx, rx) = mpsc::channel();
+        spawn(
+            "test",
+            Unplugged { reads: 0 },
+            Arc::new(AtomicBool::new(true)),
+            |_| true,
+            move |e| tx.send(Closed(e)).unwrap(),
+        );
+
+        let closed = rx.recv().unwrap();
+        assert_eq!(closed.0.kind(), ErrorKind::BrokenPipe);
+        assert!(!closed.end_of_file());
+    }
+
+    #[test]
+    fn stop() {
+        let io = Cursor::new(vec![0u8; 4 * READ_LEN]);
+
+        // receiver gone after the first read
+        let (tx, rx) = mpsc::channel();
+        let (closed_tx, closed_rx) = mpsc::channel();
+        spawn(
+            "test",
+            io,
+            Arc::new(AtomicBool::new(true)),
+            move |bytes| {
+                let _ = tx.send(bytes);
+                false
+            },
+            move |e| closed_tx.send(e).unwrap(),
+        );
+        assert_eq!(rx.iter().count(), 1);
+        assert!(closed_rx.recv().is_err());
+
+        // not running
+        let (tx, rx) = mpsc::channel();
+        spawn(
+            "test",
+            Cursor::new(vec![0u8; 16]),
+            Arc::new(AtomicBool::new(false)),
+            move |bytes| tx.send(bytes).is_ok(),
+            |_| {},
+        );
+        assert_eq!(rx.iter().count(), 0);
+    }
+}
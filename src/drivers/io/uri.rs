@@ -0,0 +1,219 @@
+use std::fmt;
+use std::fmt::Formatter;
+use std::str::FromStr;
+
+/// Location of a byte stream, e.g. "uart:///dev/ttyUSB0:115200". A bare
+/// path is a serial port, at the baud rate configured in the driver
+#[derive(Clone, Debug, PartialEq)]
+pub enum Uri {
+    /// Serial port, e.g. "uart:///dev/ttyUSB0:115200"
+    Uart { path: String, baud: Option<u32> },
+    /// TCP client, e.g. "tcp://192.168.1.20:4001"
+    Tcp { address: String },
+    /// UDP socket bound to the given address, e.g. "udp://0.0.0.0:5000".
+    /// Writes go to the last peer data was received from
+    Udp { address: String },
+    /// Capture replayed from a file, e.g. "file://capture.nmea"
+    File { path: String },
+    /// Pseudo terminal, optionally linked from the given path so that a
+    /// simulator can open it, e.g. "pty:///tmp/ttyGPS"
+    Pty { link: Option<String> },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum UriError {
+    /// Unknown scheme
+    Scheme(String),
+    /// Missing path or invalid host:port
+    Address(String),
+    /// Invalid baud rate
+    Baud(String),
+}
+
+impl fmt::Display for UriError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            UriError::Scheme(s) => write!(f, "unsupported scheme {:?}", s),
+            UriError::Address(s) => write!(f, "invalid address {:?}", s),
+            UriError::Baud(s) => write!(f, "invalid baud rate {:?}", s),
+        }
+    }
+}
+
+impl fmt::Display for Uri {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Uri::Uart {
+                path,
+                baud: Some(baud),
+            } => write!(f, "uart://{}:{}", path, baud),
+            Uri::Uart { path, baud: None } => write!(f, "uart://{}", path),
+            Uri::Tcp { address } => write!(f, "tcp://{}", address),
+            Uri::Udp { address } => write!(f, "udp://{}", address),
+            Uri::File { path } => write!(f, "file://{}", path),
+            Uri::Pty { link } => write!(f, "pty://{}", link.as_deref().unwrap_or("")),
+        }
+    }
+}
+
+impl FromStr for Uri {
+    type Err = UriError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (scheme, rest) = match s.split_once("://") {
+            Some(parts) => parts,
+            None => {
+                return non_empty(s).map(|path| Uri::Uart {
+                    path: String::from(path),
+                    baud: None,
+                })
+            }
+        };
+
+        match scheme {
+            "uart" => {
+                let (path, baud) = match rest.rsplit_once(':') {
+                    Some((path, baud)) => {
+                        let baud = baud.parse().map_err(|_| UriError::Baud(baud.to_string()))?;
+                        (path, Some(baud))
+                    }
+                    None => (rest, None),
+                };
+
+                Ok(Uri::Uart {
+                    path: String::from(non_empty(path)?),
+                    baud,
+                })
+            }
+            "tcp" => host_port(rest).map(|address| Uri::Tcp { address }),
+            "udp" => host_port(rest).map(|address| Uri::Udp { address }),
+            "file" => non_empty(rest).map(|path| Uri::File {
+                path: String::from(path),
+            }),
+            "pty" => Ok(Uri::Pty {
+                link: non_empty(rest).ok().map(String::from),
+            }),
+            _ => Err(UriError::Scheme(scheme.to_string())),
+        }
+    }
+}
+
+fn non_empty(path: &str) -> Result<&str, UriError> {
+    if path.is_empty() {
+        Err(UriError::Address(path.to_string()))
+    } else {
+        Ok(path)
+    }
+}
+
+/// Validate a "host:port" address
+fn host_port(address: &str) -> Result<String, UriError> {
+    match address.rsplit_once(':') {
+        Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => {
+            Ok(String::from(address))
+        }
+        _ => Err(UriError::Address(address.to_string())),
+    }
+}
+
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            "uart:///dev/ttyUSB0:115200".parse(),
+            Ok(Uri::Uart {
+                path: String::from("/dev/ttyUSB0"),
+                baud: Some(115200)
+            })
+        );
+        assert_eq!(
+            "uart:///dev/ttyUSB0".parse(),
+            Ok(Uri::Uart {
+                path: String::from("/dev/ttyUSB0"),
+                baud: None
+            })
+        );
+        assert_eq!(
+            "/dev/ttyACM0".parse(),
+            Ok(Uri::Uart {
+                path: String::from("/dev/ttyACM0"),
+                baud: None
+            })
+        );
+        assert_eq!(
+            "tcp://192.168.1.20:4001".parse(),
+            Ok(Uri::Tcp {
+                address: String::from("192.168.1.20:4001")
+            })
+        );
+        assert_eq!(
+            "tcp://[::1]:4001".parse(),
+            Ok(Uri::Tcp {
+                address: String::from("[::1]:4001")
+            })
+        );
+        assert_eq!(
+            "udp://0.0.0.0:5000".parse(),
+            Ok(Uri::Udp {
+                address: String::from("0.0.0.0:5000")
+            })
+        );
+        assert_eq!(
+            "file://capture.nmea".parse(),
+            Ok(Uri::File {
+                path: String::from("capture.nmea")
+            })
+        );
+        assert_eq!("pty://".parse(), Ok(Uri::Pty { link: None }));
+        assert_eq!(
+            "pty:///tmp/ttyGPS".parse(),
+            Ok(Uri::Pty {
+                link: Some(String::from("/tmp/ttyGPS"))
+            })
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(
+            "ftp://host".parse::<Uri>(),
+            Err(UriError::Scheme(String::from("ftp")))
+        );
+        assert_eq!(
+            "uart:///dev/ttyUSB0:fast".parse::<Uri>(),
+            Err(UriError::Baud(String::from("fast")))
+        );
+        assert_eq!(
+            "uart://".parse::<Uri>(),
+            Err(UriError::Address(String::new()))
+        );
+        assert_eq!(
+            "tcp://host".parse::<Uri>(),
+            Err(UriError::Address(String::from("host")))
+        );
+        assert_eq!(
+            "udp://:5000".parse::<Uri>(),
+            Err(UriError::Address(String::from(":5000")))
+        );
+        assert_eq!(
+            "tcp://host:70000".parse::<Uri>(),
+            Err(UriError::Address(String::from("host:70000")))
+        );
+        assert_eq!("".parse::<Uri>(), Err(UriError::Address(String::new())));
+    }
+
+    #[test]
+    fn display() {
+        for uri in [
+            "uart:///dev/ttyUSB0:115200",
+            "tcp://localhost:4001",
+            "udp://0.0.0.0:5000",
+            "file://capture.nmea",
+            "pty:///tmp/ttyGPS",
+        ] {
+            assert_eq!(uri.parse::<Uri>().unwrap().to_string(), uri);
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use imc::GpsFix;
+
+use ix_nmea::Builder;
+
+/// Meters per second to knots
+const MPS_TO_KNOTS: f64 = 3600.0 / 1852.0;
+/// Meters per second to kilometers per hour
+const MPS_TO_KPH: f64 = 3.6;
+
+fn valid(fix: &GpsFix, bit: imc::messages::GpsFix::ValidityBits) -> bool {
+    fix._validity & (bit as u16) != 0
+}
+
+fn position(fix: &GpsFix) -> (Option<f64>, Option<f64>) {
+    if valid(fix, imc::messages::GpsFix::ValidityBits::GFV_VALID_POS) {
+        (Some(fix._lat.to_degrees()), Some(fix._lon.to_degrees()))
+    } else {
+        (None, None)
+    }
+}
+
+fn time(fix: &GpsFix) -> Option<f64> {
+    if valid(fix, imc::messages::GpsFix::ValidityBits::GFV_VALID_TIME) {
+        Some(fix._utc_time as f64)
+    } else {
+        None
+    }
+}
+
+/// Speed over ground in meters per second
+fn sog(fix: &GpsFix) -> Option<f64> {
+    if valid(fix, imc::messages::GpsFix::ValidityBits::GFV_VALID_SOG) {
+        Some(fix._sog as f64)
+    } else {
+        None
+    }
+}
+
+/// Course over ground in degrees, from 0 to 360
+fn cog(fix: &GpsFix) -> Option<f64> {
+    if valid(fix, imc::messages::GpsFix::ValidityBits::GFV_VALID_COG) {
+        Some(heading_degrees(fix._cog as f64))
+    } else {
+        None
+    }
+}
+
+fn heading_degrees(radians: f64) -> f64 {
+    radians.to_degrees().rem_euclid(360.0)
+}
+
+/// GGA fix quality
+fn quality(fix: &GpsFix) -> u8 {
+    if !valid(fix, imc::messages::GpsFix::ValidityBits::GFV_VALID_POS) {
+        return 0;
+    }
+
+    match fix._type {
+        t if t == imc::messages::GpsFix::TypeEnum::GFT_DIFFERENTIAL as u8 => 2,
+        t if t == imc::messages::GpsFix::TypeEnum::GFT_DEAD_RECKONING as u8 => 6,
+        _ => 1,
+    }
+}
+
+/// NMEA 2.3 mode indicator of RMC and VTG
+fn mode(fix: &GpsFix) -> char {
+    match quality(fix) {
+        0 => 'N',
+        2 => 'D',
+        6 => 'E',
+        _ => 'A',
+    }
+}
+
+/// Global Positioning System Fix Data. The altitude above mean sea level is
+/// the ellipsoidal height of GpsFix minus the geoid separation. Without a
+/// separation, the height is reported as altitude and the field left empty
+pub fn gga(fix: &GpsFix, separation: Option<f32>, talker: &str) -> String {
+    let (lat, lon) = position(fix);
+    let hdop = if valid(fix, imc::messages::GpsFix::ValidityBits::GFV_VALID_HDOP) {
+        Some(fix._hdop as f64)
+    } else {
+        None
+    };
+
+    Builder::new(&format!("{}GGA", talker))
+        .time(time(fix))
+        .latitude(lat)
+        .longitude(lon)
+        .field(quality(fix))
+        .field(format_args!("{:02}", fix._satellites))
+        .optional_fixed(hdop, 1)
+        .optional_fixed(
+            lat.map(|_| (fix._height - separation.unwrap_or_default()) as f64),
+            2,
+        )
+        .field('M')
+        .optional_fixed(lat.and(separation).map(|s| s as f64), 2)
+        .field('M')
+        .empty()
+        .empty()
+        .build()
+}
+
+/// Recommended Minimum Specific GNSS Data
+pub fn rmc(fix: &GpsFix, talker: &str) -> String {
+    let (lat, lon) = position(fix);
+    let date = if valid(fix, imc::messages::GpsFix::ValidityBits::GFV_VALID_DATE) {
+        Some((fix._utc_day, fix._utc_month, fix._utc_year))
+    } else {
+        None
+    };
+
+    Builder::new(&format!("{}RMC", talker))
+        .time(time(fix))
+        .field(if lat.is_some() { 'A' } else { 'V' })
+        .latitude(lat)
+        .longitude(lon)
+        .optional_fixed(sog(fix).map(|v| v * MPS_TO_KNOTS), 2)
+        .optional_fixed(cog(fix), 2)
+        .date(date)
+        .empty()
+        .empty()
+        .field(mode(fix))
+        .build()
+}
+
+/// Course over Ground and Ground Speed. Magnetic course is left empty
+pub fn vtg(fix: &GpsFix, talker: &str) -> String {
+    let sog = sog(fix);
+
+    Builder::new(&format!("{}VTG", talker))
+        .optional_fixed(cog(fix), 2)
+        .field('T')
+        .empty()
+        .field('M')
+        .optional_fixed(sog.map(|v| v * MPS_TO_KNOTS), 2)
+        .field('N')
+        .optional_fixed(sog.map(|v| v * MPS_TO_KPH), 2)
+        .field('K')
+        .field(mode(fix))
+        .build()
+}
+
+/// True heading, given in radians
+pub fn hdt(heading: f64, talker: &str) -> String {
+    Builder::new(&format!("{}HDT", talker))
+        .fixed(heading_degrees(heading), 2)
+        .field('T')
+        .build()
+}
+
+mod tests {
+    use super::*;
+
+    fn fix() -> GpsFix {
+        let mut fix = GpsFix::new();
+        fix._validity = imc::messages::GpsFix::ValidityBits::GFV_VALID_POS as u16
+            | imc::messages::GpsFix::ValidityBits::GFV_VALID_TIME as u16
+            | imc::messages::GpsFix::ValidityBits::GFV_VALID_DATE as u16
+            | imc::messages::GpsFix::ValidityBits::GFV_VALID_HDOP as u16
+            | imc::messages::GpsFix::ValidityBits::GFV_VALID_SOG as u16
+            | imc::messages::GpsFix::ValidityBits::GFV_VALID_COG as u16;
+        fix._type = imc::messages::GpsFix::TypeEnum::GFT_DIFFERENTIAL as u8;
+        fix._utc_time = 45296.5;
+        fix._utc_day = 3;
+        fix._utc_month = 9;
+        fix._utc_year = 2023;
+        fix._lat = 41.185_f64.to_radians();
+        fix._lon = (-8.705_f64).to_radians();
+        fix._height = 52.3;
+        fix._satellites = 9;
+        fix._hdop = 0.9;
+        fix._sog = 1852.0 / 3600.0 * 5.5;
+        fix._cog = (-90.0_f32).to_radians();
+        fix
+    }
+
+    #[test]
+    fn gga_sentence() {
+        assert_eq!(
+            gga(&fix(), Some(50.1), "GP"),
+            "$GPGGA,123456.50,4111.10000,N,00842.30000,W,2,09,0.9,2.20,M,50.10,M,,*72\r\n"
+        );
+
+        // separation unknown
+        assert_eq!(
+            gga(&fix(), None, "GP"),
+            "$GPGGA,123456.50,4111.10000,N,00842.30000,W,2,09,0.9,52.30,M,,M,,*6C\r\n"
+        );
+
+        let mut fix = fix();
+        fix._validity = 0;
+        assert_eq!(
+            gga(&fix, Some(50.1), "GN"),
+            "$GNGGA,,,,,,0,09,,,M,,M,,*71\r\n"
+        );
+    }
+
+    #[test]
+    fn rmc_sentence() {
+        assert_eq!(
+            rmc(&fix(), "GP"),
+            "$GPRMC,123456.50,A,4111.10000,N,00842.30000,W,5.50,270.00,030923,,,D*4C\r\n"
+        );
+
+        let mut fix = fix();
+        fix._validity = imc::messages::GpsFix::ValidityBits::GFV_VALID_TIME as u16;
+        assert_eq!(rmc(&fix, "GP"), "$GPRMC,123456.50,V,,,,,,,,,,N*7F\r\n");
+    }
+
+    #[test]
+    fn vtg_sentence() {
+        assert_eq!(
+            vtg(&fix(), "GP"),
+            "$GPVTG,270.00,T,,M,5.50,N,10.19,K,D*04\r\n"
+        );
+    }
+
+    #[test]
+    fn hdt_sentence() {
+        assert_eq!(hdt(1.0_f64.to_radians(), "HE"), "$HEHDT,1.00,T*1E\r\n");
+        assert_eq!(hdt(-(1.0_f64.to_radians()), "HE"), "$HEHDT,359.00,T*10\r\n");
+    }
+}
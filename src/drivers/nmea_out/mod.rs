@@ -0,0 +1,342 @@
+use std::io::{ErrorKind, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+use actix::prelude::*;
+use actix_broker::{BrokerIssue, BrokerSubscribe, SystemBroker};
+
+use crate::drivers::gps::quality::SolutionStatus;
+use crate::drivers::io;
+use crate::drivers::io::{Backoff, Stream};
+use crate::ix::Parameter;
+use crate::task;
+use crate::task::entity_state;
+use crate::BrokerType;
+use crate::MessageWrapper;
+use crate::TaskBehaviour;
+
+pub mod encode;
+
+/// Where sentences are written to
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Output {
+    /// TCP server, every connected client gets all sentences
+    #[default]
+    Tcp,
+    /// Serial port or any other device URI
+    Serial,
+}
+
+/// Sentences that can be produced
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SentenceType {
+    GGA,
+    RMC,
+    VTG,
+    HDT,
+}
+
+#[derive(Default)]
+pub struct Configuration {
+    pub enabled: Parameter<bool>,
+    pub output: Parameter<Output>,
+    pub tcp_port: Parameter<u16>,
+    pub io_dev: Parameter<String>,
+    pub baud: Parameter<u32>,
+    pub reconnect_delay: Parameter<u64>,
+    pub reconnect_max_delay: Parameter<u64>,
+    pub talker: Parameter<String>,
+    pub sentences: Parameter<Vec<SentenceType>>,
+}
+
+pub struct Task {
+    pub ctx: task::Context,
+    listener: Option<TcpListener>,
+    clients: Vec<TcpStream>,
+    serial: Option<Box<dyn Stream>>,
+    /// Delay between attempts to reopen the serial device
+    backoff: Backoff,
+    /// Geoid separation of the last fix, for the altitude of GGA
+    separation: Option<f32>,
+    cfg: Configuration,
+}
+
+impl TaskBehaviour for Task {
+    fn get_ctx(&self) -> &task::Context {
+        &self.ctx
+    }
+
+    fn get_name(&self) -> &str {
+        "NMEA Output"
+    }
+
+    fn register_configuration(&mut self) {
+        self.cfg
+            .enabled
+            .name("Enabled")
+            .default(false)
+            .description("Produce NMEA sentences from navigation data");
+
+        self.cfg
+            .output
+            .name("Output")
+            .default(Output::Tcp)
+            .description("Serve sentences over a TCP server or a serial port");
+
+        self.cfg
+            .tcp_port
+            .name("TCP - Port")
+            .default(10110)
+            .description("Port the TCP server listens on");
+
+        self.cfg
+            .io_dev
+            .name("IO Device")
+            .default(String::from("/dev/ttyUSB0"))
+            .description("Device path or URI sentences are written to");
+
+        self.cfg
+            .baud
+            .name("IO - Baud Rate")
+            .default(4800)
+            .description("Baud rate applied to serial devices whose URI has none");
+
+        self.cfg
+            .reconnect_delay
+            .name("IO - Reconnect Delay")
+            .default(500)
+            .description("In milliseconds, delay before reopening the device. Doubled on every failed attempt");
+
+        self.cfg
+            .reconnect_max_delay
+            .name("IO - Maximum Reconnect Delay")
+            .default(30000)
+            .description("In milliseconds, upper bound of the reconnect delay");
+
+        self.cfg
+            .talker
+            .name("Talker")
+            .default(String::from("GP"))
+            .description("Talker identifier of the produced sentences");
+
+        self.cfg
+            .sentences
+            .name("Sentences")
+            .default(vec![
+                SentenceType::GGA,
+                SentenceType::RMC,
+                SentenceType::VTG,
+                SentenceType::HDT,
+            ])
+            .description("Sentences produced from navigation data");
+    }
+}
+
+impl Task {
+    pub fn new(context: task::Context) -> Task {
+        Task {
+            ctx: context,
+            listener: None,
+            clients: vec![],
+            serial: None,
+            backoff: Backoff::new(Duration::from_millis(500), Duration::from_secs(30)),
+            separation: None,
+            cfg: Default::default(),
+        }
+    }
+
+    fn enabled(&self, sentence: SentenceType) -> bool {
+        self.cfg.sentences.get().contains(&sentence)
+    }
+
+    /// Report the state of the output
+    fn dispatch_state(&self, state: u8, description: &str) {
+        send_message!(self, imc::EntityState, entity_state(state, description));
+    }
+
+    /// Start the TCP server. A port already in use is reported, not retried
+    fn listen(&mut self) {
+        let port = *self.cfg.tcp_port.get();
+        let listener = TcpListener::bind(("0.0.0.0", port)).and_then(|listener| {
+            listener.set_nonblocking(true)?;
+            Ok(listener)
+        });
+
+        match listener {
+            Ok(listener) => {
+                println!("serving NMEA on port {}", port);
+                self.listener = Some(listener);
+                self.dispatch_state(
+                    imc::messages::EntityState::StateEnum::ESTA_NORMAL as u8,
+                    "active",
+                );
+            }
+            Err(e) => {
+                println!("ERROR: TCP port {}: {}", port, e);
+                self.dispatch_state(
+                    imc::messages::EntityState::StateEnum::ESTA_FAULT as u8,
+                    &format!("failed to listen on port {}: {}", port, e),
+                );
+            }
+        }
+    }
+
+    /// Open the serial device. Failures are retried with backoff
+    fn connect(&mut self, ctx: &mut Context<Self>) {
+        let device = self.cfg.io_dev.get().clone();
+        println!("opening {} with {}", device, *self.cfg.baud.get());
+
+        match io::open(&device, *self.cfg.baud.get(), Duration::ZERO) {
+            Ok(serial) => {
+                self.serial = Some(serial);
+                self.backoff.reset();
+                self.dispatch_state(
+                    imc::messages::EntityState::StateEnum::ESTA_NORMAL as u8,
+                    "active",
+                );
+            }
+            Err(e) => {
+                println!("ERROR: {}: {}", device, e);
+                self.reconnect(ctx, &format!("failed to open device: {}", e));
+            }
+        }
+    }
+
+    /// Close the serial device and schedule reopening it
+    fn reconnect(&mut self, ctx: &mut Context<Self>, reason: &str) {
+        self.serial = None;
+        self.dispatch_state(
+            imc::messages::EntityState::StateEnum::ESTA_FAULT as u8,
+            reason,
+        );
+
+        if !self.is_running() {
+            return;
+        }
+
+        let delay = self.backoff.next();
+        println!("{}: reopening in {:?}", self.cfg.io_dev.get(), delay);
+        ctx.run_later(delay, |task, ctx| task.connect(ctx));
+    }
+
+    /// Write a sentence to the serial port or to every TCP client
+    fn write(&mut self, ctx: &mut Context<Self>, sentence: &str) {
+        if let Some(serial) = self.serial.as_mut() {
+            if let Err(e) = serial.write_all(sentence.as_bytes()) {
+                println!("ERROR: {}: {}", self.cfg.io_dev.get(), e);
+                self.reconnect(ctx, &format!("write failed: {}", e));
+            }
+        }
+
+        // drop clients that went away
+        self.clients
+            .retain_mut(|client| match client.write_all(sentence.as_bytes()) {
+                Ok(()) => true,
+                Err(e) => {
+                    println!("client {:?} disconnected: {}", client.peer_addr().ok(), e);
+                    false
+                }
+            });
+    }
+
+    /// Accept pending TCP clients
+    fn accept(&mut self) {
+        let listener = match self.listener.as_ref() {
+            Some(listener) => listener,
+            None => return,
+        };
+
+        loop {
+            match listener.accept() {
+                Ok((client, addr)) => {
+                    println!("client {} connected", addr);
+                    // a stalled client must not block the task
+                    if let Err(e) = client.set_write_timeout(Some(Duration::from_millis(100))) {
+                        println!("ERROR: {}: {}", addr, e);
+                    }
+                    self.clients.push(client);
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    println!("ERROR: accept: {}", e);
+                    break;
+                }
+            }
+        }
+    }
+
+    fn on_main(&mut self, _context: &mut Context<Self>) {
+        self.accept();
+    }
+}
+
+impl Actor for Task {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        if !*self.cfg.enabled.get() {
+            println!("{}: disabled", self.get_name());
+            return;
+        }
+
+        self.backoff = Backoff::new(
+            Duration::from_millis(*self.cfg.reconnect_delay.get()),
+            Duration::from_millis(*self.cfg.reconnect_max_delay.get()),
+        );
+
+        subscribe_to!(SolutionStatus, self, ctx);
+        subscribe_to!(imc::GpsFix, self, ctx);
+        subscribe_to!(imc::EulerAngles, self, ctx);
+
+        match *self.cfg.output.get() {
+            Output::Tcp => self.listen(),
+            Output::Serial => self.connect(ctx),
+        }
+
+        start_main_loop!(100, ctx);
+    }
+
+    fn stopped(&mut self, _ctx: &mut Self::Context) {
+        println!("{} stopped", self.get_name());
+    }
+}
+
+impl Handler<MessageWrapper<SolutionStatus>> for Task {
+    type Result = ();
+
+    fn handle(&mut self, msg: MessageWrapper<SolutionStatus>, _ctx: &mut Self::Context) {
+        self.separation = msg.0.separation;
+    }
+}
+
+impl Handler<MessageWrapper<imc::GpsFix>> for Task {
+    type Result = ();
+
+    fn handle(&mut self, msg: MessageWrapper<imc::GpsFix>, ctx: &mut Self::Context) {
+        let fix = &msg.0;
+        let talker = self.cfg.talker.get().clone();
+
+        if self.enabled(SentenceType::GGA) {
+            self.write(ctx, &encode::gga(fix, self.separation, &talker));
+        }
+
+        if self.enabled(SentenceType::RMC) {
+            self.write(ctx, &encode::rmc(fix, &talker));
+        }
+
+        if self.enabled(SentenceType::VTG) {
+            self.write(ctx, &encode::vtg(fix, &talker));
+        }
+    }
+}
+
+impl Handler<MessageWrapper<imc::EulerAngles>> for Task {
+    type Result = ();
+
+    fn handle(&mut self, msg: MessageWrapper<imc::EulerAngles>, ctx: &mut Self::Context) {
+        if self.enabled(SentenceType::HDT) {
+            let talker = self.cfg.talker.get().clone();
+            self.write(ctx, &encode::hdt(msg.0._psi, &talker));
+        }
+    }
+}
@@ -0,0 +1,458 @@
+use std::fmt;
+use std::fmt::Formatter;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+/// Upper bound of the response header, sourcetables excepted
+const MAX_HEADER_LEN: usize = 4096;
+
+const USER_AGENT: &str = "NTRIP ix/0.1";
+
+/// NTRIP protocol revision spoken to the caster
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Version {
+    /// NTRIP 1.0, ICY responses
+    #[default]
+    V1,
+    /// NTRIP 2.0, HTTP/1.1 with chunked transfer encoding
+    V2,
+}
+
+/// Caster and stream to connect to
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Settings {
+    pub host: String,
+    pub port: u16,
+    pub mountpoint: String,
+    /// Empty when the caster needs no credentials
+    pub username: String,
+    pub password: String,
+    pub version: Version,
+}
+
+/// Reasons a caster refuses the stream
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    /// Bad or missing credentials
+    Unauthorized,
+    /// Mountpoint unknown to the caster, which answered its sourcetable
+    SourceTable,
+    /// Any other response, by its status line
+    Status(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Unauthorized => f.write_str("unauthorized"),
+            Error::SourceTable => f.write_str("unknown mountpoint"),
+            Error::Status(line) => write!(f, "unexpected response \"{}\"", line),
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+/// Accepted stream
+#[derive(Debug, PartialEq)]
+pub struct Handshake {
+    /// Data uses chunked transfer encoding
+    pub chunked: bool,
+    /// Data received along with the response header
+    pub data: Vec<u8>,
+}
+
+/// Request for the stream of the mountpoint
+pub fn request(settings: &Settings) -> String {
+    let mountpoint = settings.mountpoint.trim_start_matches('/');
+    let mut request = match settings.version {
+        Version::V1 => format!(
+            "GET /{} HTTP/1.0\r\nUser-Agent: {}\r\nAccept: */*\r\n",
+            mountpoint, USER_AGENT
+        ),
+        Version::V2 => format!(
+            "GET /{} HTTP/1.1\r\nHost: {}:{}\r\nNtrip-Version: Ntrip/2.0\r\nUser-Agent: {}\r\nConnection: close\r\n",
+            mountpoint, settings.host, settings.port, USER_AGENT
+        ),
+    };
+
+    if !settings.username.is_empty() {
+        let credentials = format!("{}:{}", settings.username, settings.password);
+        request.push_str(&format!(
+            "Authorization: Basic {}\r\n",
+            base64(credentials.as_bytes())
+        ));
+    }
+
+    request.push_str("\r\n");
+    request
+}
+
+/// Connect to the caster and request the stream. The timeout applies to
+/// connecting and to every read of the response
+pub fn connect(settings: &Settings, timeout: Duration) -> Result<(TcpStream, Handshake), Error> {
+    let address = (settings.host.as_str(), settings.port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| std::io::Error::new(ErrorKind::NotFound, "no address for host"))?;
+
+    let mut stream = TcpStream::connect_timeout(&address, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.write_all(request(settings).as_bytes())?;
+
+    let handshake = handshake(&mut stream)?;
+    Ok((stream, handshake))
+}
+
+/// Read and evaluate the caster response
+pub fn handshake<R: Read>(io: &mut R) -> Result<Handshake, Error> {
+    let mut bfr = vec![];
+    let mut chunk = [0u8; 512];
+
+    let header_len = loop {
+        if let Some(len) = header_len(&bfr) {
+            break len;
+        }
+
+        if bfr.len() > MAX_HEADER_LEN {
+            return Err(Error::Status(status_line(&bfr)));
+        }
+
+        match io.read(&mut chunk)? {
+            0 => return Err(Error::Status(status_line(&bfr))),
+            n => bfr.extend_from_slice(&chunk[..n]),
+        }
+    };
+
+    let header = String::from_utf8_lossy(&bfr[..header_len]).to_string();
+    let data = bfr[header_len..].to_vec();
+
+    let mut lines = header.lines();
+    let status = lines.next().unwrap_or_default().trim().to_string();
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+        .collect();
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.to_ascii_lowercase())
+    };
+
+    if status.starts_with("SOURCETABLE")
+        || header("content-type").is_some_and(|t| t == "gnss/sourcetable")
+    {
+        return Err(Error::SourceTable);
+    }
+
+    let mut words = status.split_whitespace();
+    match (words.next(), words.next()) {
+        (Some("ICY"), Some("200")) => Ok(Handshake {
+            chunked: false,
+            data,
+        }),
+        (Some(http), Some("200")) if http.starts_with("HTTP/") => Ok(Handshake {
+            chunked: header("transfer-encoding").is_some_and(|t| t.contains("chunked")),
+            data,
+        }),
+        (Some(http), Some("401")) if http.starts_with("HTTP/") => Err(Error::Unauthorized),
+        _ if status.contains("Bad Password") => Err(Error::Unauthorized),
+        _ => Err(Error::Status(status)),
+    }
+}
+
+/// Length of the response header, when complete. NTRIP 1 casters may follow
+/// "ICY 200 OK" with data right away, without an empty line, or wait for a
+/// position first. Stray line ends are dropped along with other garbage
+/// when deframing
+fn header_len(bfr: &[u8]) -> Option<usize> {
+    let line_end = bfr.windows(2).position(|w| w == b"\r\n")? + 2;
+
+    if bfr.starts_with(b"ICY ") {
+        let blank = bfr[line_end..].starts_with(b"\r\n");
+        return Some(if blank { line_end + 2 } else { line_end });
+    }
+
+    bfr.windows(4).position(|w| w == b"\r\n\r\n").map(|n| n + 4)
+}
+
+fn status_line(bfr: &[u8]) -> String {
+    let line = bfr.split(|b| *b == b'\n').next().unwrap_or_default();
+    String::from_utf8_lossy(line).trim().to_string()
+}
+
+/// Decoder of the chunked transfer encoding of NTRIP 2 streams
+#[derive(Default)]
+pub struct Dechunker {
+    bfr: Vec<u8>,
+}
+
+impl Dechunker {
+    pub fn new() -> Dechunker {
+        Default::default()
+    }
+
+    /// Feed the next bytes, returning the data of the chunks they complete.
+    /// The last chunk of the stream is reported as end of file
+    pub fn push(&mut self, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        self.bfr.extend_from_slice(data);
+        let mut out = vec![];
+
+        loop {
+            let line_end = match self.bfr.windows(2).position(|w| w == b"\r\n") {
+                Some(n) => n,
+                None if self.bfr.len() > 64 => return Err(invalid("chunk size too long")),
+                None => break,
+            };
+
+            // extensions are ignored
+            let line = String::from_utf8_lossy(&self.bfr[..line_end]).to_string();
+            let size = line.split(';').next().unwrap_or_default().trim();
+            let size = usize::from_str_radix(size, 16)
+                .map_err(|_| invalid(&format!("invalid chunk size \"{}\"", size)))?;
+
+            if size == 0 {
+                // hand over the data decoded so far first
+                if !out.is_empty() {
+                    break;
+                }
+                self.bfr.clear();
+                return Err(std::io::Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "end of stream",
+                ));
+            }
+
+            let start = line_end + 2;
+            if self.bfr.len() < start + size + 2 {
+                break;
+            }
+
+            if &self.bfr[start + size..start + size + 2] != b"\r\n" {
+                return Err(invalid("chunk not terminated"));
+            }
+
+            out.extend_from_slice(&self.bfr[start..start + size]);
+            self.bfr.drain(..start + size + 2);
+        }
+
+        Ok(out)
+    }
+}
+
+fn invalid(reason: &str) -> std::io::Error {
+    std::io::Error::new(ErrorKind::InvalidData, reason)
+}
+
+/// Standard base64, padded, for basic authentication
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::new();
+    for group in data.chunks(3) {
+        let b = [
+            group[0],
+            group.get(1).copied().unwrap_or(0),
+            group.get(2).copied().unwrap_or(0),
+        ];
+        let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | b[2] as u32;
+
+        for i in 0..4 {
+            if i <= group.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+
+    out
+}
+
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Cursor};
+    use std::net::TcpListener;
+    use std::thread;
+
+    fn settings(port: u16, version: Version) -> Settings {
+        Settings {
+            host: String::from("127.0.0.1"),
+            port,
+            mountpoint: String::from("RTCM3"),
+            username: String::from("user"),
+            password: String::from("secret"),
+            version,
+        }
+    }
+
+    /// Caster accepting a single client. Answers with the given response
+    /// and hands over the request and the lines sent afterwards
+    fn caster(response: &'static [u8]) -> (u16, thread::JoinHandle<(String, Vec<String>)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut writer = stream.try_clone().unwrap();
+            let mut reader = BufReader::new(stream);
+
+            let mut request = String::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                request.push_str(&line);
+                if line == "\r\n" {
+                    break;
+                }
+            }
+
+            writer.write_all(response).unwrap();
+
+            // upstream positions, until the client goes away
+            let lines = reader.lines().map_while(Result::ok).collect();
+            (request, lines)
+        });
+
+        (port, handle)
+    }
+
+    #[test]
+    fn encode_credentials() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"user:secret"), "dXNlcjpzZWNyZXQ=");
+    }
+
+    #[test]
+    fn requests() {
+        assert_eq!(
+            request(&settings(2101, Version::V1)),
+            "GET /RTCM3 HTTP/1.0\r\nUser-Agent: NTRIP ix/0.1\r\nAccept: */*\r\n\
+             Authorization: Basic dXNlcjpzZWNyZXQ=\r\n\r\n"
+        );
+
+        let anonymous = Settings {
+            username: String::new(),
+            ..settings(2101, Version::V2)
+        };
+        assert_eq!(
+            request(&anonymous),
+            "GET /RTCM3 HTTP/1.1\r\nHost: 127.0.0.1:2101\r\nNtrip-Version: Ntrip/2.0\r\n\
+             User-Agent: NTRIP ix/0.1\r\nConnection: close\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn responses() {
+        let accepted = |response: &[u8]| handshake(&mut Cursor::new(response.to_vec())).unwrap();
+
+        assert_eq!(
+            accepted(b"ICY 200 OK\r\n\r\n\xD3\x00"),
+            Handshake {
+                chunked: false,
+                data: vec![0xD3, 0x00]
+            }
+        );
+        // without empty line
+        assert_eq!(accepted(b"ICY 200 OK\r\n\xD3\x00").data, vec![0xD3, 0x00]);
+        assert_eq!(
+            accepted(b"HTTP/1.1 200 OK\r\nNtrip-Version: Ntrip/2.0\r\nTransfer-Encoding: chunked\r\n\r\n2\r\n"),
+            Handshake {
+                chunked: true,
+                data: b"2\r\n".to_vec()
+            }
+        );
+
+        let refused = |response: &[u8]| handshake(&mut Cursor::new(response.to_vec())).unwrap_err();
+
+        assert!(matches!(
+            refused(b"SOURCETABLE 200 OK\r\nContent-Type: text/plain\r\n\r\nSTR;"),
+            Error::SourceTable
+        ));
+        assert!(matches!(
+            refused(b"HTTP/1.1 200 OK\r\nContent-Type: gnss/sourcetable\r\n\r\n"),
+            Error::SourceTable
+        ));
+        assert!(matches!(
+            refused(b"HTTP/1.1 401 Unauthorized\r\n\r\n"),
+            Error::Unauthorized
+        ));
+        assert!(matches!(
+            refused(b"ERROR - Bad Password\r\n\r\n"),
+            Error::Unauthorized
+        ));
+        match refused(b"HTTP/1.1 404 Not Found\r\n\r\n") {
+            Error::Status(line) => assert_eq!(line, "HTTP/1.1 404 Not Found"),
+            e => panic!("unexpected {:?}", e),
+        }
+        // connection closed before the end of the header
+        assert!(matches!(refused(b"HTTP/1.1 200"), Error::Status(_)));
+    }
+
+    #[test]
+    fn dechunk() {
+        let mut dechunker = Dechunker::new();
+        let stream = b"3\r\nabc\r\n5;ext=1\r\nde\r\nf\r\n";
+
+        // whatever the way it is split
+        let mut out = vec![];
+        for chunk in stream.chunks(4) {
+            out.extend(dechunker.push(chunk).unwrap());
+        }
+        assert_eq!(out, b"abcde\r\nf");
+
+        let end = dechunker.push(b"0\r\n\r\n").unwrap_err();
+        assert_eq!(end.kind(), ErrorKind::UnexpectedEof);
+
+        let invalid = Dechunker::new().push(b"zz\r\n").unwrap_err();
+        assert_eq!(invalid.kind(), ErrorKind::InvalidData);
+        let unterminated = Dechunker::new().push(b"1\r\nabc\r\n").unwrap_err();
+        assert_eq!(unterminated.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn mock_caster() {
+        let (port, caster) = caster(b"ICY 200 OK\r\n\r\n\xD3\x00\x00");
+
+        let (mut stream, handshake) =
+            connect(&settings(port, Version::V1), Duration::from_secs(5)).unwrap();
+        assert_eq!(handshake.data, vec![0xD3, 0x00, 0x00]);
+
+        stream
+            .write_all(b"$GPGGA,120000.00,4110.0000,N,00840.0000,W,1,08,0.9,10.00,M,,M,,*7B\r\n")
+            .unwrap();
+        drop(stream);
+
+        let (request, lines) = caster.join().unwrap();
+        assert!(
+            request.starts_with("GET /RTCM3 HTTP/1.0\r\n"),
+            "{}",
+            request
+        );
+        assert!(request.contains("Authorization: Basic dXNlcjpzZWNyZXQ=\r\n"));
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].starts_with("$GPGGA,"));
+    }
+
+    #[test]
+    fn mock_caster_refused() {
+        let (port, caster) = caster(b"HTTP/1.1 401 Unauthorized\r\n\r\n");
+
+        let result = connect(&settings(port, Version::V2), Duration::from_secs(5));
+        assert!(matches!(result, Err(Error::Unauthorized)));
+
+        let (request, _) = caster.join().unwrap();
+        assert!(request.contains("Ntrip-Version: Ntrip/2.0\r\n"));
+    }
+}
@@ -0,0 +1,421 @@
+use std::io::Write;
+use std::net::{Shutdown, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use actix::prelude::*;
+use actix_broker::{BrokerIssue, BrokerSubscribe};
+use imc::DevDataBinary;
+use imc::GpsFix;
+
+use crate::drivers::gps::quality::SolutionStatus;
+use crate::drivers::io;
+use crate::drivers::io::reader::{Closed, Received};
+use crate::drivers::io::Backoff;
+use crate::drivers::nmea_out::encode;
+use crate::drivers::ntrip::client::{Dechunker, Settings, Version};
+use crate::ix::Parameter;
+use crate::task;
+use crate::BrokerType;
+use crate::MessageWrapper;
+use crate::TaskBehaviour;
+
+pub mod client;
+
+/// Read timeout of the established connection
+const READ_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Default)]
+pub struct Configuration {
+    pub host: Parameter<String>,
+    pub port: Parameter<u16>,
+    pub mountpoint: Parameter<String>,
+    pub username: Parameter<String>,
+    pub password: Parameter<String>,
+    pub version: Parameter<Version>,
+    pub gga_interval: Parameter<u64>,
+    pub timeout: Parameter<u64>,
+    pub reconnect_delay: Parameter<u64>,
+    pub reconnect_max_delay: Parameter<u64>,
+}
+
+pub struct Task {
+    pub ctx: task::Context,
+    /// Connection to the caster
+    stream: Option<TcpStream>,
+    /// Cleared when the connection is dropped, ending its reader
+    current: Arc<AtomicBool>,
+    /// Set for NTRIP 2 streams using chunked transfer encoding
+    dechunker: Option<Dechunker>,
+    /// Last fix with a valid position, sent upstream
+    fix: Option<GpsFix>,
+    /// Geoid separation of the last fix
+    separation: Option<f32>,
+    /// When the position was last sent upstream
+    last_gga: Option<Instant>,
+    /// Correction bytes published
+    received: u64,
+    backoff: Backoff,
+    cfg: Configuration,
+}
+
+impl TaskBehaviour for Task {
+    fn get_ctx(&self) -> &task::Context {
+        &self.ctx
+    }
+
+    fn get_name(&self) -> &str {
+        "NTRIP Client"
+    }
+
+    fn register_configuration(&mut self) {
+        self.cfg
+            .host
+            .name("Caster Host")
+            .default(String::new())
+            .description("Host name or address of the NTRIP caster. Empty disables the client");
+
+        self.cfg
+            .port
+            .name("Caster Port")
+            .default(2101)
+            .description("TCP port of the NTRIP caster");
+
+        self.cfg
+            .mountpoint
+            .name("Mountpoint")
+            .default(String::new())
+            .description("Stream of corrections to request");
+
+        self.cfg
+            .username
+            .name("Username")
+            .default(String::new())
+            .description("Empty when the caster needs no credentials");
+
+        self.cfg
+            .password
+            .name("Password")
+            .default(String::new())
+            .description("Password of the user");
+
+        self.cfg
+            .version
+            .name("Protocol Version")
+            .default(Version::V2)
+            .description("NTRIP revision spoken to the caster, V1 or V2");
+
+        self.cfg
+            .gga_interval
+            .name("GGA Interval")
+            .default(10)
+            .description(
+            "In seconds, period of the position sent to the caster, e.g. for VRS. 0 disables it",
+        );
+
+        self.cfg
+            .timeout
+            .name("Timeout")
+            .default(10000)
+            .description("In milliseconds, for connecting and for the caster to answer, and without corrections before reconnecting");
+
+        self.cfg
+            .reconnect_delay
+            .name("Reconnect Delay")
+            .default(1000)
+            .description(
+                "In milliseconds, delay before reconnecting. Doubled on every failed attempt",
+            );
+
+        self.cfg
+            .reconnect_max_delay
+            .name("Maximum Reconnect Delay")
+            .default(60000)
+            .description("In milliseconds, upper bound of the reconnect delay");
+    }
+}
+
+impl Task {
+    pub fn new(context: task::Context) -> Task {
+        Task {
+            ctx: context,
+            stream: None,
+            current: Arc::new(AtomicBool::new(false)),
+            dechunker: None,
+            fix: None,
+            separation: None,
+            last_gga: None,
+            received: 0,
+            backoff: Backoff::new(Duration::from_secs(1), Duration::from_secs(60)),
+            cfg: Default::default(),
+        }
+    }
+
+    fn settings(&self) -> Settings {
+        Settings {
+            host: self.cfg.host.get().clone(),
+            port: *self.cfg.port.get(),
+            mountpoint: self.cfg.mountpoint.get().clone(),
+            username: self.cfg.username.get().clone(),
+            password: self.cfg.password.get().clone(),
+            version: *self.cfg.version.get(),
+        }
+    }
+
+    fn on_main(&mut self, _context: &mut Context<Self>) {
+        let interval = Duration::from_secs(*self.cfg.gga_interval.get());
+        let due = match self.last_gga {
+            Some(last) => last.elapsed() >= interval,
+            None => true,
+        };
+
+        if !interval.is_zero() && due {
+            self.send_gga();
+        }
+    }
+
+    /// Keep the last fix with a valid position
+    pub fn handle_fix(&mut self, fix: GpsFix) {
+        let valid = imc::messages::GpsFix::ValidityBits::GFV_VALID_POS as u16;
+        if fix._validity & valid != 0 {
+            self.fix = Some(fix);
+        }
+    }
+
+    /// Keep the geoid separation, published before the fix it belongs to
+    pub fn handle_status(&mut self, status: &SolutionStatus) {
+        self.separation = status.separation;
+    }
+
+    /// Sentence reporting the last known position to the caster, if any
+    pub fn gga(&self) -> Option<String> {
+        let fix = self.fix.as_ref()?;
+        Some(encode::gga(fix, self.separation, "GP"))
+    }
+
+    /// Send the last known position to the caster, if any
+    fn send_gga(&mut self) {
+        let gga = self.gga();
+        let (stream, gga) = match (self.stream.as_mut(), gga) {
+            (Some(stream), Some(gga)) => (stream, gga),
+            _ => return,
+        };
+
+        match stream.write_all(gga.as_bytes()) {
+            Ok(()) => self.last_gga = Some(Instant::now()),
+            // reported by the reader as well
+            Err(e) => println!("ERROR: {}: GGA: {}", self.cfg.host.get(), e),
+        }
+    }
+
+    /// Decode the bytes received from the caster into the corrections they
+    /// carry
+    fn decode(&mut self, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self.dechunker.as_mut() {
+            Some(dechunker) => dechunker.push(data),
+            None => Ok(data.to_vec()),
+        }
+    }
+
+    /// Publish corrections, e.g. for the GPS task to forward them to the
+    /// receiver
+    fn publish(&mut self, data: Vec<u8>) {
+        if data.is_empty() {
+            return;
+        }
+
+        self.received += data.len() as u64;
+
+        let mut msg = DevDataBinary::new();
+        msg._value = data;
+        send_message!(self, imc::DevDataBinary, msg);
+    }
+
+    /// Request the stream from the caster and start reading it. Failures are
+    /// retried with backoff
+    fn connect(&mut self, ctx: &mut Context<Self>) {
+        let settings = self.settings();
+        println!(
+            "connecting to {}:{}/{}",
+            settings.host, settings.port, settings.mountpoint
+        );
+
+        let timeout = Duration::from_millis(*self.cfg.timeout.get());
+        let connected = client::connect(&settings, timeout).and_then(|(stream, handshake)| {
+            // short reads, for the reader to notice when it is stopped
+            stream.set_read_timeout(Some(READ_TIMEOUT))?;
+            let reader = stream.try_clone()?;
+            Ok((stream, reader, handshake))
+        });
+
+        let (stream, reader, handshake) = match connected {
+            Ok(connection) => connection,
+            Err(e) => {
+                self.reconnect(ctx, &format!("{}", e));
+                return;
+            }
+        };
+
+        self.stream = Some(stream);
+        self.dechunker = if handshake.chunked {
+            Some(Dechunker::new())
+        } else {
+            None
+        };
+
+        // casters serving virtual reference stations wait for a position
+        self.last_gga = None;
+        self.send_gga();
+
+        match self.decode(&handshake.data) {
+            Ok(data) => self.publish(data),
+            Err(e) => {
+                self.reconnect(ctx, &format!("{}", e));
+                return;
+            }
+        }
+
+        // a silent caster times out the reader, as a closed connection
+        let current = Arc::new(AtomicBool::new(true));
+        self.current = Arc::clone(&current);
+        let forwarding = Arc::clone(&current);
+        let addr = ctx.address();
+        let closed = ctx.address();
+        io::reader::spawn(
+            &settings.host,
+            Silence::new(reader, timeout),
+            Arc::clone(&self.ctx.running),
+            move |data| {
+                if !forwarding.load(Ordering::Relaxed) {
+                    return false;
+                }
+                addr.do_send(Received(data));
+                addr.connected()
+            },
+            move |e| {
+                if current.load(Ordering::Relaxed) {
+                    closed.do_send(Closed(e));
+                }
+            },
+        );
+
+        self.backoff.reset();
+        println!("{}: streaming", settings.mountpoint);
+    }
+
+    /// Drop the connection and schedule a new one
+    fn reconnect(&mut self, ctx: &mut Context<Self>, reason: &str) {
+        println!("ERROR: {}: {}", self.cfg.host.get(), reason);
+        self.current.store(false, Ordering::Relaxed);
+        if let Some(stream) = self.stream.take() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        self.dechunker = None;
+
+        if !self.is_running() {
+            return;
+        }
+
+        let delay = self.backoff.next();
+        println!("{}: reconnecting in {:?}", self.cfg.host.get(), delay);
+        ctx.run_later(delay, |task, ctx| task.connect(ctx));
+    }
+}
+
+/// Reader failing once no data arrives within the timeout, which the reader
+/// thread would otherwise wait out forever
+struct Silence<R> {
+    io: R,
+    timeout: Duration,
+    last: Instant,
+}
+
+impl<R> Silence<R> {
+    fn new(io: R, timeout: Duration) -> Silence<R> {
+        Silence {
+            io,
+            timeout,
+            last: Instant::now(),
+        }
+    }
+}
+
+impl<R: std::io::Read> std::io::Read for Silence<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self.io.read(buf) {
+            Ok(n) => {
+                self.last = Instant::now();
+                Ok(n)
+            }
+            // timeouts are not errors to the reader thread
+            Err(e) if io::reader::timed_out(&e) && self.last.elapsed() > self.timeout => {
+                Err(std::io::Error::other("no data from caster"))
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl Actor for Task {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        if self.cfg.host.get().is_empty() {
+            println!("{}: no caster configured", self.get_name());
+            return;
+        }
+
+        self.backoff = Backoff::new(
+            Duration::from_millis(*self.cfg.reconnect_delay.get()),
+            Duration::from_millis(*self.cfg.reconnect_max_delay.get()),
+        );
+
+        subscribe_to!(SolutionStatus, self, ctx);
+        subscribe_to!(imc::GpsFix, self, ctx);
+
+        self.connect(ctx);
+
+        start_main_loop!(1000, ctx);
+    }
+
+    fn stopped(&mut self, _ctx: &mut Self::Context) {
+        println!("{} bytes of corrections received", self.received);
+        println!("{} stopped", self.get_name());
+    }
+}
+
+impl Handler<MessageWrapper<SolutionStatus>> for Task {
+    type Result = ();
+
+    fn handle(&mut self, msg: MessageWrapper<SolutionStatus>, _ctx: &mut Self::Context) {
+        self.handle_status(&msg.0);
+    }
+}
+
+impl Handler<MessageWrapper<imc::GpsFix>> for Task {
+    type Result = ();
+
+    fn handle(&mut self, msg: MessageWrapper<imc::GpsFix>, _ctx: &mut Self::Context) {
+        self.handle_fix(msg.0);
+    }
+}
+
+impl Handler<Received> for Task {
+    type Result = ();
+
+    fn handle(&mut self, msg: Received, ctx: &mut Self::Context) {
+        match self.decode(&msg.0) {
+            Ok(data) => self.publish(data),
+            Err(e) => self.reconnect(ctx, &format!("{}", e)),
+        }
+    }
+}
+
+impl Handler<Closed> for Task {
+    type Result = ();
+
+    fn handle(&mut self, msg: Closed, ctx: &mut Self::Context) {
+        self.reconnect(ctx, &format!("connection lost: {}", msg.0));
+    }
+}
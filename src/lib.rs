@@ -0,0 +1,12 @@
+use actix_broker::SystemBroker;
+
+pub use task::MessageWrapper;
+pub use task::TaskBehaviour;
+
+#[macro_use]
+pub mod task;
+pub mod drivers;
+
+pub mod ix;
+
+pub type BrokerType = SystemBroker;
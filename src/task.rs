@@ -53,3 +53,19 @@ pub trait TaskBehaviour {
         self.get_ctx().running.load(Ordering::Relaxed)
     }
 }
+
+/// Entity state of a task, with a human readable description
+pub fn entity_state(state: u8, description: &str) -> imc::EntityState {
+    let mut msg = imc::EntityState::new();
+    msg._state = state;
+    msg._description = String::from(description);
+    msg
+}
+
+/// Status text of a task, e.g. statistics or details the other IMC messages
+/// cannot carry
+pub fn status_text(text: String) -> imc::DevDataText {
+    let mut msg = imc::DevDataText::new();
+    msg._value = text;
+    msg
+}